@@ -242,6 +242,7 @@ pub struct Global {
     pub data_type: Type,
     pub initializer: Option<Arc<Function>>,
     pub is_pub: bool,
+    pub is_thread_local: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -743,6 +744,7 @@ pub enum Modifier {
     Test,
     Cannon,
     OptimizeImmediately,
+    ThreadLocal,
 }
 
 impl Modifier {
@@ -758,6 +760,7 @@ impl Modifier {
             "test" => Some(Modifier::Test),
             "cannon" => Some(Modifier::Cannon),
             "optimizeImmediately" => Some(Modifier::OptimizeImmediately),
+            "threadLocal" => Some(Modifier::ThreadLocal),
             _ => None,
         }
     }
@@ -774,6 +777,7 @@ impl Modifier {
             Modifier::Test => "test",
             Modifier::Cannon => "cannon",
             Modifier::OptimizeImmediately => "optimizeImmediately",
+            Modifier::ThreadLocal => "threadLocal",
         }
     }
 }
@@ -1446,13 +1450,20 @@ impl Expr {
         })
     }
 
-    pub fn create_template(id: NodeId, pos: Position, span: Span, parts: Vec<Box<Expr>>) -> Expr {
+    pub fn create_template(
+        id: NodeId,
+        pos: Position,
+        span: Span,
+        parts: Vec<Box<Expr>>,
+        specs: Vec<Option<FormatSpec>>,
+    ) -> Expr {
         Expr::Template(ExprTemplateType {
             id,
             pos,
             span,
 
             parts,
+            specs,
         })
     }
 
@@ -2117,6 +2128,150 @@ pub struct ExprTemplateType {
     pub span: Span,
 
     pub parts: Vec<Box<Expr>>,
+    // format specifier of each part, always None for string literals
+    pub specs: Vec<Option<FormatSpec>>,
+}
+
+// Format specifier like `>8`, `.3` or `08x` in `${value:spec}`, with the
+// syntax `[[fill]align][+][0][width][.precision][kind]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatSpec {
+    pub value: String,
+
+    pub fill: char,
+    pub align: Option<FormatAlign>,
+    pub sign: bool,
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    pub kind: FormatKind,
+}
+
+impl FormatSpec {
+    pub fn parse(value: &str) -> Option<FormatSpec> {
+        let chars: Vec<char> = value.chars().collect();
+        let mut idx = 0;
+
+        let mut spec = FormatSpec {
+            value: value.into(),
+
+            fill: ' ',
+            align: None,
+            sign: false,
+            zero: false,
+            width: None,
+            precision: None,
+            kind: FormatKind::Display,
+        };
+
+        if let Some(align) = chars.get(1).and_then(|&ch| FormatAlign::from_char(ch)) {
+            spec.fill = chars[0];
+            spec.align = Some(align);
+            idx = 2;
+        } else if let Some(align) = chars.get(0).and_then(|&ch| FormatAlign::from_char(ch)) {
+            spec.align = Some(align);
+            idx = 1;
+        }
+
+        if chars.get(idx) == Some(&'+') {
+            spec.sign = true;
+            idx += 1;
+        }
+
+        if chars.get(idx) == Some(&'0') {
+            spec.zero = true;
+            idx += 1;
+        }
+
+        spec.width = read_format_number(&chars, &mut idx)?;
+
+        if chars.get(idx) == Some(&'.') {
+            idx += 1;
+            spec.precision = Some(read_format_number(&chars, &mut idx)??);
+        }
+
+        if let Some(&ch) = chars.get(idx) {
+            spec.kind = FormatKind::from_char(ch)?;
+            idx += 1;
+        }
+
+        if idx == chars.len() {
+            Some(spec)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.sign || self.zero || self.precision.is_some() || self.kind != FormatKind::Display
+    }
+}
+
+// Returns None for numbers that are too large.
+fn read_format_number(chars: &[char], idx: &mut usize) -> Option<Option<usize>> {
+    let start = *idx;
+
+    while *idx < chars.len() && chars[*idx].is_ascii_digit() {
+        *idx += 1;
+    }
+
+    if start == *idx {
+        return Some(None);
+    }
+
+    let value: String = chars[start..*idx].iter().collect();
+    value.parse::<u16>().ok().map(|value| Some(value as usize))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl FormatAlign {
+    fn from_char(ch: char) -> Option<FormatAlign> {
+        match ch {
+            '<' => Some(FormatAlign::Left),
+            '^' => Some(FormatAlign::Center),
+            '>' => Some(FormatAlign::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FormatKind {
+    Display,
+    Binary,
+    Octal,
+    LowerHex,
+    UpperHex,
+    LowerExp,
+}
+
+impl FormatKind {
+    fn from_char(ch: char) -> Option<FormatKind> {
+        match ch {
+            'b' => Some(FormatKind::Binary),
+            'o' => Some(FormatKind::Octal),
+            'x' => Some(FormatKind::LowerHex),
+            'X' => Some(FormatKind::UpperHex),
+            'e' => Some(FormatKind::LowerExp),
+            _ => None,
+        }
+    }
+
+    pub fn is_radix(self) -> bool {
+        match self {
+            FormatKind::Binary
+            | FormatKind::Octal
+            | FormatKind::LowerHex
+            | FormatKind::UpperHex => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -648,8 +648,12 @@ impl<'a> AstDumper<'a> {
     fn dump_expr_template(&mut self, tmpl: &ExprTemplateType) {
         dump!(self, "template @ {} {}", tmpl.pos, tmpl.id);
         self.indent(|d| {
-            for part in &tmpl.parts {
-                d.dump_expr(part)
+            for (part, spec) in tmpl.parts.iter().zip(&tmpl.specs) {
+                d.dump_expr(part);
+
+                if let Some(ref spec) = spec {
+                    dump!(d, "format spec {:?}", spec.value);
+                }
             }
         });
     }
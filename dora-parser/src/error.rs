@@ -20,6 +20,7 @@ pub enum ParseError {
     ExpectedFactor(String),
     NumberOverflow,
     UnclosedStringTemplate,
+    InvalidFormatSpec(String),
     ExpectedIdentifier(String),
 }
 
@@ -53,6 +54,9 @@ impl ParseError {
             ParseError::MisplacedElse => "misplace else.".into(),
             ParseError::ExpectedFactor(ref got) => format!("factor expected but got {}.", got),
             ParseError::UnclosedStringTemplate => "unclosed string template.".into(),
+            ParseError::InvalidFormatSpec(ref spec) => {
+                format!("invalid format specifier `{}`.", spec)
+            }
             ParseError::ExpectedIdentifier(ref tok) => {
                 format!("identifier expected but got {}.", tok)
             }
@@ -226,6 +226,32 @@ impl Lexer {
         self.read_string(false)
     }
 
+    // Reads the format specifier after the `:` in `${expr:spec}` including
+    // the closing brace.
+    pub fn read_format_spec(&mut self) -> Result<String, ParseErrorAndPos> {
+        let pos = self.reader.pos();
+        let mut value = String::new();
+
+        while let Some(ch) = self.curr() {
+            if ch == '}' {
+                self.read_char();
+                return Ok(value);
+            }
+
+            if is_quote(Some(ch)) || is_newline(Some(ch)) {
+                break;
+            }
+
+            value.push(ch);
+            self.read_char();
+        }
+
+        Err(ParseErrorAndPos::new(
+            pos,
+            ParseError::UnclosedStringTemplate,
+        ))
+    }
+
     fn read_operator(&mut self) -> Result<Token, ParseErrorAndPos> {
         let pos = self.reader.pos();
         let idx = self.reader.idx();
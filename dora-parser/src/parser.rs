@@ -155,7 +155,7 @@ impl<'a> Parser<'a> {
             }
 
             TokenKind::Let | TokenKind::Var => {
-                self.restrict_modifiers(&modifiers, &[Modifier::Pub])?;
+                self.restrict_modifiers(&modifiers, &[Modifier::Pub, Modifier::ThreadLocal])?;
                 let global = self.parse_global(&modifiers)?;
                 Ok(Elem::Global(Arc::new(global)))
             }
@@ -426,6 +426,7 @@ impl<'a> Parser<'a> {
             mutable: mutable,
             initializer: None,
             is_pub: modifiers.contains(Modifier::Pub),
+            is_thread_local: modifiers.contains(Modifier::ThreadLocal),
         };
 
         if let Some(expr) = expr {
@@ -954,6 +955,7 @@ impl<'a> Parser<'a> {
                 "test" => Modifier::Test,
                 "cannon" => Modifier::Cannon,
                 "optimizeImmediately" => Modifier::OptimizeImmediately,
+                "threadLocal" => Modifier::ThreadLocal,
                 annotation => {
                     return Err(ParseErrorAndPos::new(
                         self.token.position,
@@ -2123,18 +2125,36 @@ impl<'a> Parser<'a> {
             TokenKind::StringExpr(value) => {
                 let start = self.token.span.start();
                 let mut parts: Vec<Box<Expr>> = Vec::new();
+                let mut specs: Vec<Option<FormatSpec>> = Vec::new();
                 parts.push(Box::new(Expr::create_lit_str(
                     self.generate_id(),
                     string.position,
                     span,
                     value,
                 )));
+                specs.push(None);
 
                 loop {
                     let expr = self.parse_expression()?;
                     parts.push(expr);
 
-                    if !self.token.is(TokenKind::RBrace) {
+                    if self.token.is(TokenKind::Colon) {
+                        // the lexer already consumed the closing brace
+                        let pos = self.token.position;
+                        let value = self.lexer.read_format_spec()?;
+
+                        match FormatSpec::parse(&value) {
+                            Some(spec) => specs.push(Some(spec)),
+                            None => {
+                                return Err(ParseErrorAndPos::new(
+                                    pos,
+                                    ParseError::InvalidFormatSpec(value),
+                                ));
+                            }
+                        }
+                    } else if self.token.is(TokenKind::RBrace) {
+                        specs.push(None);
+                    } else {
                         return Err(ParseErrorAndPos::new(
                             self.token.position,
                             ParseError::UnclosedStringTemplate,
@@ -2159,6 +2179,7 @@ impl<'a> Parser<'a> {
                         span,
                         value,
                     )));
+                    specs.push(None);
 
                     self.advance_token()?;
 
@@ -2174,6 +2195,7 @@ impl<'a> Parser<'a> {
                     string.position,
                     span,
                     parts,
+                    specs,
                 )))
             }
 
@@ -3771,6 +3793,54 @@ mod tests {
         assert!(expr.is_lit_str());
     }
 
+    #[test]
+    fn parse_template_with_format_spec() {
+        let (expr, _) = parse_expr("\"a${1:>8}b${2}c${x:*^+08.3e}\"");
+        let tmpl = expr.to_template().unwrap();
+        assert_eq!(tmpl.parts.len(), 7);
+        assert_eq!(tmpl.specs.len(), 7);
+
+        assert!(tmpl.specs[0].is_none());
+        let spec = tmpl.specs[1].as_ref().unwrap();
+        assert_eq!(">8", spec.value);
+        assert_eq!(Some(FormatAlign::Right), spec.align);
+        assert_eq!(Some(8), spec.width);
+        assert!(!spec.is_numeric());
+        assert!(tmpl.specs[3].is_none());
+
+        let spec = tmpl.specs[5].as_ref().unwrap();
+        assert_eq!('*', spec.fill);
+        assert_eq!(Some(FormatAlign::Center), spec.align);
+        assert!(spec.sign);
+        assert!(spec.zero);
+        assert_eq!(Some(8), spec.width);
+        assert_eq!(Some(3), spec.precision);
+        assert_eq!(FormatKind::LowerExp, spec.kind);
+        assert_eq!("c".to_string(), tmpl.parts[4].to_lit_str().unwrap().value);
+
+        let (expr, _) = parse_expr("\"${n:08x}${n:b}\"");
+        let tmpl = expr.to_template().unwrap();
+        let spec = tmpl.specs[1].as_ref().unwrap();
+        assert!(spec.zero);
+        assert_eq!(Some(8), spec.width);
+        assert_eq!(FormatKind::LowerHex, spec.kind);
+        assert_eq!(FormatKind::Binary, tmpl.specs[3].as_ref().unwrap().kind);
+
+        err_expr(
+            "\"${1:8y}\"",
+            ParseError::InvalidFormatSpec("8y".into()),
+            1,
+            5,
+        );
+        err_expr(
+            "\"${1:.}\"",
+            ParseError::InvalidFormatSpec(".".into()),
+            1,
+            5,
+        );
+        err_expr("\"${1:8\"", ParseError::UnclosedStringTemplate, 1, 6);
+    }
+
     #[test]
     fn parse_class_type_params() {
         let (prog, interner) = parse("class Foo[T]");
@@ -3885,6 +3955,17 @@ mod tests {
 
         assert_eq!("b", *interner.str(global.name));
         assert_eq!(false, global.mutable);
+        assert_eq!(false, global.is_thread_local);
+    }
+
+    #[test]
+    fn parse_global_thread_local() {
+        let (prog, interner) = parse("@threadLocal var c: int = 0;");
+        let global = prog.global0();
+
+        assert_eq!("c", *interner.str(global.name));
+        assert_eq!(true, global.mutable);
+        assert_eq!(true, global.is_thread_local);
     }
 
     #[test]
@@ -205,6 +205,51 @@ impl Assembler {
         self.emit_u32(imm.uint32());
     }
 
+    pub fn lock_cmpxchgl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_lock();
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x0f);
+        self.emit_u8(0xb1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_cmpxchgq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_lock();
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x0f);
+        self.emit_u8(0xb1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_xaddl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_lock();
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x0f);
+        self.emit_u8(0xc1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn lock_xaddq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_lock();
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x0f);
+        self.emit_u8(0xc1);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    // xchg with a memory operand is always locked, no prefix needed
+    pub fn xchgl_ar(&mut self, dest: Address, src: Register) {
+        self.emit_rex32_modrm_address(src, dest);
+        self.emit_u8(0x87);
+        self.emit_address(src.low_bits(), dest);
+    }
+
+    pub fn xchgq_ar(&mut self, dest: Address, src: Register) {
+        self.emit_rex64_modrm_address(src, dest);
+        self.emit_u8(0x87);
+        self.emit_address(src.low_bits(), dest);
+    }
+
     pub fn movl_rr(&mut self, dest: Register, src: Register) {
         self.emit_rex32_optional(src, dest);
         self.emit_u8(0x89);
@@ -511,6 +556,26 @@ impl Assembler {
         self.emit_modrm_sse_registers(dest, src);
     }
 
+    pub fn roundss_ri(&mut self, dest: XmmRegister, src: XmmRegister, mode: u8) {
+        self.emit_u8(0x66);
+        self.emit_rex_sse_modrm_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x3a);
+        self.emit_u8(0x0a);
+        self.emit_modrm_sse_registers(dest, src);
+        self.emit_u8(mode);
+    }
+
+    pub fn roundsd_ri(&mut self, dest: XmmRegister, src: XmmRegister, mode: u8) {
+        self.emit_u8(0x66);
+        self.emit_rex_sse_modrm_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x3a);
+        self.emit_u8(0x0b);
+        self.emit_modrm_sse_registers(dest, src);
+        self.emit_u8(mode);
+    }
+
     pub fn pxor_rr(&mut self, dest: XmmRegister, src: XmmRegister) {
         self.emit_u8(0x66);
         self.emit_rex_sse_modrm_optional(dest, src);
@@ -561,6 +626,21 @@ impl Assembler {
         self.emit_modrm_sse_registers(dest, src);
     }
 
+    pub fn andps_ra(&mut self, dest: XmmRegister, src: Address) {
+        self.emit_rex_sse_address_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x54);
+        self.emit_address(dest.low_bits(), src);
+    }
+
+    pub fn andpd_ra(&mut self, dest: XmmRegister, src: Address) {
+        self.emit_u8(0x66);
+        self.emit_rex_sse_address_optional(dest, src);
+        self.emit_u8(0x0f);
+        self.emit_u8(0x54);
+        self.emit_address(dest.low_bits(), src);
+    }
+
     pub fn xorpd_ra(&mut self, dest: XmmRegister, src: Address) {
         self.emit_u8(0x66);
         self.emit_rex_sse_address_optional(dest, src);
@@ -1103,6 +1183,10 @@ impl Assembler {
         self.emit_modrm_opcode(0b001, opnd);
     }
 
+    fn emit_lock(&mut self) {
+        self.emit_u8(0xf0);
+    }
+
     fn emit_rex_sse_modrm_optional(&mut self, reg: XmmRegister, rm: XmmRegister) {
         if reg.needs_rex() || rm.needs_rex() {
             self.emit_rex(false, reg.needs_rex(), false, rm.needs_rex());
@@ -2249,6 +2333,20 @@ mod tests {
         assert_emit!(0x66, 0x41, 0x0f, 0x2e, 0xe0; ucomisd_rr(XMM4, XMM8));
     }
 
+    #[test]
+    fn test_roundss_ri() {
+        assert_emit!(0x66, 0x0f, 0x3a, 0x0a, 0xc8, 0x01; roundss_ri(XMM1, XMM0, 1));
+        assert_emit!(0x66, 0x44, 0x0f, 0x3a, 0x0a, 0xfb, 0x02; roundss_ri(XMM15, XMM3, 2));
+        assert_emit!(0x66, 0x41, 0x0f, 0x3a, 0x0a, 0xe0, 0x03; roundss_ri(XMM4, XMM8, 3));
+    }
+
+    #[test]
+    fn test_roundsd_ri() {
+        assert_emit!(0x66, 0x0f, 0x3a, 0x0b, 0xc8, 0x01; roundsd_ri(XMM1, XMM0, 1));
+        assert_emit!(0x66, 0x44, 0x0f, 0x3a, 0x0b, 0xfb, 0x02; roundsd_ri(XMM15, XMM3, 2));
+        assert_emit!(0x66, 0x41, 0x0f, 0x3a, 0x0b, 0xe0, 0x03; roundsd_ri(XMM4, XMM8, 3));
+    }
+
     #[test]
     fn test_pxor_rr() {
         assert_emit!(0x66, 0x0f, 0xef, 0xc8; pxor_rr(XMM1, XMM0));
@@ -2521,6 +2619,18 @@ mod tests {
         assert_emit!(0x41, 0x0f, 0x57, 0xf8; xorps_rr(XMM7, XMM8));
     }
 
+    #[test]
+    fn test_andps_ra() {
+        assert_emit!(0x0f, 0x54, 0x05, 1, 0, 0, 0; andps_ra(XMM0, Address::rip(1)));
+        assert_emit!(0x44, 0x0f, 0x54, 0x3d, 1, 0, 0, 0; andps_ra(XMM15, Address::rip(1)));
+    }
+
+    #[test]
+    fn test_andpd_ra() {
+        assert_emit!(0x66, 0x0f, 0x54, 0x05, 1, 0, 0, 0; andpd_ra(XMM0, Address::rip(1)));
+        assert_emit!(0x66, 0x44, 0x0f, 0x54, 0x3d, 1, 0, 0, 0; andpd_ra(XMM15, Address::rip(1)));
+    }
+
     #[test]
     fn test_movl_ai() {
         assert_emit!(0xc7, 0x00, 1, 0, 0, 0; movl_ai(Address::offset(RAX, 0), Immediate(1)));
@@ -2536,4 +2646,42 @@ mod tests {
         assert_emit!(0x48, 0xc7, 0x07, 0xff, 0xff, 0xff, 0x7f; movq_ai(Address::offset(RDI, 0), Immediate(i32::max_value() as i64)));
         assert_emit!(0x49, 0xc7, 0x07, 0, 0, 0, 0x80; movq_ai(Address::offset(R15, 0), Immediate(i32::min_value() as i64)));
     }
+
+    #[test]
+    fn test_lock_cmpxchgl_ar() {
+        assert_emit!(0xf0, 0x0f, 0xb1, 0x4d, 0; lock_cmpxchgl_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0xf0, 0x44, 0x0f, 0xb1, 0x7d, 0; lock_cmpxchgl_ar(Address::offset(RBP, 0), R15));
+        assert_emit!(0xf0, 0x41, 0x0f, 0xb1, 0x48, 8; lock_cmpxchgl_ar(Address::offset(R8, 8), RCX));
+    }
+
+    #[test]
+    fn test_lock_cmpxchgq_ar() {
+        assert_emit!(0xf0, 0x48, 0x0f, 0xb1, 0x4d, 0; lock_cmpxchgq_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0xf0, 0x4c, 0x0f, 0xb1, 0x7d, 0; lock_cmpxchgq_ar(Address::offset(RBP, 0), R15));
+        assert_emit!(0xf0, 0x49, 0x0f, 0xb1, 0x48, 8; lock_cmpxchgq_ar(Address::offset(R8, 8), RCX));
+    }
+
+    #[test]
+    fn test_lock_xaddl_ar() {
+        assert_emit!(0xf0, 0x0f, 0xc1, 0x4d, 0; lock_xaddl_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0xf0, 0x44, 0x0f, 0xc1, 0x7d, 0; lock_xaddl_ar(Address::offset(RBP, 0), R15));
+    }
+
+    #[test]
+    fn test_lock_xaddq_ar() {
+        assert_emit!(0xf0, 0x48, 0x0f, 0xc1, 0x4d, 0; lock_xaddq_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0xf0, 0x4c, 0x0f, 0xc1, 0x7d, 0; lock_xaddq_ar(Address::offset(RBP, 0), R15));
+    }
+
+    #[test]
+    fn test_xchgl_ar() {
+        assert_emit!(0x87, 0x4d, 0; xchgl_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0x44, 0x87, 0x7d, 0; xchgl_ar(Address::offset(RBP, 0), R15));
+    }
+
+    #[test]
+    fn test_xchgq_ar() {
+        assert_emit!(0x48, 0x87, 0x4d, 0; xchgq_ar(Address::offset(RBP, 0), RCX));
+        assert_emit!(0x4c, 0x87, 0x7d, 0; xchgq_ar(Address::offset(RBP, 0), R15));
+    }
 }
@@ -566,6 +566,41 @@ impl<'a> AstBytecodeGen<'a> {
         }
     }
 
+    // Runtime function formatting values of the given type, None for
+    // types that are formatted by padding the result of toString().
+    fn format_fct_id(&self, ty: &SourceType) -> Option<FctId> {
+        let functions = &self.vm.known.functions;
+
+        match ty {
+            SourceType::UInt8 => Some(functions.format_uint8),
+            SourceType::Int32 => Some(functions.format_int32),
+            SourceType::Int64 => Some(functions.format_int64),
+            SourceType::Float32 => Some(functions.format_float32),
+            SourceType::Float64 => Some(functions.format_float64),
+            _ => None,
+        }
+    }
+
+    fn emit_format(
+        &mut self,
+        dest: Register,
+        value: Register,
+        spec: &FormatSpec,
+        format_fct_id: FctId,
+        pos: Position,
+    ) {
+        let spec_register = self.alloc_temp(BytecodeType::Ptr);
+        self.gen
+            .emit_const_string(spec_register, spec.value.clone());
+
+        let fct_idx = self.gen.add_const_fct(format_fct_id);
+        self.gen.emit_push_register(value);
+        self.gen.emit_push_register(spec_register);
+        self.gen.emit_invoke_static(dest, fct_idx, pos);
+
+        self.free_temp(spec_register);
+    }
+
     fn visit_expr_template(&mut self, expr: &ExprTemplateType, dest: DataDest) -> Register {
         let buffer_register = self.ensure_register(dest, BytecodeType::Ptr);
 
@@ -577,14 +612,26 @@ impl<'a> AstBytecodeGen<'a> {
 
         let part_register = self.alloc_temp(BytecodeType::Ptr);
 
-        for part in &expr.parts {
+        for (part, spec) in expr.parts.iter().zip(&expr.specs) {
             if let Some(ref lit_str) = part.to_lit_str() {
                 let value = lit_str.value.clone();
                 self.gen.emit_const_string(part_register, value);
             } else {
                 let ty = self.ty(part.id());
+                let format_fct_id = spec.as_ref().and_then(|_| self.format_fct_id(&ty));
 
-                if ty.cls_id() == Some(self.vm.known.classes.string()) {
+                if let Some(format_fct_id) = format_fct_id {
+                    // numbers are formatted by the runtime
+                    let expr_register = self.visit_expr(part, DataDest::Alloc);
+                    self.emit_format(
+                        part_register,
+                        expr_register,
+                        spec.as_ref().unwrap(),
+                        format_fct_id,
+                        part.pos(),
+                    );
+                    self.free_if_temp(expr_register);
+                } else if ty.cls_id() == Some(self.vm.known.classes.string()) {
                     self.visit_expr(part, DataDest::Reg(part_register));
                 } else if ty.is_type_param() {
                     let type_list_id = match ty {
@@ -639,6 +686,18 @@ impl<'a> AstBytecodeGen<'a> {
 
                     self.free_if_temp(expr_register);
                 }
+
+                if let (Some(spec), None) = (spec, format_fct_id) {
+                    // pad the string returned by toString()
+                    let format_fct_id = self.vm.known.functions.format_string;
+                    self.emit_format(
+                        part_register,
+                        part_register,
+                        spec,
+                        format_fct_id,
+                        part.pos(),
+                    );
+                }
             }
 
             // build StringBuffer::append() call
@@ -13,11 +13,11 @@ use crate::compiler::codegen::{
 use crate::compiler::fct::{Code, GcPoint, JitDescriptor};
 use crate::compiler::native_stub::{NativeFct, NativeFctDescriptor};
 use crate::cpu::{
-    has_lzcnt, has_popcnt, has_tzcnt, Reg, FREG_PARAMS, FREG_RESULT, FREG_TMP1, REG_PARAMS,
-    REG_RESULT, REG_SP, REG_TMP1, REG_TMP2, STACK_FRAME_ALIGNMENT,
+    has_lzcnt, has_popcnt, has_round, has_tzcnt, Reg, FREG_PARAMS, FREG_RESULT, FREG_TMP1,
+    REG_PARAMS, REG_RESULT, REG_SP, REG_TMP1, REG_TMP2, STACK_FRAME_ALIGNMENT,
 };
 use crate::gc::Address;
-use crate::masm::{CondCode, Label, Mem};
+use crate::masm::{CondCode, FloatRounding, Label, Mem};
 use crate::mem::{self, align_i32};
 use crate::object::{offset_of_array_data, Header, Str};
 use crate::semck::specialize::{
@@ -34,6 +34,10 @@ use crate::vm::{
 };
 use crate::vtable::{VTable, DISPLAY_SIZE};
 
+// Values of the variants of std::MemoryOrder.
+const MEMORY_ORDER_RELAXED: i32 = 0;
+const MEMORY_ORDER_RELEASE: i32 = 2;
+
 macro_rules! comment {
     (
         $cannon:expr,
@@ -1415,14 +1419,17 @@ impl<'a> CannonCodeGen<'a> {
                 let pos = self.bytecode.offset_position(self.current_offset.to_u32());
                 self.asm.emit_bailout(lbl_bailout, Trap::ILLEGAL, pos);
 
-                let field_id = edef.field_id(&*xenum, variant_id, element);
-                let field = &cls.fields[field_id as usize];
-
-                if field.ty.is_unit() {
+                if edef
+                    .element_ty(self.vm, &*xenum, variant_id, element)
+                    .is_unit()
+                {
                     assert_eq!(self.specialize_register_type_unit(dest), None);
                     return;
                 }
 
+                let field_id = edef.field_id(self.vm, &*xenum, variant_id, element);
+                let field = &cls.fields[field_id as usize];
+
                 let bty = BytecodeType::from_ty(self.vm, field.ty.clone());
                 assert_eq!(bty, self.specialize_register_type(dest));
 
@@ -1863,6 +1870,11 @@ impl<'a> CannonCodeGen<'a> {
                 BytecodeType::from_ty(self.vm, field.ty.clone())
             );
 
+            if self.vm.gc.needs_pre_write_barrier() {
+                let offsets = self.reference_offsets(&bytecode_type, field.offset);
+                self.asm.emit_pre_write_barrier(obj_reg, &offsets);
+            }
+
             let needs_write_barrier;
 
             match bytecode_type {
@@ -1966,9 +1978,7 @@ impl<'a> CannonCodeGen<'a> {
             self.asm.ensure_global(&*glob, fid, ptr, glob.pos, gcpoint);
         }
 
-        let disp = self.asm.add_addr(glob.address_value.to_ptr());
-        let pos = self.asm.pos() as i32;
-        self.asm.load_constpool(REG_TMP1, disp + pos);
+        self.asm.load_global_address(REG_TMP1, &*glob);
 
         let bytecode_type = self.bytecode.register_type(dest);
 
@@ -1986,10 +1996,7 @@ impl<'a> CannonCodeGen<'a> {
             BytecodeType::from_ty(self.vm, glob.ty.clone())
         );
 
-        let disp = self.asm.add_addr(glob.address_value.to_ptr());
-        let pos = self.asm.pos() as i32;
-
-        self.asm.load_constpool(REG_TMP1, disp + pos);
+        self.asm.load_global_address(REG_TMP1, &*glob);
 
         let bytecode_type = self.bytecode.register_type(src);
 
@@ -1998,9 +2005,7 @@ impl<'a> CannonCodeGen<'a> {
         self.copy_bytecode_ty(bytecode_type, dest, src);
 
         if glob.needs_initialization() {
-            let disp = self.asm.add_addr(glob.address_init.to_ptr());
-            let pos = self.asm.pos() as i32;
-            self.asm.load_constpool(REG_RESULT, disp + pos);
+            self.asm.load_global_init_address(REG_RESULT, &*glob);
             self.asm.load_int_const(MachineMode::Int8, REG_TMP1, 1);
             self.asm
                 .store_mem(MachineMode::Int8, Mem::Base(REG_RESULT, 0), REG_TMP1.into());
@@ -2291,8 +2296,14 @@ impl<'a> CannonCodeGen<'a> {
 
         let gcpoint = self.create_gcpoint();
         let position = self.bytecode.offset_position(self.current_offset.to_u32());
-        self.asm
-            .allocate(REG_RESULT.into(), alloc_size, position, false, gcpoint);
+        self.asm.allocate(
+            REG_RESULT.into(),
+            alloc_size,
+            position,
+            false,
+            class_def_id,
+            gcpoint,
+        );
 
         // store gc object in temporary storage
         self.emit_store_register(REG_RESULT.into(), dest);
@@ -2371,8 +2382,14 @@ impl<'a> CannonCodeGen<'a> {
 
         let gcpoint = self.create_gcpoint();
         let position = self.bytecode.offset_position(self.current_offset.to_u32());
-        self.asm
-            .allocate(REG_RESULT.into(), alloc_size, position, array_ref, gcpoint);
+        self.asm.allocate(
+            REG_RESULT.into(),
+            alloc_size,
+            position,
+            array_ref,
+            class_def_id,
+            gcpoint,
+        );
 
         // store gc object in temporary storage
         self.emit_store_register(REG_RESULT.into(), dest);
@@ -2534,6 +2551,7 @@ impl<'a> CannonCodeGen<'a> {
                     AllocationSize::Fixed(alloc_size),
                     position,
                     false,
+                    cls_def_id,
                     gcpoint,
                 );
 
@@ -2665,6 +2683,7 @@ impl<'a> CannonCodeGen<'a> {
             AllocationSize::Fixed(alloc_size),
             position,
             false,
+            cls_def_id,
             gcpoint,
         );
 
@@ -2786,6 +2805,20 @@ impl<'a> CannonCodeGen<'a> {
 
         let src_type = src_type.unwrap();
 
+        if self.vm.gc.needs_pre_write_barrier() {
+            let offsets = self.reference_offsets(&src_type, 0);
+
+            if !offsets.is_empty() {
+                let element_size = self.element_size(&src_type);
+                self.asm
+                    .array_address(REG_TMP1, REG_RESULT, REG_TMP1, element_size);
+                self.asm.emit_pre_write_barrier(REG_TMP1, &offsets);
+
+                self.emit_load_register(arr, REG_RESULT.into());
+                self.emit_load_register(idx, REG_TMP1.into());
+            }
+        }
+
         match src_type {
             BytecodeType::Tuple(tuple_id) => {
                 let element_size = self.vm.tuples.lock().get_tuple(tuple_id).size();
@@ -3327,6 +3360,26 @@ impl<'a> CannonCodeGen<'a> {
                 );
             }
 
+            Intrinsic::Float32Abs | Intrinsic::Float64Abs => {
+                self.emit_intrinsic_float_abs(dest, intrinsic, arguments, type_params);
+            }
+
+            Intrinsic::Float32RoundDown
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float32RoundToZero
+            | Intrinsic::Float64RoundToZero => {
+                self.emit_intrinsic_float_round(
+                    dest,
+                    fct_id,
+                    intrinsic,
+                    arguments,
+                    type_params,
+                    pos,
+                );
+            }
+
             Intrinsic::Int32CountZeroBits
             | Intrinsic::Int32CountZeroBitsLeading
             | Intrinsic::Int32CountZeroBitsTrailing
@@ -3493,6 +3546,39 @@ impl<'a> CannonCodeGen<'a> {
                 );
             }
 
+            Intrinsic::UnsafeIsNull => {
+                self.emit_intrinsic_unsafe_is_null(
+                    dest,
+                    fct_id,
+                    intrinsic,
+                    arguments,
+                    type_params,
+                    pos,
+                );
+            }
+
+            Intrinsic::WeakRefIsCleared => {
+                self.emit_intrinsic_weak_ref_is_cleared(
+                    dest,
+                    fct_id,
+                    intrinsic,
+                    arguments,
+                    type_params,
+                    pos,
+                );
+            }
+
+            Intrinsic::WeakRefKeepAlive => {
+                self.emit_intrinsic_weak_ref_keep_alive(
+                    dest,
+                    fct_id,
+                    intrinsic,
+                    arguments,
+                    type_params,
+                    pos,
+                );
+            }
+
             Intrinsic::OptionIsNone | Intrinsic::OptionIsSome => {
                 self.emit_intrinsic_option_is_none(
                     dest,
@@ -3515,6 +3601,23 @@ impl<'a> CannonCodeGen<'a> {
                 );
             }
 
+            Intrinsic::AtomicInt32Load
+            | Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt32Swap
+            | Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt32FetchAdd
+            | Intrinsic::AtomicInt64Load
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicInt64Swap
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicInt64FetchAdd
+            | Intrinsic::AtomicRefLoad
+            | Intrinsic::AtomicRefStore
+            | Intrinsic::AtomicRefSwap
+            | Intrinsic::AtomicRefCompareExchange => {
+                self.emit_intrinsic_atomic(dest, fct_id, intrinsic, arguments, type_params, pos);
+            }
+
             Intrinsic::Debug => {
                 self.asm.debug();
             }
@@ -3523,6 +3626,148 @@ impl<'a> CannonCodeGen<'a> {
         }
     }
 
+    fn emit_intrinsic_atomic(
+        &mut self,
+        dest: Option<Register>,
+        fct_id: FctId,
+        intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+        type_params: SourceTypeArray,
+        pos: Position,
+    ) {
+        let cls_id = self.vm.fcts.idx(fct_id).read().cls_id();
+
+        // All atomic classes store their value in the first field.
+        let class_def_id = specialize_class_id_params(self.vm, cls_id, &type_params);
+        let cls = self.vm.class_defs.idx(class_def_id);
+        let field = &cls.fields[0];
+
+        let mode = match intrinsic {
+            Intrinsic::AtomicInt32Load
+            | Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt32Swap
+            | Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt32FetchAdd => MachineMode::Int32,
+            Intrinsic::AtomicInt64Load
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicInt64Swap
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicInt64FetchAdd => MachineMode::Int64,
+            Intrinsic::AtomicRefLoad
+            | Intrinsic::AtomicRefStore
+            | Intrinsic::AtomicRefSwap
+            | Intrinsic::AtomicRefCompareExchange => {
+                // semck rejects AtomicRef with a value type
+                debug_assert!(field.ty.reference_type());
+                MachineMode::Ptr
+            }
+            _ => unreachable!(),
+        };
+        assert_eq!(field.ty.mode(), mode);
+
+        let is_store = match intrinsic {
+            Intrinsic::AtomicInt32Load | Intrinsic::AtomicInt64Load | Intrinsic::AtomicRefLoad => {
+                false
+            }
+            _ => true,
+        };
+
+        let needs_write_barrier = is_store && mode == MachineMode::Ptr;
+
+        let obj_reg = REG_TMP1;
+        self.emit_load_register(arguments[0], obj_reg.into());
+        self.asm.test_if_nil_bailout(pos, obj_reg, Trap::NIL);
+
+        if needs_write_barrier && self.vm.gc.needs_pre_write_barrier() {
+            self.asm.emit_pre_write_barrier(obj_reg, &[field.offset]);
+        }
+
+        let mem = Mem::Base(obj_reg, field.offset);
+
+        match intrinsic {
+            Intrinsic::AtomicInt32Load | Intrinsic::AtomicInt64Load | Intrinsic::AtomicRefLoad => {
+                let lbl_relaxed = self.asm.create_label();
+                let lbl_end = self.asm.create_label();
+
+                self.emit_load_register(arguments[1], REG_TMP2.into());
+                self.asm
+                    .cmp_reg_imm(MachineMode::Int32, REG_TMP2, MEMORY_ORDER_RELAXED);
+                self.asm.jump_if(CondCode::Equal, lbl_relaxed);
+                self.asm
+                    .atomic_load(mode, REG_RESULT, Mem::Base(obj_reg, field.offset));
+                self.asm.jump(lbl_end);
+                self.asm.bind_label(lbl_relaxed);
+                self.asm
+                    .load_mem(mode, REG_RESULT.into(), Mem::Base(obj_reg, field.offset));
+                self.asm.bind_label(lbl_end);
+            }
+
+            Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicRefStore => {
+                let lbl_relaxed = self.asm.create_label();
+                let lbl_release = self.asm.create_label();
+                let lbl_end = self.asm.create_label();
+
+                self.emit_load_register(arguments[1], REG_RESULT.into());
+                self.emit_load_register(arguments[2], REG_TMP2.into());
+                self.asm
+                    .cmp_reg_imm(MachineMode::Int32, REG_TMP2, MEMORY_ORDER_RELAXED);
+                self.asm.jump_if(CondCode::Equal, lbl_relaxed);
+                self.asm
+                    .cmp_reg_imm(MachineMode::Int32, REG_TMP2, MEMORY_ORDER_RELEASE);
+                self.asm.jump_if(CondCode::Equal, lbl_release);
+                // Acquire is not a valid order for stores and is treated
+                // like AcqRel and SeqCst.
+                self.asm
+                    .atomic_store(mode, Mem::Base(obj_reg, field.offset), REG_RESULT);
+                self.asm.jump(lbl_end);
+                self.asm.bind_label(lbl_release);
+                self.asm
+                    .atomic_store_release(mode, Mem::Base(obj_reg, field.offset), REG_RESULT);
+                self.asm.jump(lbl_end);
+                self.asm.bind_label(lbl_relaxed);
+                self.asm
+                    .store_mem(mode, Mem::Base(obj_reg, field.offset), REG_RESULT.into());
+                self.asm.bind_label(lbl_end);
+            }
+
+            // Read-modify-write operations are always sequentially
+            // consistent, which satisfies every requested order.
+            Intrinsic::AtomicInt32Swap | Intrinsic::AtomicInt64Swap | Intrinsic::AtomicRefSwap => {
+                self.emit_load_register(arguments[1], REG_RESULT.into());
+                self.asm.atomic_swap(mode, mem, REG_RESULT);
+            }
+
+            Intrinsic::AtomicInt32FetchAdd | Intrinsic::AtomicInt64FetchAdd => {
+                self.emit_load_register(arguments[1], REG_RESULT.into());
+                self.asm.atomic_fetch_add(mode, mem, REG_RESULT);
+            }
+
+            Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicRefCompareExchange => {
+                self.emit_load_register(arguments[1], REG_RESULT.into());
+                self.emit_load_register(arguments[2], REG_TMP2.into());
+                self.asm
+                    .atomic_compare_exchange(mode, mem, REG_RESULT, REG_TMP2);
+            }
+
+            _ => unreachable!(),
+        }
+
+        if let Some(dest) = dest {
+            if self.specialize_register_type_unit(dest).is_some() {
+                self.emit_store_register(REG_RESULT.into(), dest);
+            }
+        }
+
+        if needs_write_barrier && self.vm.gc.needs_write_barrier() {
+            let card_table_offset = self.vm.gc.card_table_offset();
+            self.asm.emit_barrier(obj_reg, card_table_offset);
+        }
+    }
+
     fn emit_intrinsic_count_bits(
         &mut self,
         dest: Option<Register>,
@@ -3635,6 +3880,61 @@ impl<'a> CannonCodeGen<'a> {
         self.emit_store_register(FREG_RESULT.into(), dest.expect("dest expected"));
     }
 
+    fn emit_intrinsic_float_abs(
+        &mut self,
+        dest: Option<Register>,
+        intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+        type_params: SourceTypeArray,
+    ) {
+        debug_assert_eq!(arguments.len(), 1);
+        debug_assert!(type_params.is_empty());
+
+        let mode = match intrinsic {
+            Intrinsic::Float32Abs => MachineMode::Float32,
+            Intrinsic::Float64Abs => MachineMode::Float64,
+            _ => unreachable!(),
+        };
+
+        self.emit_load_register(arguments[0], FREG_RESULT.into());
+        self.asm.float_abs(mode, FREG_RESULT, FREG_RESULT);
+        self.emit_store_register(FREG_RESULT.into(), dest.expect("dest expected"));
+    }
+
+    fn emit_intrinsic_float_round(
+        &mut self,
+        dest: Option<Register>,
+        fct_id: FctId,
+        intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+        type_params: SourceTypeArray,
+        pos: Position,
+    ) {
+        debug_assert_eq!(arguments.len(), 1);
+        debug_assert!(type_params.is_empty());
+
+        if !has_round() {
+            // falls back to the implementation in Dora
+            self.emit_invoke_direct(dest, fct_id, type_params, arguments, pos);
+            return;
+        }
+
+        let (mode, rounding) = match intrinsic {
+            Intrinsic::Float32RoundDown => (MachineMode::Float32, FloatRounding::Down),
+            Intrinsic::Float64RoundDown => (MachineMode::Float64, FloatRounding::Down),
+            Intrinsic::Float32RoundUp => (MachineMode::Float32, FloatRounding::Up),
+            Intrinsic::Float64RoundUp => (MachineMode::Float64, FloatRounding::Up),
+            Intrinsic::Float32RoundToZero => (MachineMode::Float32, FloatRounding::TowardZero),
+            Intrinsic::Float64RoundToZero => (MachineMode::Float64, FloatRounding::TowardZero),
+            _ => unreachable!(),
+        };
+
+        self.emit_load_register(arguments[0], FREG_RESULT.into());
+        self.asm
+            .float_round(mode, FREG_RESULT, FREG_RESULT, rounding);
+        self.emit_store_register(FREG_RESULT.into(), dest.expect("dest expected"));
+    }
+
     fn emit_intrinsic_option_unwrap(
         &mut self,
         dest: Option<Register>,
@@ -3757,6 +4057,11 @@ impl<'a> CannonCodeGen<'a> {
 
                 self.asm
                     .array_address(REG_TMP1, REG_RESULT, REG_TMP1, mem::ptr_width());
+
+                if self.vm.gc.needs_pre_write_barrier() {
+                    self.asm.emit_pre_write_barrier(REG_TMP1, &[0]);
+                }
+
                 self.asm
                     .store_zero(MachineMode::Ptr, Mem::Base(REG_TMP1, 0));
             }
@@ -3769,6 +4074,12 @@ impl<'a> CannonCodeGen<'a> {
                 let tuple_size = self.vm.tuples.lock().get_tuple(tuple_id).size();
                 self.asm
                     .array_address(REG_TMP1, REG_RESULT, REG_TMP1, tuple_size);
+
+                if self.vm.gc.needs_pre_write_barrier() {
+                    let offsets = self.reference_offsets(&BytecodeType::Tuple(tuple_id), 0);
+                    self.asm.emit_pre_write_barrier(REG_TMP1, &offsets);
+                }
+
                 self.zero_refs_tuple(tuple_id, RegOrOffset::Reg(REG_TMP1));
             }
 
@@ -3813,8 +4124,13 @@ impl<'a> CannonCodeGen<'a> {
             | BytecodeType::Float64
             | BytecodeType::Tuple(_)
             | BytecodeType::Enum(_, _)
-            | BytecodeType::TypeParam(_)
-            | BytecodeType::Struct(_, _) => unreachable!(),
+            | BytecodeType::Struct(_, _) => {
+                // values are never null
+                self.asm.load_false(REG_RESULT);
+                self.emit_store_register(REG_RESULT.into(), dest_reg);
+            }
+
+            BytecodeType::TypeParam(_) => unreachable!(),
 
             BytecodeType::Ptr => {
                 self.emit_load_register(arguments[0], REG_RESULT.into());
@@ -3825,6 +4141,81 @@ impl<'a> CannonCodeGen<'a> {
         }
     }
 
+    // Loads the referent without the nil check of regular field loads, the
+    // collector clears it when the referenced object died.
+    fn emit_intrinsic_weak_ref_is_cleared(
+        &mut self,
+        dest: Option<Register>,
+        _fct_id: FctId,
+        _intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+        type_params: SourceTypeArray,
+        pos: Position,
+    ) {
+        assert_eq!(1, type_params.len());
+        assert_eq!(1, arguments.len());
+
+        let dest_reg = dest.expect("missing dest");
+
+        if !type_params[0].reference_type() {
+            // values are never cleared
+            self.asm.load_false(REG_RESULT);
+            self.emit_store_register(REG_RESULT.into(), dest_reg);
+            return;
+        }
+
+        let cls_id = self.vm.known.classes.weak_ref.expect("WeakRef missing");
+        let class_def_id = specialize_class_id_params(self.vm, cls_id, &type_params);
+        let cls = self.vm.class_defs.idx(class_def_id);
+
+        // referent is always the first field of WeakRef
+        let referent = &cls.fields[0];
+
+        let obj_reg = REG_TMP1;
+        self.emit_load_register(arguments[0], obj_reg.into());
+        self.asm.test_if_nil_bailout(pos, obj_reg, Trap::NIL);
+        self.asm.load_mem(
+            MachineMode::Ptr,
+            REG_RESULT.into(),
+            Mem::Base(obj_reg, referent.offset),
+        );
+        self.asm.cmp_reg_imm(MachineMode::Ptr, REG_RESULT, 0);
+        self.asm.set(REG_RESULT, CondCode::Equal);
+        self.emit_store_register_as(REG_RESULT.into(), dest_reg, MachineMode::Int8);
+    }
+
+    // Records the referent like an overwritten reference during concurrent
+    // marking, the mutator might store it into an already traced object.
+    fn emit_intrinsic_weak_ref_keep_alive(
+        &mut self,
+        dest: Option<Register>,
+        _fct_id: FctId,
+        _intrinsic: Intrinsic,
+        arguments: Vec<Register>,
+        type_params: SourceTypeArray,
+        pos: Position,
+    ) {
+        assert_eq!(1, type_params.len());
+        assert_eq!(1, arguments.len());
+        assert!(dest.is_none());
+
+        if !type_params[0].reference_type() || !self.vm.gc.needs_pre_write_barrier() {
+            return;
+        }
+
+        let cls_id = self.vm.known.classes.weak_ref.expect("WeakRef missing");
+        let class_def_id = specialize_class_id_params(self.vm, cls_id, &type_params);
+        let cls = self.vm.class_defs.idx(class_def_id);
+
+        // referent is always the first field of WeakRef
+        let referent = &cls.fields[0];
+
+        let obj_reg = REG_TMP1;
+        self.emit_load_register(arguments[0], obj_reg.into());
+        self.asm.test_if_nil_bailout(pos, obj_reg, Trap::NIL);
+        self.asm.emit_pre_write_barrier(obj_reg, &[referent.offset]);
+    }
+
     fn emit_intrinsic_option_is_none(
         &mut self,
         dest: Option<Register>,
@@ -4056,6 +4447,65 @@ impl<'a> CannonCodeGen<'a> {
         RegOrOffset::Offset(self.register_offset(reg))
     }
 
+    // offsets of all references in a value of the given type stored at `offset`
+    fn reference_offsets(&self, ty: &BytecodeType, offset: i32) -> Vec<i32> {
+        match ty {
+            BytecodeType::Ptr => vec![offset],
+
+            BytecodeType::Tuple(tuple_id) => {
+                let tuples = self.vm.tuples.lock();
+                let tuple = tuples.get_tuple(*tuple_id);
+
+                tuple
+                    .references()
+                    .iter()
+                    .map(|&ref_offset| offset + ref_offset)
+                    .collect()
+            }
+
+            BytecodeType::Struct(struct_id, type_params) => {
+                let sdef_id = specialize_struct_id_params(self.vm, *struct_id, type_params.clone());
+                let sdef = self.vm.struct_defs.idx(sdef_id);
+
+                sdef.ref_fields
+                    .iter()
+                    .map(|&ref_offset| offset + ref_offset)
+                    .collect()
+            }
+
+            BytecodeType::Enum(_, _) => {
+                if ty.mode(self.vm) == MachineMode::Ptr {
+                    vec![offset]
+                } else {
+                    Vec::new()
+                }
+            }
+
+            BytecodeType::TypeParam(_) => unreachable!(),
+
+            BytecodeType::UInt8
+            | BytecodeType::Bool
+            | BytecodeType::Char
+            | BytecodeType::Int32
+            | BytecodeType::Int64
+            | BytecodeType::Float32
+            | BytecodeType::Float64 => Vec::new(),
+        }
+    }
+
+    fn element_size(&self, ty: &BytecodeType) -> i32 {
+        match ty {
+            BytecodeType::Tuple(tuple_id) => self.vm.tuples.lock().get_tuple(*tuple_id).size(),
+
+            BytecodeType::Struct(struct_id, type_params) => {
+                let sdef_id = specialize_struct_id_params(self.vm, *struct_id, type_params.clone());
+                self.vm.struct_defs.idx(sdef_id).size
+            }
+
+            _ => ty.mode(self.vm).size(),
+        }
+    }
+
     fn specialize_register_type(&self, reg: Register) -> BytecodeType {
         let ty = self.bytecode.register_type(reg);
         self.specialize_bytecode_type(ty)
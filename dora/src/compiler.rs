@@ -4,9 +4,11 @@ pub use crate::compiler::map::*;
 pub use crate::compiler::native_stub::*;
 
 pub mod asm;
+pub mod catch_stub;
 pub mod codegen;
 pub mod compile_stub;
 pub mod dora_stub;
 pub mod fct;
+pub mod fiber_stub;
 pub mod map;
 pub mod native_stub;
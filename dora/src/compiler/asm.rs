@@ -8,12 +8,12 @@ use crate::compiler::native_stub::{NativeFct, NativeFctDescriptor};
 use crate::cpu::{FReg, Reg, FREG_RESULT, REG_PARAMS, REG_RESULT, REG_THREAD, REG_TMP1, REG_TMP2};
 use crate::gc::tlab::TLAB_OBJECT_SIZE;
 use crate::gc::Address;
-use crate::masm::{CondCode, Label, MacroAssembler, Mem, ScratchReg};
+use crate::masm::{CondCode, FloatRounding, Label, MacroAssembler, Mem, ScratchReg};
 use crate::stdlib;
 use crate::threads::ThreadLocalData;
 use crate::ty::{MachineMode, SourceType, SourceTypeArray};
 use crate::vm::FctId;
-use crate::vm::{GlobalData, Trap, VM};
+use crate::vm::{ClassDefId, GlobalData, Trap, VM};
 
 pub struct BaselineAssembler<'a> {
     masm: MacroAssembler,
@@ -146,6 +146,36 @@ impl<'a> BaselineAssembler<'a> {
         self.masm.store_zero(mode, mem);
     }
 
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        self.masm.atomic_load(mode, dest, mem);
+    }
+
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_store(mode, mem, src);
+    }
+
+    pub fn atomic_store_release(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_store_release(mode, mem, src);
+    }
+
+    pub fn atomic_swap(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_swap(mode, mem, src);
+    }
+
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.masm.atomic_fetch_add(mode, mem, src);
+    }
+
+    pub fn atomic_compare_exchange(
+        &mut self,
+        mode: MachineMode,
+        mem: Mem,
+        expected: Reg,
+        new: Reg,
+    ) {
+        self.masm.atomic_compare_exchange(mode, mem, expected, new);
+    }
+
     pub fn lea(&mut self, dest: Reg, mem: Mem) {
         self.masm.lea(dest, mem);
     }
@@ -194,6 +224,33 @@ impl<'a> BaselineAssembler<'a> {
         self.masm.emit_barrier(src, card_table_offset);
     }
 
+    // Records the references at `base + offset` for all offsets before they are
+    // overwritten. `base` is preserved, all other registers are clobbered.
+    pub fn emit_pre_write_barrier(&mut self, base: Reg, offsets: &[i32]) {
+        if offsets.is_empty() {
+            return;
+        }
+
+        let lbl_done = self.masm.create_label();
+        self.masm.cmp_mem_imm(
+            MachineMode::Int8,
+            Mem::Base(REG_THREAD, ThreadLocalData::concurrent_marking_offset()),
+            0,
+        );
+        self.masm.jump_if(CondCode::Equal, lbl_done);
+
+        for &offset in offsets {
+            self.masm.copy_reg(MachineMode::Ptr, REG_PARAMS[0], base);
+            self.masm
+                .load_int_const(MachineMode::Ptr, REG_PARAMS[1], offset as i64);
+            self.masm
+                .raw_call(stdlib::gc_pre_write_barrier as *const u8);
+            self.masm.copy_reg(MachineMode::Ptr, base, REG_RESULT);
+        }
+
+        self.masm.bind_label(lbl_done);
+    }
+
     pub fn emit_bailout(&mut self, lbl: Label, trap: Trap, pos: Position) {
         self.masm.emit_bailout(lbl, trap, pos);
     }
@@ -398,6 +455,20 @@ impl<'a> BaselineAssembler<'a> {
         self.masm.float_sqrt(mode, dest, src);
     }
 
+    pub fn float_abs(&mut self, mode: MachineMode, dest: FReg, src: FReg) {
+        self.masm.float_abs(mode, dest, src);
+    }
+
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: FloatRounding,
+    ) {
+        self.masm.float_round(mode, dest, src, rounding);
+    }
+
     pub fn copy(&mut self, mode: MachineMode, dest: AnyReg, src: AnyReg) {
         self.masm.copy(mode, dest, src);
     }
@@ -562,6 +633,7 @@ impl<'a> BaselineAssembler<'a> {
         size: AllocationSize,
         pos: Position,
         array_ref: bool,
+        cls_def_id: ClassDefId,
         gcpoint: GcPoint,
     ) {
         match size {
@@ -581,9 +653,15 @@ impl<'a> BaselineAssembler<'a> {
             if array_ref { 1 } else { 0 },
         );
 
+        self.masm.load_int_const(
+            MachineMode::Ptr,
+            REG_PARAMS[2],
+            cls_def_id.to_usize() as i64,
+        );
+
         let internal_fct = NativeFct {
             ptr: Address::from_ptr(stdlib::gc_alloc as *const u8),
-            args: &[SourceType::Int64, SourceType::Bool],
+            args: &[SourceType::Int64, SourceType::Bool, SourceType::Int64],
             return_type: SourceType::Ptr,
             desc: NativeFctDescriptor::AllocStub,
         };
@@ -598,6 +676,7 @@ impl<'a> BaselineAssembler<'a> {
         size: AllocationSize,
         pos: Position,
         array_ref: bool,
+        cls_def_id: ClassDefId,
         gcpoint: GcPoint,
     ) {
         let lbl_slow_path = self.masm.create_label();
@@ -674,6 +753,7 @@ impl<'a> BaselineAssembler<'a> {
             size,
             pos,
             array_ref,
+            cls_def_id,
             gcpoint,
         ));
     }
@@ -684,28 +764,62 @@ impl<'a> BaselineAssembler<'a> {
         size: AllocationSize,
         pos: Position,
         array_ref: bool,
+        cls_def_id: ClassDefId,
         gcpoint: GcPoint,
     ) {
         if self.vm.args.flag_disable_tlab {
-            self.gc_allocate(dest, size, pos, array_ref, gcpoint);
+            self.gc_allocate(dest, size, pos, array_ref, cls_def_id, gcpoint);
             return;
         }
 
         match size {
             AllocationSize::Fixed(fixed_size) => {
                 if fixed_size < TLAB_OBJECT_SIZE {
-                    self.tlab_allocate(dest, size, pos, array_ref, gcpoint);
+                    self.tlab_allocate(dest, size, pos, array_ref, cls_def_id, gcpoint);
                 } else {
-                    self.gc_allocate(dest, size, pos, array_ref, gcpoint);
+                    self.gc_allocate(dest, size, pos, array_ref, cls_def_id, gcpoint);
                 }
             }
 
             AllocationSize::Dynamic(_) => {
-                self.tlab_allocate(dest, size, pos, array_ref, gcpoint);
+                self.tlab_allocate(dest, size, pos, array_ref, cls_def_id, gcpoint);
             }
         }
     }
 
+    // Loads the address of the global's value. Thread-local globals are
+    // stored in the thread-local area of the current thread.
+    pub fn load_global_address(&mut self, dest: Reg, glob: &GlobalData) {
+        if glob.is_thread_local {
+            self.load_thread_locals(dest);
+            self.masm.lea(dest, Mem::Base(dest, glob.offset_value));
+        } else {
+            let disp = self.masm.add_addr(glob.address_value.to_ptr());
+            let pos = self.masm.pos() as i32;
+            self.masm.load_constpool(dest, disp + pos);
+        }
+    }
+
+    // Loads the address of the global's initialization flag.
+    pub fn load_global_init_address(&mut self, dest: Reg, glob: &GlobalData) {
+        if glob.is_thread_local {
+            self.load_thread_locals(dest);
+            self.masm.lea(dest, Mem::Base(dest, glob.offset_init));
+        } else {
+            let disp = self.masm.add_addr(glob.address_init.to_ptr());
+            let pos = self.masm.pos() as i32;
+            self.masm.load_constpool(dest, disp + pos);
+        }
+    }
+
+    fn load_thread_locals(&mut self, dest: Reg) {
+        self.masm.load_mem(
+            MachineMode::Ptr,
+            dest.into(),
+            Mem::Base(REG_THREAD, ThreadLocalData::thread_locals_offset()),
+        );
+    }
+
     pub fn ensure_global(
         &mut self,
         glob: &GlobalData,
@@ -717,9 +831,7 @@ impl<'a> BaselineAssembler<'a> {
         let lbl_global = self.masm.create_label();
         let lbl_return = self.masm.create_label();
 
-        let disp = self.masm.add_addr(glob.address_init.to_ptr());
-        let pos = self.masm.pos() as i32;
-        self.masm.load_constpool(REG_RESULT, disp + pos);
+        self.load_global_init_address(REG_RESULT, glob);
         self.masm.load_mem(
             MachineMode::Int8,
             REG_RESULT.into(),
@@ -746,10 +858,11 @@ impl<'a> BaselineAssembler<'a> {
                     size,
                     pos,
                     array_ref,
+                    cls_def_id,
                     gcpoint,
                 ) => {
                     self.slow_path_tlab_allocation_failure(
-                        lbl_start, lbl_return, dest, size, pos, array_ref, gcpoint,
+                        lbl_start, lbl_return, dest, size, pos, array_ref, cls_def_id, gcpoint,
                     );
                 }
 
@@ -790,11 +903,12 @@ impl<'a> BaselineAssembler<'a> {
         size: AllocationSize,
         pos: Position,
         array_ref: bool,
+        cls_def_id: ClassDefId,
         gcpoint: GcPoint,
     ) {
         self.masm.bind_label(lbl_start);
         self.masm.emit_comment("slow path tlab allocation".into());
-        self.gc_allocate(dest, size, pos, array_ref, gcpoint);
+        self.gc_allocate(dest, size, pos, array_ref, cls_def_id, gcpoint);
         self.masm.jump(lbl_return);
     }
 
@@ -862,7 +976,16 @@ impl<'a> BaselineAssembler<'a> {
 }
 
 enum SlowPathKind {
-    TlabAllocationFailure(Label, Label, Reg, AllocationSize, Position, bool, GcPoint),
+    TlabAllocationFailure(
+        Label,
+        Label,
+        Reg,
+        AllocationSize,
+        Position,
+        bool,
+        ClassDefId,
+        GcPoint,
+    ),
     StackOverflow(Label, Label, Position, GcPoint),
     Safepoint(Label, Label, Position, GcPoint),
     Assert(Label, Position),
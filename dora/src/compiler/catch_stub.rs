@@ -0,0 +1,30 @@
+use crate::compiler::fct::{JitDescriptor, JitFct};
+use crate::compiler::map::CodeDescriptor;
+use crate::cpu::CCALL_REG_PARAMS;
+use crate::gc::Address;
+use crate::masm::MacroAssembler;
+use crate::vm::VM;
+
+// Generates the stub for invoking code that can be left by switching to
+// the saved stack pointer with the fiber stub:
+// extern "C" fn(save: *mut usize, fct: extern "C" fn(usize), arg: usize)
+pub fn generate(vm: &VM) -> Address {
+    let mut masm = MacroAssembler::new();
+    masm.call_saving_stack(
+        CCALL_REG_PARAMS[0],
+        CCALL_REG_PARAMS[1],
+        CCALL_REG_PARAMS[2],
+    );
+
+    let jit_fct = masm.jit(vm, 0, JitDescriptor::CatchStub);
+    let ptr = jit_fct.instruction_start();
+
+    vm.insert_code_map(
+        jit_fct.ptr_start(),
+        jit_fct.ptr_end(),
+        CodeDescriptor::CatchStub,
+    );
+    vm.jit_fcts.push(JitFct::Compiled(jit_fct));
+
+    ptr
+}
@@ -18,8 +18,8 @@ use crate::stack::DoraToNativeInfo;
 use crate::threads::ThreadLocalData;
 use crate::ty::{MachineMode, SourceType, SourceTypeArray};
 use crate::vm::{
-    find_trait_impl, get_vm, AnalysisData, ClassDefId, Fct, FctId, FctParent, TypeParam,
-    TypeParamId, VM,
+    find_trait_impl, get_vm, AnalysisData, ClassDef, ClassDefId, ClassId, Fct, FctId, FctParent,
+    TypeParam, TypeParamId, VM,
 };
 
 // This code generates the compiler stub, there should only be one instance
@@ -264,7 +264,9 @@ fn patch_virtual_call(
         let cls = cls.read();
 
         let fct_id = cls.virtual_fcts[vtable_index as usize];
-        compiler::generate(vm, fct_id, type_params)
+        let type_params =
+            specialize_virtual_fct(vm, cls_id, &cls_def, fct_id, trait_fct_id, type_params);
+        compiler::generate(vm, fct_id, &type_params)
     } else {
         let object_ty = cls_def.trait_object.clone().expect("trait object expected");
         let all_type_params = type_params.connect_single(object_ty.clone());
@@ -280,6 +282,33 @@ fn patch_virtual_call(
     fct_ptr
 }
 
+// The call site only knows the type params of the class that declares the
+// called method, the implementation needs the type params of its own class
+// which follow from the class of the receiver.
+fn specialize_virtual_fct(
+    vm: &VM,
+    cls_id: ClassId,
+    cls_def: &ClassDef,
+    fct_id: FctId,
+    callee_id: FctId,
+    type_params: &SourceTypeArray,
+) -> SourceTypeArray {
+    let impl_cls_id = vm.fcts.idx(fct_id).read().cls_id();
+    let container_type_params = vm.fcts.idx(callee_id).read().container_type_params;
+
+    let list_id = vm
+        .source_type_arrays
+        .lock()
+        .insert(cls_def.type_params.clone());
+    let impl_ty = SourceType::Class(cls_id, list_id)
+        .superclass_with_id(vm, impl_cls_id)
+        .expect("method of superclass expected");
+
+    let fct_type_params =
+        SourceTypeArray::with(type_params.types()[container_type_params..].to_vec());
+    impl_ty.type_params(vm).connect(&fct_type_params)
+}
+
 fn patch_direct_call(
     vm: &VM,
     ra: usize,
@@ -14,7 +14,7 @@ use crate::vm::VM;
 
 use dora_parser::Position;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct JitFctId(usize);
 
 impl JitFctId {
@@ -134,6 +134,8 @@ pub enum JitDescriptor {
     DoraStub,
     GuardCheckStub,
     SafepointStub,
+    FiberStub,
+    CatchStub,
 }
 
 pub struct Code {
@@ -0,0 +1,25 @@
+use crate::compiler::fct::{JitDescriptor, JitFct};
+use crate::compiler::map::CodeDescriptor;
+use crate::cpu::CCALL_REG_PARAMS;
+use crate::gc::Address;
+use crate::masm::MacroAssembler;
+use crate::vm::VM;
+
+// Generates the stub for switching between stacks:
+// extern "C" fn(save: *mut usize, sp: usize)
+pub fn generate<'a>(vm: &'a VM) -> Address {
+    let mut masm = MacroAssembler::new();
+    masm.switch_stack(CCALL_REG_PARAMS[0], CCALL_REG_PARAMS[1]);
+
+    let jit_fct = masm.jit(vm, 0, JitDescriptor::FiberStub);
+    let ptr = jit_fct.instruction_start();
+
+    vm.insert_code_map(
+        jit_fct.ptr_start(),
+        jit_fct.ptr_end(),
+        CodeDescriptor::FiberStub,
+    );
+    vm.jit_fcts.push(JitFct::Compiled(jit_fct));
+
+    ptr
+}
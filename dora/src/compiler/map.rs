@@ -44,6 +44,8 @@ impl CodeMap {
                 &CodeDescriptor::DoraStub => println!("dora_stub"),
                 &CodeDescriptor::GuardCheckStub => println!("guard_check_stub"),
                 &CodeDescriptor::SafepointStub => println!("safepoint_stub"),
+                &CodeDescriptor::FiberStub => println!("fiber_stub"),
+                &CodeDescriptor::CatchStub => println!("catch_stub"),
             }
         }
 
@@ -73,6 +75,8 @@ pub enum CodeDescriptor {
     DoraStub,
     GuardCheckStub,
     SafepointStub,
+    FiberStub,
+    CatchStub,
 }
 
 #[derive(Copy, Clone, Debug)]
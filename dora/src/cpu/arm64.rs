@@ -1,3 +1,8 @@
+use std::ptr;
+
+use crate::gc::Address;
+use crate::mem;
+
 pub use self::param::*;
 pub use self::reg::*;
 
@@ -70,6 +75,26 @@ pub fn cacheline_sizes() -> (usize, usize) {
     (insn, data)
 }
 
+// Prepares a new stack for MacroAssembler::switch_stack(): switching to
+// the returned stack pointer zeroes all callee-saved registers and returns
+// to entry.
+pub fn fiber_stack_pointer(stack_top: Address, entry: Address) -> Address {
+    debug_assert!(stack_top.to_usize() % STACK_FRAME_ALIGNMENT == 0);
+    let pairs_size = CALLEE_SAVED_PAIRS.len() * 2 * mem::ptr_width_usize();
+    let fregs_size = CALLEE_SAVED_FREGS.len() * 8;
+    let sp = stack_top.sub(pairs_size + fregs_size);
+
+    // the link register is saved in the last slot
+    let lr_addr = stack_top.sub(mem::ptr_width_usize());
+
+    unsafe {
+        ptr::write_bytes(sp.to_mut_ptr::<u8>(), 0, stack_top.offset_from(sp));
+        *lr_addr.to_mut_ptr::<usize>() = entry.to_usize();
+    }
+
+    sp
+}
+
 pub fn has_round() -> bool {
     true
 }
@@ -524,6 +524,59 @@ fn cls_ld_literal(opc: u32, v: u32, imm19: i32, rt: Reg) -> u32 {
     0b011u32 << 27 | opc << 30 | v << 26 | imm << 5 | rt.asm()
 }
 
+pub fn ldar(sf: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(0b10 | sf, 1, 1, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn stlr(sf: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(0b10 | sf, 1, 0, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn ldaxr(sf: u32, rt: Reg, rn: Reg) -> u32 {
+    cls_ldst_exclusive(0b10 | sf, 0, 1, 0, REG_ZERO, 1, rn, rt)
+}
+
+pub fn stlxr(sf: u32, rs: Reg, rt: Reg, rn: Reg) -> u32 {
+    assert!(rs.is_gpr());
+    cls_ldst_exclusive(0b10 | sf, 0, 0, 0, rs, 1, rn, rt)
+}
+
+fn cls_ldst_exclusive(
+    size: u32,
+    o2: u32,
+    l: u32,
+    o1: u32,
+    rs: Reg,
+    o0: u32,
+    rn: Reg,
+    rt: Reg,
+) -> u32 {
+    assert!(fits_u2(size));
+    assert!(fits_bit(o2));
+    assert!(fits_bit(l));
+    assert!(fits_bit(o1));
+    assert!(rs.is_gpr_or_zero());
+    assert!(fits_bit(o0));
+    assert!(rn.is_gpr_or_sp());
+    assert!(rt.is_gpr());
+
+    size << 30
+        | 0b001000u32 << 24
+        | o2 << 23
+        | l << 22
+        | o1 << 21
+        | rs.asm() << 16
+        | o0 << 15
+        | 0b11111u32 << 10
+        | rn.asm() << 5
+        | rt.asm()
+}
+
+// data memory barrier for the inner shareable domain
+pub fn dmb_ish() -> u32 {
+    0xD5033BBF
+}
+
 pub fn and_shreg(sf: u32, rd: Reg, rn: Reg, rm: Reg, shift: Shift, imm6: u32) -> u32 {
     cls_logical_shreg(sf, 0b00, shift, 0, rm, imm6, rn, rd)
 }
@@ -1028,6 +1081,22 @@ pub fn fsqrt(ty: u32, rd: FReg, rn: FReg) -> u32 {
     cls_fp_dataproc1(0, 0, ty, 0b000011, rn, rd)
 }
 
+pub fn fabs(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b000001, rn, rd)
+}
+
+pub fn frintp(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001001, rn, rd)
+}
+
+pub fn frintm(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001010, rn, rd)
+}
+
+pub fn frintz(ty: u32, rd: FReg, rn: FReg) -> u32 {
+    cls_fp_dataproc1(0, 0, ty, 0b001011, rn, rd)
+}
+
 pub fn fcvt_sd(rd: FReg, rn: FReg) -> u32 {
     cls_fp_dataproc1(0, 0, 0b00, 0b000101, rn, rd)
 }
@@ -1595,6 +1664,23 @@ mod tests {
         assert_emit!(0xf9400862; ldrx_imm(R2, R3, 2));
     }
 
+    #[test]
+    fn test_ldst_exclusive() {
+        assert_emit!(0x88dffc20; ldar(0, R0, R1));
+        assert_emit!(0xc8dffc20; ldar(1, R0, R1));
+        assert_emit!(0x889ffc20; stlr(0, R0, R1));
+        assert_emit!(0xc89ffc20; stlr(1, R0, R1));
+        assert_emit!(0x885ffc20; ldaxr(0, R0, R1));
+        assert_emit!(0xc85ffc20; ldaxr(1, R0, R1));
+        assert_emit!(0x8802fc20; stlxr(0, R2, R0, R1));
+        assert_emit!(0xc802fc20; stlxr(1, R2, R0, R1));
+    }
+
+    #[test]
+    fn test_dmb_ish() {
+        assert_emit!(0xd5033bbf; dmb_ish());
+    }
+
     #[test]
     fn test_ldr_literal() {
         // forward jump
@@ -1950,6 +2036,23 @@ mod tests {
         assert_eq!(0x93407d8f, sxtw(R15, R12));
     }
 
+    #[test]
+    fn test_fabs() {
+        assert_eq!(0x1e20c020, fabs(0, F0, F1)); // fabs s0, s1
+        assert_eq!(0x1e60c020, fabs(1, F0, F1)); // fabs d0, d1
+        assert_eq!(0x1e60c149, fabs(1, F9, F10)); // fabs d9, d10
+    }
+
+    #[test]
+    fn test_frint() {
+        assert_eq!(0x1e24c020, frintp(0, F0, F1)); // frintp s0, s1
+        assert_eq!(0x1e64c149, frintp(1, F9, F10)); // frintp d9, d10
+        assert_eq!(0x1e254020, frintm(0, F0, F1)); // frintm s0, s1
+        assert_eq!(0x1e654149, frintm(1, F9, F10)); // frintm d9, d10
+        assert_eq!(0x1e25c020, frintz(0, F0, F1)); // frintz s0, s1
+        assert_eq!(0x1e65c149, frintz(1, F9, F10)); // frintz d9, d10
+    }
+
     #[test]
     fn test_fcmp() {
         assert_eq!(0x1e212000, fcmp(0, F0, F1));
@@ -8,6 +8,18 @@ pub static CCALL_FREG_PARAMS: [FReg; 8] = [F0, F1, F2, F3, F4, F5, F6, F7];
 
 pub static SCRATCH: [Reg; 5] = [R9, R12, R13, R14, R15];
 
+// saved by MacroAssembler::switch_stack(), only the lower 64 bits of
+// v8-v15 are callee-saved
+pub static CALLEE_SAVED_PAIRS: [(Reg, Reg); 6] = [
+    (R19, R20),
+    (R21, R22),
+    (R23, R24),
+    (R25, R26),
+    (R27, R28),
+    (R29, R30),
+];
+pub static CALLEE_SAVED_FREGS: [FReg; 8] = [F8, F9, F10, F11, F12, F13, F14, F15];
+
 pub const REG_RESULT: Reg = R0;
 pub const REG_TMP1: Reg = R10;
 pub const REG_TMP2: Reg = R11;
@@ -1,5 +1,8 @@
+use std::ptr;
 use std::sync::atomic::{compiler_fence, Ordering};
 
+use crate::gc::Address;
+use crate::mem;
 use crate::ty::SourceType;
 use lazy_static::lazy_static;
 use raw_cpuid::{CpuId, ExtendedFeatures, FeatureInfo};
@@ -27,6 +30,32 @@ pub fn has_tzcnt() -> bool {
     *HAS_TZCNT
 }
 
+// Prepares a new stack for MacroAssembler::switch_stack(): switching to
+// the returned stack pointer zeroes all callee-saved registers and returns
+// to entry as if entry was called.
+pub fn fiber_stack_pointer(stack_top: Address, entry: Address) -> Address {
+    debug_assert!(stack_top.to_usize() % STACK_FRAME_ALIGNMENT == 0);
+
+    // Windows needs the shadow space for entry's parameters above the
+    // return address
+    let stack_top = if cfg!(target_family = "windows") {
+        stack_top.sub(32)
+    } else {
+        stack_top
+    };
+
+    let ret_addr = stack_top.sub(mem::ptr_width_usize());
+    let entry_addr = ret_addr.sub(mem::ptr_width_usize());
+    let sp = entry_addr.sub(CALLEE_SAVED.len() * mem::ptr_width_usize());
+
+    unsafe {
+        ptr::write_bytes(sp.to_mut_ptr::<u8>(), 0, stack_top.offset_from(sp));
+        *entry_addr.to_mut_ptr::<usize>() = entry.to_usize();
+    }
+
+    sp
+}
+
 lazy_static! {
 static ref FEATURES: FeatureInfo = CpuId::new().get_feature_info().unwrap();
 static ref FEATURES_EXTENDED: ExtendedFeatures = CpuId::new().get_extended_feature_info().unwrap();
@@ -76,6 +105,13 @@ pub const REG_SP: Reg = RSP;
 pub const REG_FP: Reg = RBP;
 pub const REG_THREAD: Reg = R15;
 
+// saved by MacroAssembler::switch_stack(). XMM6-XMM15 are callee-saved on
+// Windows as well but not preserved.
+#[cfg(target_family = "unix")]
+pub static CALLEE_SAVED: [Reg; 6] = [RBX, RBP, R12, R13, R14, R15];
+#[cfg(target_family = "windows")]
+pub static CALLEE_SAVED: [Reg; 8] = [RBX, RBP, RDI, RSI, R12, R13, R14, R15];
+
 #[cfg(target_family = "unix")]
 pub static SCRATCH: [Reg; 4] = [RDI, RSI, RDX, RCX];
 #[cfg(target_family = "windows")]
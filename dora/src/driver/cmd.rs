@@ -3,12 +3,15 @@ use std::cmp::{max, min};
 use std::default::Default;
 use std::fmt;
 use std::ops::Deref;
+use std::time::Duration;
 
-use crate::gc::M;
+use crate::gc::{K, M};
+use crate::mem;
 use docopt::Docopt;
 use serde::{de, Deserialize, Deserializer};
 
 use crate::gc::{DEFAULT_CODE_SPACE_LIMIT, DEFAULT_PERM_SPACE_LIMIT};
+use crate::threads::fiber::FIBER_STACK_SIZE;
 
 pub fn parse() -> Args {
     Docopt::new(USAGE)
@@ -46,16 +49,27 @@ Options:
     --gc-parallel-full      Enable parallel full collection.
     --gc-parallel-minor     Enable parallel minor collection.
     --gc-parallel           Enable both parallel minor and full collection.
+    --gc-concurrent-mark    Mark old generation concurrently to the mutator.
+    --gc-concurrent-mark-ratio=<num>  Start concurrent marking above num percent old generation occupancy (default: 50).
     --gc-stats              Print GC statistics.
+    --gc-log=<file>         Write one JSON object per collection into file (swiper only).
     --gc-verbose            Verbose GC.
     --gc-dev-verbose        Verbose GC for developers.
     --gc-verify             Verify heap before and after collections.
     --gc-verify-write       Verify references when storing in the heap.
     --gc-worker=<num>       Number of GC worker threads.
-    --gc=<name>             Switch GC. Possible values: zero, copy, swiper (default).
+    --gc=<name>             Switch GC. Possible values: zero, copy, region, swiper (default).
     --gc-young-size=<SIZE>  Use fixed size for young generation.
     --gc-young-appel        Use Appel dynamic resizing of young generation.
     --gc-semi-ratio=<num>   Use fixed ratio of semi space in young generation.
+    --gc-time-ratio=<num>   Target percentage of time spent in collections (default: 5).
+    --gc-target-pause=<ms>  Shrink young generation when minor collections take longer.
+    --gc-max-free-ratio=<num>  Shrink heap when more than num percent stay free (default: 70).
+    --alloc-profile         Sample allocations and print top allocation sites at exit.
+    --alloc-profile-interval=<SIZE>  Take an allocation sample every SIZE bytes.
+
+    --safepoint-timeout=<ms>  Report threads that didn't reach a safepoint within ms milliseconds.
+    --safepoint-timeout-abort  Abort after reporting a safepoint timeout.
 
     --compiler=<name>       Switch default compiler. Possible values: cannon [default: cannon].
     --test-filter=<name>    Filter tests.
@@ -68,6 +82,7 @@ Options:
     --max-heap-size=<SIZE>  Set maximum heap size.
     --code-size=<SIZE>      Set code size limit.
     --perm-size=<SIZE>      Set perm size limit.
+    --fiber-stack-size=<SIZE>  Set stack size of fibers (default: 256K).
 
     --stdlib=<path>         Load standard library from the given path.
     --boots=<path>          Load boots source from the given path.
@@ -99,19 +114,30 @@ pub struct Args {
     flag_gc_parallel_full: bool,
     flag_gc_parallel_minor: bool,
     flag_gc_parallel: bool,
+    pub flag_gc_concurrent_mark: bool,
+    flag_gc_concurrent_mark_ratio: Option<usize>,
     pub flag_gc_stats: bool,
+    pub flag_gc_log: Option<String>,
     pub flag_gc_verbose: bool,
     pub flag_gc_dev_verbose: bool,
     pub flag_gc_verify: bool,
     pub flag_gc_worker: usize,
     flag_gc_young_size: Option<MemSize>,
     pub flag_gc_semi_ratio: Option<usize>,
+    flag_gc_time_ratio: Option<Percentage>,
+    pub flag_gc_target_pause: Option<f32>,
+    flag_gc_max_free_ratio: Option<Percentage>,
     pub flag_gc: Option<CollectorName>,
+    pub flag_alloc_profile: bool,
+    flag_alloc_profile_interval: Option<MemSize>,
+    pub flag_safepoint_timeout: Option<u64>,
+    pub flag_safepoint_timeout_abort: bool,
     pub flag_compiler: Option<CompilerName>,
     pub flag_min_heap_size: Option<MemSize>,
     pub flag_max_heap_size: Option<MemSize>,
     pub flag_code_size: Option<MemSize>,
     pub flag_perm_size: Option<MemSize>,
+    flag_fiber_stack_size: Option<MemSize>,
     pub flag_check: bool,
     pub flag_disable_tlab: bool,
     pub flag_disable_barrier: bool,
@@ -150,6 +176,15 @@ impl Args {
             .unwrap_or(DEFAULT_PERM_SPACE_LIMIT)
     }
 
+    pub fn fiber_stack_size(&self) -> usize {
+        let fiber_stack_size = self
+            .flag_fiber_stack_size
+            .map(|s| *s)
+            .unwrap_or(FIBER_STACK_SIZE);
+
+        mem::page_align(max(fiber_stack_size, 128 * K))
+    }
+
     pub fn gc_workers(&self) -> usize {
         if self.flag_gc_worker > 0 {
             self.flag_gc_worker
@@ -166,6 +201,24 @@ impl Args {
         self.flag_gc_young_size.is_none()
     }
 
+    // targeted ratio of collection time to total time
+    pub fn gc_time_ratio(&self) -> f32 {
+        let percentage = self.flag_gc_time_ratio.map(|p| *p).unwrap_or(5);
+        percentage as f32 / 100.0
+    }
+
+    // old generation occupancy above which concurrent marking starts
+    pub fn gc_concurrent_mark_ratio(&self) -> f64 {
+        let percentage = self.flag_gc_concurrent_mark_ratio.unwrap_or(50);
+        percentage as f64 / 100.0
+    }
+
+    // ratio of free memory after a collection above which the heap shrinks
+    pub fn gc_max_free_ratio(&self) -> f32 {
+        let percentage = self.flag_gc_max_free_ratio.map(|p| *p).unwrap_or(70);
+        percentage as f32 / 100.0
+    }
+
     pub fn parallel_minor(&self) -> bool {
         self.flag_gc_parallel_minor || self.flag_gc_parallel
     }
@@ -174,6 +227,21 @@ impl Args {
         self.flag_gc_parallel_full || self.flag_gc_parallel
     }
 
+    // phase timings are needed for statistics and the GC log
+    pub fn gc_phases(&self) -> bool {
+        self.flag_gc_stats || self.flag_gc_log.is_some()
+    }
+
+    pub fn alloc_profile_interval(&self) -> usize {
+        self.flag_alloc_profile_interval
+            .map(|interval| *interval)
+            .unwrap_or(512 * 1024)
+    }
+
+    pub fn safepoint_timeout(&self) -> Option<Duration> {
+        self.flag_safepoint_timeout.map(Duration::from_millis)
+    }
+
     pub fn compiler(&self) -> CompilerName {
         self.flag_compiler.unwrap_or(CompilerName::Cannon)
     }
@@ -205,19 +273,30 @@ impl Default for Args {
             flag_gc_parallel_full: false,
             flag_gc_parallel_minor: false,
             flag_gc_parallel: false,
+            flag_gc_concurrent_mark: false,
+            flag_gc_concurrent_mark_ratio: None,
             flag_gc_stats: false,
+            flag_gc_log: None,
             flag_gc_verbose: false,
             flag_gc_dev_verbose: false,
             flag_gc_verify: false,
             flag_gc_worker: 0,
             flag_gc_young_size: None,
             flag_gc_semi_ratio: None,
+            flag_gc_time_ratio: None,
+            flag_gc_target_pause: None,
+            flag_gc_max_free_ratio: None,
             flag_gc: None,
+            flag_alloc_profile: false,
+            flag_alloc_profile_interval: None,
+            flag_safepoint_timeout: None,
+            flag_safepoint_timeout_abort: false,
             flag_compiler: None,
             flag_min_heap_size: None,
             flag_max_heap_size: None,
             flag_code_size: None,
             flag_perm_size: None,
+            flag_fiber_stack_size: None,
             flag_check: false,
             flag_disable_tlab: false,
             flag_disable_barrier: false,
@@ -238,6 +317,7 @@ pub enum CollectorName {
     Compact,
     Copy,
     Sweep,
+    Region,
     Swiper,
     SweepSwiper,
 }
@@ -310,3 +390,43 @@ impl<'de> Deserialize<'de> for MemSize {
         deserializer.deserialize_str(MemSizeVisitor)
     }
 }
+
+// percentage strictly between 0 and 100
+#[derive(Copy, Clone, Debug)]
+pub struct Percentage(usize);
+
+impl Deref for Percentage {
+    type Target = usize;
+
+    fn deref(&self) -> &usize {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Percentage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PercentageVisitor;
+        impl<'de> de::Visitor<'de> for PercentageVisitor {
+            type Value = Percentage;
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a percentage between 1 and 99")
+            }
+            fn visit_str<E>(self, percentage: &str) -> Result<Percentage, E>
+            where
+                E: de::Error,
+            {
+                match percentage.parse::<usize>() {
+                    Ok(value) if value > 0 && value < 100 => Ok(Percentage(value)),
+                    _ => Err(de::Error::custom(format!(
+                        "'{}' is not a percentage between 1 and 99",
+                        percentage
+                    ))),
+                }
+            }
+        }
+        deserializer.deserialize_str(PercentageVisitor)
+    }
+}
@@ -2,7 +2,7 @@ use crate::error::msg::SemError;
 use crate::vm::VM;
 use crate::vm::{init_global_addresses, Fct, FctId};
 
-use crate::driver::cmd;
+use crate::driver::cmd::{self, CollectorName};
 use crate::object;
 use crate::timer::Timer;
 
@@ -18,6 +18,17 @@ pub fn start() -> i32 {
         return 0;
     }
 
+    // only the generational collectors write a GC log
+    if args.flag_gc_log.is_some() {
+        match args.flag_gc.unwrap_or(CollectorName::Swiper) {
+            CollectorName::Swiper | CollectorName::SweepSwiper => {}
+            _ => {
+                eprintln!("error: --gc-log is only supported by the swiper collector");
+                return 1;
+            }
+        }
+    }
+
     let mut vm = VM::new(args);
 
     if !semck::check(&mut vm) {
@@ -80,6 +91,10 @@ pub fn start() -> i32 {
         vm.dump_gc_summary(duration);
     }
 
+    if vm.args.flag_alloc_profile {
+        vm.gc.dump_alloc_profile(&vm);
+    }
+
     code
 }
 
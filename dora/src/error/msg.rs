@@ -94,6 +94,7 @@ pub enum SemError {
     ExpectedType(String),
     ExpectedIdentifier(String),
     ExpectedStringable(String),
+    InvalidFormatSpec(String, String),
     ExpectedSomeIdentifier,
     ExpectedNamespace,
     ExpectedPath,
@@ -395,6 +396,9 @@ impl SemError {
             SemError::ExpectedStringable(ref ty) => {
                 format!("type {} does not implement Stringable.", ty)
             }
+            SemError::InvalidFormatSpec(ref spec, ref ty) => {
+                format!("format specifier `{}` not supported for type {}.", spec, ty)
+            }
             SemError::MisplacedAnnotation(ref modifier) => {
                 format!("misplaced annotation `{}`.", modifier)
             }
@@ -5,16 +5,22 @@ use crate::driver::cmd::{Args, CollectorName};
 use crate::gc::code::CodeSpace;
 use crate::gc::compact::MarkCompactCollector;
 use crate::gc::copy::CopyCollector;
+use crate::gc::pin::PinnedObjects;
+use crate::gc::profile::AllocationProfiler;
+use crate::gc::region::RegionCollector;
 use crate::gc::space::{Space, SpaceConfig};
 use crate::gc::sweep::SweepCollector;
 use crate::gc::swiper::sweep::SweepSwiper;
-use crate::gc::swiper::{Swiper, CARD_SIZE};
+use crate::gc::swiper::{Swiper, CARD_SIZE, CARD_SIZE_BITS};
 use crate::gc::tlab::TLAB_OBJECT_SIZE;
+use crate::gc::weak::WeakRefs;
 use crate::gc::zero::ZeroCollector;
+use crate::handle::Handle;
 use crate::mem;
 use crate::object::{Header, Obj};
 use crate::os;
-use crate::vm::VM;
+use crate::threads::DoraThread;
+use crate::vm::{ClassDefId, VM};
 use crate::vtable::VTable;
 
 pub mod bump;
@@ -23,12 +29,16 @@ pub mod compact;
 pub mod copy;
 pub mod freelist;
 pub mod marking;
+pub mod pin;
 pub mod pmarking;
+pub mod profile;
+pub mod region;
 pub mod root;
 pub mod space;
 pub mod sweep;
 pub mod swiper;
 pub mod tlab;
+pub mod weak;
 pub mod zero;
 
 pub const K: usize = 1024;
@@ -48,6 +58,10 @@ pub struct Gc {
 
     code_space: CodeSpace,
     perm_space: Space,
+
+    weak_refs: WeakRefs,
+    pinned_objects: PinnedObjects,
+    alloc_profiler: Option<AllocationProfiler>,
 }
 
 impl Gc {
@@ -66,18 +80,29 @@ impl Gc {
             CollectorName::Compact => box MarkCompactCollector::new(args),
             CollectorName::Copy => box CopyCollector::new(args),
             CollectorName::Sweep => box SweepCollector::new(args),
+            CollectorName::Region => box RegionCollector::new(args),
             CollectorName::Swiper => box Swiper::new(args),
             CollectorName::SweepSwiper => box SweepSwiper::new(args),
         };
 
         let supports_tlab = !args.flag_disable_tlab && collector.supports_tlab();
 
+        let alloc_profiler = if args.flag_alloc_profile {
+            Some(AllocationProfiler::new(args.alloc_profile_interval()))
+        } else {
+            None
+        };
+
         Gc {
             collector,
             supports_tlab,
 
             code_space: CodeSpace::new(),
             perm_space: Space::new(perm_config, "perm"),
+
+            weak_refs: WeakRefs::new(),
+            pinned_objects: PinnedObjects::new(),
+            alloc_profiler,
         }
     }
 
@@ -89,6 +114,72 @@ impl Gc {
         self.collector.card_table_offset()
     }
 
+    pub fn needs_pre_write_barrier(&self) -> bool {
+        self.collector.needs_pre_write_barrier()
+    }
+
+    pub fn pre_write_barrier(&self, old: Address) {
+        self.collector.pre_write_barrier(old);
+    }
+
+    // Stores a reference into a field of an object from native code,
+    // performs the same barriers as the code emitted by the JIT.
+    pub fn write_ref(&self, object: Address, slot: Address, value: Address) {
+        if self.needs_pre_write_barrier() {
+            let old = unsafe { *slot.to_ptr::<Address>() };
+
+            if old.is_non_null() {
+                self.pre_write_barrier(old);
+            }
+        }
+
+        unsafe {
+            *slot.to_mut_ptr::<Address>() = value;
+        }
+
+        if self.needs_write_barrier() {
+            let card = (object.to_usize() >> CARD_SIZE_BITS) + self.card_table_offset();
+
+            unsafe {
+                *(card as *mut u8) = 0;
+            }
+        }
+    }
+
+    pub fn detach_thread(&self, thread: &DoraThread) {
+        self.collector.detach_thread(thread);
+    }
+
+    pub fn weak_refs(&self) -> &WeakRefs {
+        &self.weak_refs
+    }
+
+    pub fn pinned_objects(&self) -> &PinnedObjects {
+        &self.pinned_objects
+    }
+
+    pub fn supports_pinning(&self) -> bool {
+        self.collector.supports_pinning()
+    }
+
+    // The object is not moved by collections until it is unpinned. Pinning
+    // itself might move the object, read its address from the handle
+    // afterwards.
+    pub fn pin(&self, vm: &VM, object: Handle<Obj>) {
+        assert!(object.direct().address().is_non_null());
+        assert!(
+            self.collector.supports_pinning(),
+            "collector does not support pinning objects"
+        );
+
+        self.pinned_objects.pin(object.direct().address());
+        self.collector.pin(vm, object);
+    }
+
+    pub fn unpin(&self, object: Address) {
+        self.pinned_objects.unpin(object);
+    }
+
     pub fn alloc_code(&self, size: usize) -> Address {
         self.code_space.alloc(size)
     }
@@ -97,7 +188,7 @@ impl Gc {
         self.perm_space.alloc(size)
     }
 
-    pub fn alloc(&self, vm: &VM, size: usize, array_ref: bool) -> Address {
+    pub fn alloc(&self, vm: &VM, size: usize, array_ref: bool, cls_def_id: ClassDefId) -> Address {
         if vm.args.flag_gc_stress_minor {
             self.minor_collect(vm, GcReason::StressMinor);
         }
@@ -107,13 +198,23 @@ impl Gc {
         }
 
         if size < TLAB_OBJECT_SIZE && self.supports_tlab {
-            self.alloc_tlab(vm, size, array_ref)
+            self.alloc_tlab(vm, size, array_ref, cls_def_id)
         } else {
+            if let Some(ref alloc_profiler) = self.alloc_profiler {
+                alloc_profiler.record(vm, cls_def_id, size);
+            }
+
             self.collector.alloc(vm, size, array_ref)
         }
     }
 
-    fn alloc_tlab(&self, vm: &VM, size: usize, _array_ref: bool) -> Address {
+    fn alloc_tlab(
+        &self,
+        vm: &VM,
+        size: usize,
+        _array_ref: bool,
+        cls_def_id: ClassDefId,
+    ) -> Address {
         // try to allocate in current tlab
         if let Some(addr) = tlab::allocate(size) {
             return addr;
@@ -124,6 +225,10 @@ impl Gc {
 
         // allocate new tlab
         if let Some(tlab) = self.collector.alloc_tlab_area(vm, tlab::calculate_size()) {
+            if let Some(ref alloc_profiler) = self.alloc_profiler {
+                alloc_profiler.record(vm, cls_def_id, tlab.size());
+            }
+
             let object_start = tlab.start;
             let tlab = Region::new(tlab.start.offset(size), tlab.end);
 
@@ -150,6 +255,16 @@ impl Gc {
         self.collector.dump_summary(runtime);
     }
 
+    pub fn heap_stats(&self) -> HeapStats {
+        self.collector.heap_stats()
+    }
+
+    pub fn dump_alloc_profile(&self, vm: &VM) {
+        if let Some(ref alloc_profiler) = self.alloc_profiler {
+            alloc_profiler.dump(vm);
+        }
+    }
+
     pub fn verify_ref(&self, vm: &VM, reference: Address) {
         if reference.is_null() {
             return;
@@ -177,9 +292,36 @@ trait Collector {
         false
     }
 
+    // decides whether to emit pre-write barriers needed for
+    // snapshot-at-the-beginning concurrent marking
+    fn needs_pre_write_barrier(&self) -> bool {
+        false
+    }
+
+    // records the overwritten reference while concurrent marking is active
+    fn pre_write_barrier(&self, _old: Address) {
+        // do nothing
+    }
+
+    // called before the thread is removed from the list of threads
+    fn detach_thread(&self, _thread: &DoraThread) {
+        // do nothing
+    }
+
     // gives true when collector supports tlab allocation.
     fn supports_tlab(&self) -> bool;
 
+    // gives true when collector can keep pinned objects in place
+    fn supports_pinning(&self) -> bool {
+        true
+    }
+
+    // called after the object was added to the pinned objects, moves
+    // the object where collections do not move it anymore
+    fn pin(&self, _vm: &VM, _object: Handle<Obj>) {
+        // do nothing
+    }
+
     // only need if write barriers needed
     fn card_table_offset(&self) -> usize {
         0
@@ -188,6 +330,9 @@ trait Collector {
     // prints GC summary: minor/full collections, etc.
     fn dump_summary(&self, _runtime: f32);
 
+    // current heap usage and collection statistics
+    fn heap_stats(&self) -> HeapStats;
+
     // verify reference
     fn verify_ref(&self, _vm: &VM, _addr: Address) {
         // do nothing
@@ -467,6 +612,7 @@ pub enum GcReason {
     ForceMinorCollect,
     Stress,
     StressMinor,
+    Pin,
 }
 
 impl GcReason {
@@ -478,6 +624,7 @@ impl GcReason {
             GcReason::ForceMinorCollect => "force minor collect",
             GcReason::Stress => "stress",
             GcReason::StressMinor => "stress minor",
+            GcReason::Pin => "pin",
         }
     }
 }
@@ -560,6 +707,29 @@ pub fn fill_region_with_free(vm: &VM, start: Address, end: Address, next: Addres
     }
 }
 
+// Heap usage, collection statistics and limits of the collector. Sizes are in
+// bytes, pauses in milliseconds. Collectors without generations report their
+// whole heap as old generation and all collections as full collections.
+#[derive(Clone, Default)]
+pub struct HeapStats {
+    pub young_used: usize,
+    pub young_committed: usize,
+    pub old_used: usize,
+    pub old_committed: usize,
+    pub large_used: usize,
+    pub large_committed: usize,
+
+    pub minor_collections: usize,
+    pub minor_pause: f32,
+    pub full_collections: usize,
+    pub full_pause: f32,
+
+    pub min_heap_size: usize,
+    pub max_heap_size: usize,
+    pub young_limit: usize,
+    pub old_limit: usize,
+}
+
 struct CollectionStats {
     collections: usize,
     total_pause: f32,
@@ -6,7 +6,10 @@ use crate::gc::marking;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::space::Space;
 use crate::gc::tlab;
-use crate::gc::{formatted_size, Address, CollectionStats, Collector, GcReason, Region};
+use crate::gc::weak::WeakRefLocation;
+use crate::gc::{
+    fill_region, formatted_size, Address, CollectionStats, Collector, GcReason, HeapStats, Region,
+};
 use crate::object::Obj;
 use crate::os;
 use crate::safepoint;
@@ -78,7 +81,7 @@ impl Collector for MarkCompactCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(true);
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, threads);
@@ -86,17 +89,30 @@ impl Collector for MarkCompactCollector {
             self.mark_compact(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
-            let duration = timer.stop();
-            let mut stats = self.stats.lock();
-            stats.add(duration);
-        }
+        let duration = timer.stop();
+        let mut stats = self.stats.lock();
+        stats.add(duration);
     }
 
     fn minor_collect(&self, vm: &VM, reason: GcReason) {
         self.collect(vm, reason);
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        let stats = self.stats.lock();
+
+        HeapStats {
+            old_used: self.alloc.top().offset_from(self.heap.start),
+            old_committed: self.heap.size(),
+            full_collections: stats.collections(),
+            full_pause: stats.pause(),
+            min_heap_size: self.heap.size(),
+            max_heap_size: self.heap.size(),
+            old_limit: self.heap.size(),
+            ..Default::default()
+        }
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
@@ -137,6 +153,10 @@ impl MarkCompactCollector {
             init_top: self.alloc.top(),
             top: self.heap.start,
 
+            pinned: vm.gc.pinned_objects().objects(),
+            pinned_gaps: Vec::new(),
+            next_pinned_gap: 0,
+
             rootset,
             reason,
         };
@@ -154,6 +174,11 @@ struct MarkCompact<'a> {
     init_top: Address,
     top: Address,
 
+    // pinned objects in ascending order and the unused gaps in front of them
+    pinned: Vec<Address>,
+    pinned_gaps: Vec<Region>,
+    next_pinned_gap: usize,
+
     rootset: &'a [Slot],
     reason: GcReason,
 }
@@ -162,6 +187,7 @@ impl<'a> MarkCompact<'a> {
     fn collect(&mut self) {
         self.mark_live();
         self.compute_forward();
+        self.process_weak_refs();
         self.update_references();
         self.relocate();
     }
@@ -171,14 +197,35 @@ impl<'a> MarkCompact<'a> {
     }
 
     fn compute_forward(&mut self) {
-        self.walk_heap(|mc, object, _addr, object_size| {
+        self.walk_heap(|mc, object, addr, object_size| {
             if object.header().is_marked_non_atomic() {
-                let fwd = mc.allocate(object_size);
+                let fwd = if mc.pinned.binary_search(&addr).is_ok() {
+                    mc.allocate_pinned(addr, object_size)
+                } else {
+                    mc.allocate(object_size)
+                };
                 object.header_mut().set_fwdptr_non_atomic(fwd);
             }
         });
     }
 
+    fn process_weak_refs(&mut self) {
+        let heap = self.heap;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Current, |object_addr| {
+                if !heap.contains(object_addr) {
+                    Some(object_addr)
+                } else if object_addr.to_obj().header().is_marked_non_atomic() {
+                    Some(object_addr.to_obj().header().fwdptr_non_atomic())
+                } else {
+                    None
+                }
+            });
+    }
+
     fn allocate(&mut self, object_size: usize) -> Address {
         let addr = self.top;
         let next = self.top.offset(object_size);
@@ -191,6 +238,17 @@ impl<'a> MarkCompact<'a> {
         panic!("FAIL: Not enough space for objects.");
     }
 
+    fn allocate_pinned(&mut self, object: Address, object_size: usize) -> Address {
+        assert!(self.top <= object);
+
+        if self.top < object {
+            self.pinned_gaps.push(Region::new(self.top, object));
+        }
+
+        self.top = object.offset(object_size);
+        object
+    }
+
     fn update_references(&mut self) {
         self.walk_heap(|mc, object, _addr, _object_size| {
             if object.header().is_marked_non_atomic() {
@@ -225,6 +283,15 @@ impl<'a> MarkCompact<'a> {
                 let dest = object.header().fwdptr_non_atomic();
                 debug_assert!(mc.heap.contains(dest));
 
+                // all objects in front of a pinned object were already
+                // relocated, now the gap in front of it can be filled
+                if let Some(&gap) = mc.pinned_gaps.get(mc.next_pinned_gap) {
+                    if gap.end == address {
+                        fill_region(mc.vm, gap.start, gap.end);
+                        mc.next_pinned_gap += 1;
+                    }
+                }
+
                 // determine location after relocated object
                 let next_dest = dest.offset(object_size);
                 debug_assert!(mc.heap.valid_top(next_dest));
@@ -4,7 +4,8 @@ use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::tlab;
-use crate::gc::{formatted_size, Address, CollectionStats, Collector, GcReason, Region};
+use crate::gc::weak::WeakRefLocation;
+use crate::gc::{formatted_size, Address, CollectionStats, Collector, GcReason, HeapStats, Region};
 use crate::mem;
 use crate::object::Obj;
 use crate::os::{self, MemoryPermission};
@@ -53,6 +54,10 @@ impl Collector for CopyCollector {
         true
     }
 
+    fn supports_pinning(&self) -> bool {
+        false
+    }
+
     fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
         let ptr = self.alloc.bump_alloc(size);
 
@@ -83,7 +88,7 @@ impl Collector for CopyCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(true);
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, &*threads);
@@ -91,17 +96,31 @@ impl Collector for CopyCollector {
             self.copy_collect(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
-            let duration = timer.stop();
-            let mut stats = self.stats.lock();
-            stats.add(duration);
-        }
+        let duration = timer.stop();
+        let mut stats = self.stats.lock();
+        stats.add(duration);
     }
 
     fn minor_collect(&self, vm: &VM, reason: GcReason) {
         self.collect(vm, reason);
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        let stats = self.stats.lock();
+        let from_space = self.from_space();
+
+        HeapStats {
+            old_used: self.alloc.top().offset_from(from_space.start),
+            old_committed: from_space.size(),
+            full_collections: stats.collections(),
+            full_pause: stats.pause(),
+            min_heap_size: self.total.size(),
+            max_heap_size: self.total.size(),
+            old_limit: from_space.size(),
+            ..Default::default()
+        }
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
@@ -174,6 +193,16 @@ impl CopyCollector {
             scan = scan.offset(object.size());
         }
 
+        vm.gc
+            .weak_refs
+            .process(WeakRefLocation::Forwarded, |object_addr| {
+                if from_space.contains(object_addr) {
+                    object_addr.to_obj().header().vtblptr_forwarded()
+                } else {
+                    Some(object_addr)
+                }
+            });
+
         // disable access in current from-space
         // makes sure that no pointer into from-space is left (in debug-builds)
         if cfg!(debug_assertions) {
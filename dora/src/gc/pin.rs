@@ -0,0 +1,73 @@
+use parking_lot::Mutex;
+
+use crate::gc::root::Slot;
+use crate::gc::Address;
+
+// Keeps track of all pinned objects. Pinned objects are roots and must not
+// be moved by the collectors: moving collectors need to either keep them in
+// place or move them before `Gc::pin` returns. Each entry counts how often
+// the object was pinned, the object is unpinned when the count drops to 0.
+pub struct PinnedObjects {
+    entries: Mutex<Vec<PinnedObject>>,
+}
+
+struct PinnedObject {
+    object: Address,
+    count: usize,
+}
+
+impl PinnedObjects {
+    pub fn new() -> PinnedObjects {
+        PinnedObjects {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn pin(&self, object: Address) {
+        let mut entries = self.entries.lock();
+
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.object == object) {
+            entry.count += 1;
+        } else {
+            entries.push(PinnedObject { object, count: 1 });
+        }
+    }
+
+    pub fn unpin(&self, object: Address) {
+        let mut entries = self.entries.lock();
+
+        let idx = entries
+            .iter()
+            .position(|entry| entry.object == object)
+            .expect("object not pinned");
+
+        entries[idx].count -= 1;
+
+        if entries[idx].count == 0 {
+            entries.swap_remove(idx);
+        }
+    }
+
+    pub fn is_pinned(&self, object: Address) -> bool {
+        let entries = self.entries.lock();
+        entries.iter().any(|entry| entry.object == object)
+    }
+
+    // addresses of all pinned objects in ascending order
+    pub fn objects(&self) -> Vec<Address> {
+        let entries = self.entries.lock();
+        let mut objects: Vec<Address> = entries.iter().map(|entry| entry.object).collect();
+        objects.sort();
+        objects
+    }
+
+    // only valid while the world is stopped: entries are not added or
+    // removed then and the slots stay valid
+    pub fn roots(&self, rootset: &mut Vec<Slot>) {
+        let entries = self.entries.lock();
+
+        for entry in entries.iter() {
+            rootset.push(Slot::at(Address::from_ptr(&entry.object)));
+        }
+    }
+}
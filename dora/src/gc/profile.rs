@@ -0,0 +1,126 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::compiler::fct::JitFctId;
+use crate::gc::formatted_size;
+use crate::stack::stacktrace_from_last_dtn;
+use crate::vm::{ClassDefId, VM};
+
+// number of allocation sites printed for each ordering
+const TOP_SITES: usize = 10;
+
+// number of innermost frames printed for each allocation site
+const MAX_FRAMES: usize = 8;
+
+// Samples allocations for `--alloc-profile`. Every time the number of
+// allocated bytes crosses a multiple of the sampling interval, the class of
+// the allocated object and the current Dora stack are recorded. Allocations
+// in the TLAB fast path are only seen at TLAB refills, the bytes of the whole
+// TLAB are then attributed to the object that caused the refill.
+pub struct AllocationProfiler {
+    interval: usize,
+    allocated: AtomicUsize,
+    sites: Mutex<HashMap<AllocationSite, SiteStats>>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct AllocationSite {
+    cls_def_id: ClassDefId,
+    frames: Vec<(JitFctId, i32)>,
+}
+
+#[derive(Default)]
+struct SiteStats {
+    // estimated number of bytes: each sample stands for `interval` bytes
+    bytes: usize,
+    samples: usize,
+}
+
+impl AllocationProfiler {
+    pub fn new(interval: usize) -> AllocationProfiler {
+        assert!(interval > 0);
+
+        AllocationProfiler {
+            interval,
+            allocated: AtomicUsize::new(0),
+            sites: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, vm: &VM, cls_def_id: ClassDefId, size: usize) {
+        let before = self.allocated.fetch_add(size, Ordering::Relaxed);
+        let after = before + size;
+        let crossed = after / self.interval - before / self.interval;
+
+        if crossed == 0 {
+            return;
+        }
+
+        let stacktrace = stacktrace_from_last_dtn(vm);
+        let site = AllocationSite {
+            cls_def_id,
+            frames: stacktrace.frames().collect(),
+        };
+
+        let mut sites = self.sites.lock();
+        let stats = sites.entry(site).or_default();
+        stats.bytes += crossed * self.interval;
+        stats.samples += crossed;
+    }
+
+    pub fn dump(&self, vm: &VM) {
+        let sites = self.sites.lock();
+        let mut sites: Vec<_> = sites.iter().collect();
+        let samples: usize = sites.iter().map(|(_, stats)| stats.samples).sum();
+
+        println!(
+            "Allocation profile: {} samples, interval {}, {} allocated",
+            samples,
+            formatted_size(self.interval),
+            formatted_size(self.allocated.load(Ordering::Relaxed))
+        );
+
+        sites.sort_by(|(_, lhs), (_, rhs)| rhs.bytes.cmp(&lhs.bytes));
+        println!("\nTop allocation sites by bytes:");
+        dump_sites(vm, &sites);
+
+        sites.sort_by(|(_, lhs), (_, rhs)| rhs.samples.cmp(&lhs.samples));
+        println!("\nTop allocation sites by count:");
+        dump_sites(vm, &sites);
+    }
+}
+
+fn dump_sites(vm: &VM, sites: &[(&AllocationSite, &SiteStats)]) {
+    for (idx, (site, stats)) in sites.iter().take(TOP_SITES).enumerate() {
+        let cls = vm.class_defs.idx(site.cls_def_id);
+
+        println!(
+            "{:>3}. {} ~{} in {} samples",
+            idx + 1,
+            cls.name(vm),
+            formatted_size(stats.bytes),
+            stats.samples
+        );
+
+        if site.frames.is_empty() {
+            println!("       <no Dora frames>");
+        }
+
+        for &(fct_id, lineno) in site.frames.iter().take(MAX_FRAMES) {
+            let jit_fct = vm.jit_fcts.idx(fct_id);
+            let fct = vm.fcts.idx(jit_fct.fct_id());
+            let fct = fct.read();
+
+            if lineno == 0 {
+                println!("       {}: ?", fct.name_with_params(vm));
+            } else {
+                println!("       {}: {}", fct.name_with_params(vm), lineno);
+            }
+        }
+
+        if site.frames.len() > MAX_FRAMES {
+            println!("       ... {} more", site.frames.len() - MAX_FRAMES);
+        }
+    }
+}
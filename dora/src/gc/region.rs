@@ -0,0 +1,791 @@
+use fixedbitset::FixedBitSet;
+use parking_lot::Mutex;
+
+use crate::driver::cmd::Args;
+use crate::gc::root::{get_rootset, Slot};
+use crate::gc::tlab::{self, TLAB_OBJECT_SIZE};
+use crate::gc::weak::WeakRefLocation;
+use crate::gc::{
+    formatted_size, Address, CollectionStats, Collector, GcReason, HeapStats, Region, K,
+};
+use crate::mem;
+use crate::os;
+use crate::safepoint;
+use crate::timer::Timer;
+use crate::vm::VM;
+
+// The heap is divided into blocks and each block into lines. Marking
+// records the lines occupied by live objects, sweeping turns runs of free
+// lines into holes that are reused for bump allocation (Immix).
+pub const BLOCK_SIZE: usize = 32 * K;
+pub const LINE_SIZE: usize = 256;
+const LINES_PER_BLOCK: usize = BLOCK_SIZE / LINE_SIZE;
+
+// blocks with at most this many live lines in the last collection are
+// evacuated in the next collection
+const EVACUATION_LINES: usize = LINES_PER_BLOCK / 4;
+
+// objects larger than a block are allocated in a run of free blocks
+const LARGE_OBJECT_SIZE: usize = BLOCK_SIZE;
+
+pub struct RegionCollector {
+    heap: Region,
+    space: Mutex<RegionSpace>,
+    stats: Mutex<CollectionStats>,
+}
+
+impl RegionCollector {
+    pub fn new(args: &Args) -> RegionCollector {
+        let heap_size = mem::align_usize(args.max_heap_size(), BLOCK_SIZE);
+        let heap_start = os::commit(heap_size, false);
+
+        if heap_start.is_null() {
+            panic!("could not allocate heap of size {} bytes", heap_size);
+        }
+
+        let heap_end = heap_start.offset(heap_size);
+        let heap = Region::new(heap_start, heap_end);
+
+        if args.flag_gc_verbose {
+            println!(
+                "GC: {} {} ({} blocks)",
+                heap,
+                formatted_size(heap_size),
+                heap_size / BLOCK_SIZE
+            );
+        }
+
+        RegionCollector {
+            heap,
+            space: Mutex::new(RegionSpace::new(heap)),
+            stats: Mutex::new(CollectionStats::new()),
+        }
+    }
+}
+
+impl Collector for RegionCollector {
+    fn supports_tlab(&self) -> bool {
+        true
+    }
+
+    fn alloc_tlab_area(&self, vm: &VM, size: usize) -> Option<Region> {
+        if let Some(tlab) = self.space.lock().alloc_tlab_area(size) {
+            return Some(tlab);
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+        self.space.lock().alloc_tlab_area(size)
+    }
+
+    fn alloc(&self, vm: &VM, size: usize, _array_ref: bool) -> Address {
+        let ptr = self.space.lock().alloc(size);
+
+        if ptr.is_non_null() {
+            return ptr;
+        }
+
+        self.collect(vm, GcReason::AllocationFailure);
+        self.space.lock().alloc(size)
+    }
+
+    fn collect(&self, vm: &VM, reason: GcReason) {
+        let mut timer = Timer::new(true);
+
+        safepoint::stop_the_world(vm, |threads| {
+            tlab::make_iterable_all(vm, threads);
+            let rootset = get_rootset(vm, threads);
+            self.mark_region(vm, &rootset, reason);
+        });
+
+        let duration = timer.stop();
+        let mut stats = self.stats.lock();
+        stats.add(duration);
+    }
+
+    fn minor_collect(&self, vm: &VM, reason: GcReason) {
+        self.collect(vm, reason);
+    }
+
+    fn heap_stats(&self) -> HeapStats {
+        let (used, large) = {
+            let space = self.space.lock();
+            (space.used(), space.large_size())
+        };
+
+        let stats = self.stats.lock();
+
+        // holes left in TLABs are counted as used
+        HeapStats {
+            old_used: used - large,
+            old_committed: self.heap.size() - large,
+            large_used: large,
+            large_committed: large,
+            full_collections: stats.collections(),
+            full_pause: stats.pause(),
+            min_heap_size: self.heap.size(),
+            max_heap_size: self.heap.size(),
+            old_limit: self.heap.size(),
+            ..Default::default()
+        }
+    }
+
+    fn dump_summary(&self, runtime: f32) {
+        let stats = self.stats.lock();
+        let (mutator, gc) = stats.percentage(runtime);
+
+        println!("GC stats: total={:.1}", runtime);
+        println!("GC stats: mutator={:.1}", stats.mutator(runtime));
+        println!("GC stats: collection={:.1}", stats.pause());
+
+        println!("");
+        println!("GC stats: collection-count={}", stats.collections());
+        println!("GC stats: collection-pauses={}", stats.pauses());
+
+        println!(
+            "GC summary: {:.1}ms collection ({}), {:.1}ms mutator, {:.1}ms total ({}% mutator, {}% GC)",
+            stats.pause(),
+            stats.collections(),
+            stats.mutator(runtime),
+            runtime,
+            mutator,
+            gc,
+        );
+    }
+
+    fn verify_ref(&self, vm: &VM, reference: Address) {
+        let found = vm.gc.perm_space.contains(reference)
+            || (self.heap.contains(reference) && self.space.lock().is_allocated(reference));
+
+        assert!(found, "write barrier found invalid reference");
+    }
+}
+
+impl Drop for RegionCollector {
+    fn drop(&mut self) {
+        os::free(self.heap.start, self.heap.size());
+    }
+}
+
+impl RegionCollector {
+    fn mark_region(&self, vm: &VM, rootset: &[Slot], reason: GcReason) {
+        let mut space = self.space.lock();
+
+        let mut collector = MarkRegion {
+            vm,
+            heap: self.heap,
+            perm: vm.gc.perm_space.total(),
+            space: &mut space,
+
+            rootset,
+            reason,
+
+            candidates: FixedBitSet::with_capacity(0),
+            evacuation: Region::new(Address::null(), Address::null()),
+            marking_stack: Vec::new(),
+            evacuated: 0,
+        };
+
+        collector.collect();
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum BlockState {
+    Free,
+
+    // block for small and medium-sized objects, stores the number of
+    // live lines after the last collection
+    Used(usize),
+
+    // first block of a large object and the number of its blocks
+    Large(usize),
+
+    // remaining blocks of a large object
+    LargeTail,
+}
+
+struct RegionSpace {
+    heap: Region,
+    blocks: Vec<BlockState>,
+
+    // free blocks and holes are stored in descending address order,
+    // allocation pops the lowest address first
+    free_blocks: Vec<usize>,
+    holes: Vec<Region>,
+    small_holes: Vec<Region>,
+
+    // bump allocation region for objects allocated outside of TLABs
+    current: Region,
+
+    // marks of the last collection, one bit per line and one per word
+    line_marks: FixedBitSet,
+    mark_bits: FixedBitSet,
+}
+
+impl RegionSpace {
+    fn new(heap: Region) -> RegionSpace {
+        let blocks = heap.size() / BLOCK_SIZE;
+
+        RegionSpace {
+            heap,
+            blocks: vec![BlockState::Free; blocks],
+            free_blocks: (0..blocks).rev().collect(),
+            holes: Vec::new(),
+            small_holes: Vec::new(),
+            current: Region::new(heap.start, heap.start),
+            line_marks: FixedBitSet::with_capacity(heap.size() / LINE_SIZE),
+            mark_bits: FixedBitSet::with_capacity(heap.size() / mem::ptr_width_usize()),
+        }
+    }
+
+    fn alloc_tlab_area(&mut self, size: usize) -> Option<Region> {
+        if let Some(hole) = self.holes.pop() {
+            return Some(self.split_hole(hole, size));
+        }
+
+        let block = self.alloc_block()?;
+        let block = self.block_region(block);
+        Some(self.split_hole(block, size))
+    }
+
+    fn alloc(&mut self, size: usize) -> Address {
+        if size > LARGE_OBJECT_SIZE {
+            self.alloc_large(size)
+        } else if size >= TLAB_OBJECT_SIZE {
+            self.alloc_medium(size)
+        } else {
+            self.alloc_small(size)
+        }
+    }
+
+    fn alloc_small(&mut self, size: usize) -> Address {
+        loop {
+            if size <= self.current.size() {
+                let object = self.current.start;
+                self.current.start = object.offset(size);
+                return object;
+            }
+
+            // the rest of the current region is wasted until the next collection
+            let next = if let Some(hole) = self.small_holes.pop() {
+                hole
+            } else if let Some(hole) = self.holes.pop() {
+                hole
+            } else if let Some(block) = self.alloc_block() {
+                self.block_region(block)
+            } else {
+                return Address::null();
+            };
+
+            self.current = next;
+        }
+    }
+
+    fn alloc_medium(&mut self, size: usize) -> Address {
+        if let Some(idx) = self.holes.iter().rposition(|hole| hole.size() >= size) {
+            let hole = self.holes[idx];
+            let rest = Region::new(hole.start.offset(size), hole.end);
+
+            if rest.size() >= TLAB_OBJECT_SIZE {
+                self.holes[idx] = rest;
+            } else {
+                self.holes.remove(idx);
+                self.add_hole(rest);
+            }
+
+            return hole.start;
+        }
+
+        if let Some(block) = self.alloc_block() {
+            let block = self.block_region(block);
+            self.add_hole(Region::new(block.start.offset(size), block.end));
+            return block.start;
+        }
+
+        Address::null()
+    }
+
+    fn alloc_large(&mut self, size: usize) -> Address {
+        let count = (size + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        let mut run = 0;
+
+        for idx in 0..self.blocks.len() {
+            if self.blocks[idx] != BlockState::Free {
+                run = 0;
+                continue;
+            }
+
+            run += 1;
+
+            if run == count {
+                let first = idx + 1 - count;
+
+                self.blocks[first] = BlockState::Large(count);
+
+                for block in first + 1..=idx {
+                    self.blocks[block] = BlockState::LargeTail;
+                }
+
+                self.free_blocks
+                    .retain(|&block| block < first || block > idx);
+
+                return self.block_region(first).start;
+            }
+        }
+
+        Address::null()
+    }
+
+    fn alloc_block(&mut self) -> Option<usize> {
+        let block = self.free_blocks.pop()?;
+        debug_assert!(self.blocks[block] == BlockState::Free);
+
+        // fresh blocks are not considered for evacuation before they
+        // survived a collection
+        self.blocks[block] = BlockState::Used(LINES_PER_BLOCK);
+        Some(block)
+    }
+
+    fn split_hole(&mut self, hole: Region, size: usize) -> Region {
+        if hole.size() <= size {
+            return hole;
+        }
+
+        let end = hole.start.offset(size);
+        self.add_hole(Region::new(end, hole.end));
+
+        Region::new(hole.start, end)
+    }
+
+    fn add_hole(&mut self, hole: Region) {
+        if hole.size() >= TLAB_OBJECT_SIZE {
+            self.holes.push(hole);
+        } else if !hole.empty() {
+            self.small_holes.push(hole);
+        }
+    }
+
+    fn block_region(&self, block: usize) -> Region {
+        let start = self.heap.start.offset(block * BLOCK_SIZE);
+        start.region_start(BLOCK_SIZE)
+    }
+
+    fn block_index(&self, addr: Address) -> usize {
+        addr.offset_from(self.heap.start) / BLOCK_SIZE
+    }
+
+    fn line_index(&self, addr: Address) -> usize {
+        addr.offset_from(self.heap.start) / LINE_SIZE
+    }
+
+    fn mark_index(&self, addr: Address) -> usize {
+        addr.offset_from(self.heap.start) / mem::ptr_width_usize()
+    }
+
+    fn is_allocated(&self, addr: Address) -> bool {
+        let block = self.block_index(addr);
+        self.blocks[block] != BlockState::Free
+    }
+
+    fn used(&self) -> usize {
+        let holes: usize = self
+            .holes
+            .iter()
+            .chain(self.small_holes.iter())
+            .map(|hole| hole.size())
+            .sum();
+        let free = self.free_blocks.len() * BLOCK_SIZE + holes + self.current.size();
+
+        self.heap.size() - free
+    }
+
+    fn large_size(&self) -> usize {
+        let blocks = self
+            .blocks
+            .iter()
+            .filter(|&&state| match state {
+                BlockState::Large(_) | BlockState::LargeTail => true,
+                _ => false,
+            })
+            .count();
+
+        blocks * BLOCK_SIZE
+    }
+}
+
+struct MarkRegion<'a> {
+    vm: &'a VM,
+    heap: Region,
+    perm: Region,
+    space: &'a mut RegionSpace,
+
+    rootset: &'a [Slot],
+    reason: GcReason,
+
+    // blocks whose objects are copied out during marking
+    candidates: FixedBitSet,
+    evacuation: Region,
+    marking_stack: Vec<Address>,
+    evacuated: usize,
+}
+
+impl<'a> MarkRegion<'a> {
+    fn collect(&mut self) {
+        let dev_verbose = self.vm.args.flag_gc_dev_verbose;
+        let timer = Timer::new(self.vm.args.flag_gc_verbose);
+        let old_size = self.space.used();
+
+        if self.vm.args.flag_gc_verify {
+            self.verify("pre", false);
+        }
+
+        if dev_verbose {
+            println!("Region GC: Phase 1 (marking)");
+        }
+
+        self.select_candidates();
+        self.mark();
+        self.process_weak_refs();
+
+        if dev_verbose {
+            println!("Region GC: Phase 2 (sweep)");
+        }
+
+        self.sweep();
+
+        if self.vm.args.flag_gc_verify {
+            self.verify("post", true);
+        }
+
+        if dev_verbose {
+            println!("Region GC: Stop");
+        }
+
+        timer.stop_with(|time_pause| {
+            let new_size = self.space.used();
+
+            println!(
+                "Region GC: {:.1} ms, {}->{} size, {} evacuated from {} blocks, ({})",
+                time_pause,
+                formatted_size(old_size),
+                formatted_size(new_size),
+                formatted_size(self.evacuated),
+                self.candidates.count_ones(..),
+                self.reason
+            );
+        });
+    }
+
+    fn select_candidates(&mut self) {
+        let mut candidates: Vec<(usize, usize)> = self
+            .space
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(block, &state)| match state {
+                BlockState::Used(live_lines) if live_lines <= EVACUATION_LINES => {
+                    Some((live_lines, block))
+                }
+                _ => None,
+            })
+            .collect();
+
+        candidates.sort();
+
+        // only evacuate as many lines as fit into the free blocks
+        let mut budget = self.space.free_blocks.len() * LINES_PER_BLOCK;
+        self.candidates = FixedBitSet::with_capacity(self.space.blocks.len());
+
+        for (live_lines, block) in candidates {
+            if live_lines > budget {
+                break;
+            }
+
+            budget -= live_lines;
+            self.candidates.insert(block);
+        }
+
+        // blocks with pinned objects are never evacuated
+        for object in self.vm.gc.pinned_objects().objects() {
+            if self.heap.contains(object) {
+                let block = self.space.block_index(object);
+                self.candidates.set(block, false);
+            }
+        }
+    }
+
+    fn mark(&mut self) {
+        self.space.line_marks.clear();
+        self.space.mark_bits.clear();
+
+        for &root in self.rootset {
+            self.trace(root);
+        }
+
+        while let Some(object_addr) = self.marking_stack.pop() {
+            let object = object_addr.to_mut_obj();
+
+            object.visit_reference_fields(|field| {
+                self.trace(field);
+            });
+        }
+    }
+
+    fn trace(&mut self, slot: Slot) {
+        let object_addr = slot.get();
+
+        if !self.heap.contains(object_addr) {
+            debug_assert!(object_addr.is_null() || self.perm.contains(object_addr));
+            return;
+        }
+
+        let object = object_addr.to_mut_obj();
+
+        if let Some(fwd) = object.header().vtblptr_forwarded() {
+            slot.set(fwd);
+            return;
+        }
+
+        if self
+            .space
+            .mark_bits
+            .contains(self.space.mark_index(object_addr))
+        {
+            return;
+        }
+
+        let object_size = object.size();
+
+        if self
+            .candidates
+            .contains(self.space.block_index(object_addr))
+        {
+            if let Some(dest) = self.alloc_evacuation(object_size) {
+                object.copy_to(dest, object_size);
+                object.header_mut().vtblptr_forward(dest);
+                slot.set(dest);
+
+                self.evacuated += object_size;
+                self.mark_object(dest, object_size);
+                return;
+            }
+        }
+
+        self.mark_object(object_addr, object_size);
+    }
+
+    fn mark_object(&mut self, object_addr: Address, object_size: usize) {
+        let mark_index = self.space.mark_index(object_addr);
+        self.space.mark_bits.insert(mark_index);
+
+        // large objects are kept alive through their first block
+        if object_size <= LARGE_OBJECT_SIZE {
+            let first = self.space.line_index(object_addr);
+            let last = self.space.line_index(object_addr.offset(object_size - 1));
+            self.space.line_marks.set_range(first..last + 1, true);
+        }
+
+        self.marking_stack.push(object_addr);
+    }
+
+    fn alloc_evacuation(&mut self, size: usize) -> Option<Address> {
+        if size > self.evacuation.size() {
+            // the rest of the block becomes a hole in the sweep
+            let block = self.space.alloc_block()?;
+            self.evacuation = self.space.block_region(block);
+        }
+
+        let object = self.evacuation.start;
+        self.evacuation.start = object.offset(size);
+        Some(object)
+    }
+
+    fn process_weak_refs(&mut self) {
+        let heap = self.heap;
+        let space = &*self.space;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Forwarded, |object_addr| {
+                if !heap.contains(object_addr) {
+                    Some(object_addr)
+                } else if let Some(fwd) = object_addr.to_obj().header().vtblptr_forwarded() {
+                    Some(fwd)
+                } else if space.mark_bits.contains(space.mark_index(object_addr)) {
+                    Some(object_addr)
+                } else {
+                    None
+                }
+            });
+    }
+
+    fn sweep(&mut self) {
+        let mut free_blocks = Vec::new();
+        let mut holes = Vec::new();
+        let mut block = 0;
+
+        while block < self.space.blocks.len() {
+            match self.space.blocks[block] {
+                BlockState::Free => {
+                    free_blocks.push(block);
+                    block += 1;
+                }
+
+                BlockState::Large(count) => {
+                    let start = self.space.block_region(block).start;
+
+                    if !self.space.mark_bits.contains(self.space.mark_index(start)) {
+                        for large_block in block..block + count {
+                            self.space.blocks[large_block] = BlockState::Free;
+                            free_blocks.push(large_block);
+                        }
+                    }
+
+                    block += count;
+                }
+
+                BlockState::LargeTail => unreachable!(),
+
+                BlockState::Used(_) => {
+                    let live_lines = self.sweep_block(block, &mut holes);
+
+                    if live_lines == 0 {
+                        self.space.blocks[block] = BlockState::Free;
+                        free_blocks.push(block);
+                    } else {
+                        self.space.blocks[block] = BlockState::Used(live_lines);
+                    }
+
+                    block += 1;
+                }
+            }
+        }
+
+        free_blocks.reverse();
+        holes.reverse();
+
+        self.space.free_blocks = free_blocks;
+        self.space.holes.clear();
+        self.space.small_holes.clear();
+        self.space.current = Region::new(self.heap.start, self.heap.start);
+
+        for hole in holes {
+            self.space.add_hole(hole);
+        }
+    }
+
+    fn sweep_block(&mut self, block: usize, holes: &mut Vec<Region>) -> usize {
+        let region = self.space.block_region(block);
+        let first_line = self.space.line_index(region.start);
+        let mut block_holes = Vec::new();
+        let mut hole_start = None;
+        let mut live_lines = 0;
+
+        for line in 0..LINES_PER_BLOCK {
+            let line_start = region.start.offset(line * LINE_SIZE);
+
+            if self.space.line_marks.contains(first_line + line) {
+                live_lines += 1;
+
+                if let Some(start) = hole_start.take() {
+                    block_holes.push(Region::new(start, line_start));
+                }
+            } else if hole_start.is_none() {
+                hole_start = Some(line_start);
+            }
+        }
+
+        if let Some(start) = hole_start {
+            block_holes.push(Region::new(start, region.end));
+        }
+
+        // empty blocks are returned as free blocks
+        if live_lines > 0 {
+            holes.extend(block_holes);
+        }
+
+        live_lines
+    }
+
+    fn verify(&self, phase: &str, after_marking: bool) {
+        let mut visited = FixedBitSet::with_capacity(self.space.mark_bits.len());
+        let mut stack = Vec::new();
+
+        for &root in self.rootset {
+            self.verify_ref(root, phase, after_marking, &mut visited, &mut stack);
+        }
+
+        while let Some(object_addr) = stack.pop() {
+            let object = object_addr.to_mut_obj();
+
+            object.visit_reference_fields(|field| {
+                self.verify_ref(field, phase, after_marking, &mut visited, &mut stack);
+            });
+        }
+    }
+
+    fn verify_ref(
+        &self,
+        slot: Slot,
+        phase: &str,
+        after_marking: bool,
+        visited: &mut FixedBitSet,
+        stack: &mut Vec<Address>,
+    ) {
+        let object_addr = slot.get();
+
+        if object_addr.is_null() || self.perm.contains(object_addr) {
+            return;
+        }
+
+        assert!(
+            self.heap.contains(object_addr),
+            "region verify {}: reference {} in slot {} outside of heap",
+            phase,
+            object_addr,
+            slot.address()
+        );
+
+        assert!(
+            self.space.is_allocated(object_addr),
+            "region verify {}: reference {} in slot {} points into free block",
+            phase,
+            object_addr,
+            slot.address()
+        );
+
+        let object = object_addr.to_obj();
+
+        assert!(
+            object.header().vtblptr_forwarded().is_none()
+                && object.header().vtblptr().is_non_null(),
+            "region verify {}: reference {} in slot {} points to invalid object",
+            phase,
+            object_addr,
+            slot.address()
+        );
+
+        if after_marking {
+            let mark_index = self.space.mark_index(object_addr);
+            let line = self.space.line_index(object_addr);
+            let large = match self.space.blocks[self.space.block_index(object_addr)] {
+                BlockState::Large(_) => true,
+                _ => false,
+            };
+
+            assert!(
+                self.space.mark_bits.contains(mark_index)
+                    && (large || self.space.line_marks.contains(line)),
+                "region verify {}: reference {} in slot {} points to unmarked object",
+                phase,
+                object_addr,
+                slot.address()
+            );
+        }
+
+        if !visited.put(self.space.mark_index(object_addr)) {
+            stack.push(object_addr);
+        }
+    }
+}
@@ -13,8 +13,12 @@ pub fn get_rootset(vm: &VM, threads: &[Arc<DoraThread>]) -> Vec<Slot> {
 
     determine_rootset_from_stack(&mut rootset, vm, threads);
     determine_rootset_from_handles(&mut rootset, threads);
+    determine_rootset_from_thread_objects(&mut rootset, threads);
+    determine_rootset_from_fibers(&mut rootset, vm);
 
     determine_rootset_from_globals(&mut rootset, vm);
+    determine_rootset_from_thread_locals(&mut rootset, vm, threads);
+    vm.gc.pinned_objects().roots(&mut rootset);
 
     rootset
 }
@@ -28,70 +32,122 @@ fn determine_rootset_from_handles(rootset: &mut Vec<Slot>, threads: &[Arc<DoraTh
     }
 }
 
+fn determine_rootset_from_thread_objects(rootset: &mut Vec<Slot>, threads: &[Arc<DoraThread>]) {
+    for thread in threads {
+        if thread.object().is_non_null() {
+            rootset.push(Slot::at(thread.object_slot()));
+        }
+    }
+}
+
+// Frames of running fibers are reachable from the thread running the
+// fiber, only suspended fibers need to be scanned here.
+fn determine_rootset_from_fibers(rootset: &mut Vec<Slot>, vm: &VM) {
+    vm.threads.fibers.each(|fiber| {
+        rootset.push(Slot::at(fiber.object_slot()));
+
+        let dtn = Address::from_ptr(fiber.dtn());
+        determine_rootset_from_stack_for_thread(rootset, vm, dtn);
+    });
+}
+
 fn determine_rootset_from_globals(rootset: &mut Vec<Slot>, vm: &VM) {
     for glob in vm.globals.iter() {
         let glob = glob.read();
 
-        match glob.ty {
-            SourceType::Struct(struct_id, type_params_id) => {
-                let type_params = vm.source_type_arrays.lock().get(type_params_id);
-                let sdef_id = specialize_struct_id_params(vm, struct_id, type_params);
-                let sdef = vm.struct_defs.idx(sdef_id);
+        if glob.is_thread_local {
+            continue;
+        }
 
-                for &offset in &sdef.ref_fields {
-                    let slot_address = glob.address_value.offset(offset as usize);
-                    let slot = Slot::at(slot_address);
-                    rootset.push(slot);
-                }
-            }
+        determine_rootset_from_value(rootset, vm, &glob.ty, glob.address_value);
+    }
+}
 
-            SourceType::Enum(enum_id, type_params_id) => {
-                let type_params = vm.source_type_arrays.lock().get(type_params_id);
-                let edef_id = specialize_enum_id_params(vm, enum_id, type_params);
-                let edef = vm.enum_defs.idx(edef_id);
-
-                match edef.layout {
-                    EnumLayout::Int => {}
-                    EnumLayout::Ptr | EnumLayout::Tagged => {
-                        let slot = Slot::at(glob.address_value);
-                        rootset.push(slot);
-                    }
-                }
+fn determine_rootset_from_thread_locals(
+    rootset: &mut Vec<Slot>,
+    vm: &VM,
+    threads: &[Arc<DoraThread>],
+) {
+    for glob in vm.globals.iter() {
+        let glob = glob.read();
+
+        if !glob.is_thread_local {
+            continue;
+        }
+
+        for thread in threads {
+            let address = thread.thread_locals().offset(glob.offset_value as usize);
+            determine_rootset_from_value(rootset, vm, &glob.ty, address);
+        }
+    }
+}
+
+// Pushes all references in the value of type `ty` stored at `address`.
+fn determine_rootset_from_value(
+    rootset: &mut Vec<Slot>,
+    vm: &VM,
+    ty: &SourceType,
+    address: Address,
+) {
+    match *ty {
+        SourceType::Struct(struct_id, type_params_id) => {
+            let type_params = vm.source_type_arrays.lock().get(type_params_id);
+            let sdef_id = specialize_struct_id_params(vm, struct_id, type_params);
+            let sdef = vm.struct_defs.idx(sdef_id);
+
+            for &offset in &sdef.ref_fields {
+                let slot_address = address.offset(offset as usize);
+                let slot = Slot::at(slot_address);
+                rootset.push(slot);
             }
+        }
 
-            SourceType::Tuple(tuple_id) => {
-                let tuples = vm.tuples.lock();
-                let tuple = tuples.get_tuple(tuple_id);
+        SourceType::Enum(enum_id, type_params_id) => {
+            let type_params = vm.source_type_arrays.lock().get(type_params_id);
+            let edef_id = specialize_enum_id_params(vm, enum_id, type_params);
+            let edef = vm.enum_defs.idx(edef_id);
 
-                for &offset in tuple.offsets() {
-                    let slot_address = glob.address_value.offset(offset as usize);
-                    let slot = Slot::at(slot_address);
+            match edef.layout {
+                EnumLayout::Int => {}
+                EnumLayout::Ptr | EnumLayout::Tagged => {
+                    let slot = Slot::at(address);
                     rootset.push(slot);
                 }
             }
+        }
+
+        SourceType::Tuple(tuple_id) => {
+            let tuples = vm.tuples.lock();
+            let tuple = tuples.get_tuple(tuple_id);
 
-            SourceType::Unit
-            | SourceType::UInt8
-            | SourceType::Bool
-            | SourceType::Char
-            | SourceType::Int32
-            | SourceType::Int64
-            | SourceType::Float32
-            | SourceType::Float64 => {}
-
-            SourceType::Class(_, _) | SourceType::Trait(_, _) => {
-                let slot = Slot::at(glob.address_value);
+            for &offset in tuple.offsets() {
+                let slot_address = address.offset(offset as usize);
+                let slot = Slot::at(slot_address);
                 rootset.push(slot);
             }
+        }
 
-            SourceType::TypeParam(_)
-            | SourceType::Error
-            | SourceType::Any
-            | SourceType::This
-            | SourceType::Module(_)
-            | SourceType::Lambda(_)
-            | SourceType::Ptr => unreachable!(),
+        SourceType::Unit
+        | SourceType::UInt8
+        | SourceType::Bool
+        | SourceType::Char
+        | SourceType::Int32
+        | SourceType::Int64
+        | SourceType::Float32
+        | SourceType::Float64 => {}
+
+        SourceType::Class(_, _) | SourceType::Trait(_, _) => {
+            let slot = Slot::at(address);
+            rootset.push(slot);
         }
+
+        SourceType::TypeParam(_)
+        | SourceType::Error
+        | SourceType::Any
+        | SourceType::This
+        | SourceType::Module(_)
+        | SourceType::Lambda(_)
+        | SourceType::Ptr => unreachable!(),
     }
 }
 
@@ -170,6 +226,10 @@ fn determine_rootset(rootset: &mut Vec<Slot>, vm: &VM, fp: usize, pc: usize) ->
         Some(CodeDescriptor::GuardCheckStub) => true,
         Some(CodeDescriptor::CompileStub) => true,
 
+        // frames below a trap are never resumed, threads that catch traps
+        // leave them through the catch stub
+        Some(CodeDescriptor::TrapStub) => false,
+
         _ => {
             println!("data = {:?}", data);
             panic!("invalid stack frame");
@@ -6,8 +6,10 @@ use crate::gc::marking;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::space::Space;
 use crate::gc::tlab;
+use crate::gc::weak::WeakRefLocation;
 use crate::gc::{
-    fill_region_with_free, formatted_size, Address, CollectionStats, Collector, GcReason, Region,
+    fill_region_with_free, formatted_size, Address, CollectionStats, Collector, GcReason,
+    HeapStats, Region,
 };
 use crate::os;
 use crate::safepoint;
@@ -79,7 +81,7 @@ impl Collector for SweepCollector {
     }
 
     fn collect(&self, vm: &VM, reason: GcReason) {
-        let mut timer = Timer::new(vm.args.flag_gc_stats);
+        let mut timer = Timer::new(true);
 
         safepoint::stop_the_world(vm, |threads| {
             tlab::make_iterable_all(vm, threads);
@@ -87,17 +89,32 @@ impl Collector for SweepCollector {
             self.mark_sweep(vm, &rootset, reason);
         });
 
-        if vm.args.flag_gc_stats {
-            let duration = timer.stop();
-            let mut stats = self.stats.lock();
-            stats.add(duration);
-        }
+        let duration = timer.stop();
+        let mut stats = self.stats.lock();
+        stats.add(duration);
     }
 
     fn minor_collect(&self, vm: &VM, reason: GcReason) {
         self.collect(vm, reason);
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        let stats = self.stats.lock();
+        let top = self.alloc.lock().top;
+
+        // memory in the free list below top is counted as used
+        HeapStats {
+            old_used: top.offset_from(self.heap.start),
+            old_committed: self.heap.size(),
+            full_collections: stats.collections(),
+            full_pause: stats.pause(),
+            min_heap_size: self.heap.size(),
+            max_heap_size: self.heap.size(),
+            old_limit: self.heap.size(),
+            ..Default::default()
+        }
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let stats = self.stats.lock();
         let (mutator, gc) = stats.percentage(runtime);
@@ -174,6 +191,7 @@ impl<'a> MarkSweep<'a> {
         }
 
         self.mark();
+        self.process_weak_refs();
 
         if dev_verbose {
             println!("Sweep GC: Phase 2 (sweep)");
@@ -190,6 +208,23 @@ impl<'a> MarkSweep<'a> {
         marking::start(self.rootset, self.heap, self.perm_space.total());
     }
 
+    fn process_weak_refs(&mut self) {
+        let heap = self.heap;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Current, |object_addr| {
+                if !heap.contains(object_addr)
+                    || object_addr.to_obj().header().is_marked_non_atomic()
+                {
+                    Some(object_addr)
+                } else {
+                    None
+                }
+            });
+    }
+
     fn sweep(&mut self) {
         let start = self.heap.start;
         let end = self.heap.end;
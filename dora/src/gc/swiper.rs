@@ -8,6 +8,7 @@ use crate::driver::cmd::Args;
 use crate::gc::root::{get_rootset, Slot};
 use crate::gc::swiper::card::CardTable;
 use crate::gc::swiper::compact::FullCollector;
+use crate::gc::swiper::concurrent::ConcurrentMarker;
 use crate::gc::swiper::controller::{HeapConfig, SharedHeapConfig};
 use crate::gc::swiper::crossing::CrossingMap;
 use crate::gc::swiper::large::LargeSpace;
@@ -20,15 +21,18 @@ use crate::gc::swiper::young::YoungGen;
 use crate::gc::tlab;
 use crate::gc::Collector;
 use crate::gc::{align_gen, fill_region, formatted_size, Address, Region, K};
-use crate::gc::{GcReason, GEN_SIZE};
+use crate::gc::{GcReason, HeapStats, GEN_SIZE};
+use crate::handle::Handle;
 use crate::mem;
 use crate::object::Obj;
 use crate::os::{self, MemoryPermission};
 use crate::safepoint;
+use crate::threads::DoraThread;
 use crate::vm::VM;
 
 pub mod card;
 mod compact;
+mod concurrent;
 mod controller;
 mod crossing;
 mod large;
@@ -79,6 +83,9 @@ pub struct Swiper {
 
     threadpool: Option<Mutex<Pool>>,
     config: SharedHeapConfig,
+
+    // only available with concurrent marking enabled
+    concurrent_marker: Option<ConcurrentMarker>,
 }
 
 impl Swiper {
@@ -186,8 +193,16 @@ impl Swiper {
             None
         };
 
+        let heap = Region::new(heap_start, heap_end);
+
+        let concurrent_marker = if args.flag_gc_concurrent_mark {
+            Some(ConcurrentMarker::new(heap, &young, &large))
+        } else {
+            None
+        };
+
         Swiper {
-            heap: Region::new(heap_start, heap_end),
+            heap,
             reserved_area,
             unaligned_reserved,
 
@@ -206,9 +221,17 @@ impl Swiper {
             max_heap_size,
 
             threadpool,
+            concurrent_marker,
         }
     }
 
+    fn concurrent_marking_active(&self) -> bool {
+        self.concurrent_marker
+            .as_ref()
+            .map(|marker| marker.is_active())
+            .unwrap_or(false)
+    }
+
     fn perform_collection_and_choose(&self, vm: &VM, reason: GcReason) -> CollectionKind {
         let kind = controller::choose_collection_kind(&self.config, &vm.args, &self.young);
         self.perform_collection(vm, kind, reason)
@@ -226,22 +249,38 @@ impl Swiper {
             tlab::make_iterable_all(vm, threads);
             let rootset = get_rootset(vm, threads);
 
-            let kind = match kind {
-                CollectionKind::Minor => {
-                    let promotion_failed = self.minor_collect(vm, reason, &rootset);
+            let kind = if self.concurrent_marking_active() {
+                // minor collections are not possible while marking, the
+                // collection finishes marking and compacts the heap instead
+                self.concurrent_marker
+                    .as_ref()
+                    .unwrap()
+                    .remark(vm, threads, &rootset, &self.young);
 
-                    if promotion_failed {
-                        reason = GcReason::PromotionFailure;
-                        self.full_collect(vm, reason, &rootset);
-                        CollectionKind::Full
-                    } else {
-                        CollectionKind::Minor
-                    }
+                if vm.args.flag_gc_verbose {
+                    println!("GC: concurrent marking finished");
                 }
 
-                CollectionKind::Full => {
-                    self.full_collect(vm, reason, &rootset);
-                    CollectionKind::Full
+                self.full_collect(vm, reason, &rootset, true);
+                CollectionKind::Full
+            } else {
+                match kind {
+                    CollectionKind::Minor => {
+                        let promotion_failed = self.minor_collect(vm, reason, &rootset);
+
+                        if promotion_failed {
+                            reason = GcReason::PromotionFailure;
+                            self.full_collect(vm, reason, &rootset, false);
+                            CollectionKind::Full
+                        } else {
+                            CollectionKind::Minor
+                        }
+                    }
+
+                    CollectionKind::Full => {
+                        self.full_collect(vm, reason, &rootset, false);
+                        CollectionKind::Full
+                    }
                 }
             };
 
@@ -253,8 +292,19 @@ impl Swiper {
                 &self.large,
                 &vm.args,
                 reason,
+                threads.len(),
             );
 
+            if let Some(ref marker) = self.concurrent_marker {
+                if kind.is_minor() && controller::start_concurrent_marking(&self.config, &vm.args) {
+                    marker.start(vm, threads, &rootset);
+
+                    if vm.args.flag_gc_verbose {
+                        println!("GC: concurrent marking started");
+                    }
+                }
+            }
+
             kind
         })
     }
@@ -298,7 +348,7 @@ impl Swiper {
 
             let promotion_failed = collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_minor(collector.phases());
             }
@@ -321,7 +371,7 @@ impl Swiper {
 
             let promotion_failed = collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_minor(collector.phases());
             }
@@ -342,7 +392,7 @@ impl Swiper {
         promotion_failed
     }
 
-    fn full_collect(&self, vm: &VM, reason: GcReason, rootset: &[Slot]) {
+    fn full_collect(&self, vm: &VM, reason: GcReason, rootset: &[Slot], marked: bool) {
         self.verify(
             vm,
             VerifierPhase::PreFull,
@@ -368,12 +418,13 @@ impl Swiper {
                 rootset,
                 reason,
                 pool.thread_count() as usize,
+                marked,
                 self.min_heap_size,
                 self.max_heap_size,
             );
             collector.collect(&mut pool);
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_full(collector.phases());
             }
@@ -389,12 +440,13 @@ impl Swiper {
                 &vm.gc.perm_space,
                 rootset,
                 reason,
+                marked,
                 self.min_heap_size,
                 self.max_heap_size,
             );
             collector.collect();
 
-            if vm.args.flag_gc_stats {
+            if vm.args.gc_phases() {
                 let mut config = self.config.lock();
                 config.add_full(collector.phases());
             }
@@ -465,12 +517,24 @@ impl Swiper {
         let ptr = self.large.alloc(size);
 
         if !ptr.is_null() {
+            self.record_large_allocation(ptr);
             return ptr;
         }
 
         self.perform_collection(vm, CollectionKind::Full, GcReason::AllocationFailure);
 
-        self.large.alloc(size)
+        let ptr = self.large.alloc(size);
+        self.record_large_allocation(ptr);
+        ptr
+    }
+
+    fn record_large_allocation(&self, object: Address) {
+        if object.is_non_null() && self.concurrent_marking_active() {
+            self.concurrent_marker
+                .as_ref()
+                .unwrap()
+                .record_large_allocation(object);
+        }
     }
 }
 
@@ -512,6 +576,16 @@ impl Collector for Swiper {
         }
     }
 
+    fn pin(&self, vm: &VM, object: Handle<Obj>) {
+        // Minor collections promote pinned objects, the old generation
+        // and large objects are compacted around pinned objects. If
+        // promotion fails, the following full collection moves the
+        // object into the old generation.
+        while self.young.contains(object.direct().address()) {
+            self.perform_collection(vm, CollectionKind::Minor, GcReason::Pin);
+        }
+    }
+
     fn collect(&self, vm: &VM, reason: GcReason) {
         self.perform_collection(vm, CollectionKind::Full, reason);
     }
@@ -524,10 +598,32 @@ impl Collector for Swiper {
         self.emit_write_barrier
     }
 
+    fn needs_pre_write_barrier(&self) -> bool {
+        self.concurrent_marker.is_some()
+    }
+
+    fn pre_write_barrier(&self, old: Address) {
+        if let Some(ref marker) = self.concurrent_marker {
+            if marker.is_active() {
+                marker.record_overwritten(old);
+            }
+        }
+    }
+
+    fn detach_thread(&self, thread: &DoraThread) {
+        if let Some(ref marker) = self.concurrent_marker {
+            marker.flush_thread(thread);
+        }
+    }
+
     fn card_table_offset(&self) -> usize {
         self.card_table_offset
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        controller::heap_stats(&self.config, &self.young, &self.old, &self.large)
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let config = self.config.lock();
         let total_gc = config.total_minor_pause + config.total_full_pause;
@@ -614,6 +710,10 @@ impl Collector for Swiper {
 
 impl Drop for Swiper {
     fn drop(&mut self) {
+        if let Some(ref marker) = self.concurrent_marker {
+            marker.stop();
+        }
+
         os::free(
             self.unaligned_reserved.start,
             self.unaligned_reserved.size(),
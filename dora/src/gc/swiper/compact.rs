@@ -11,7 +11,8 @@ use crate::gc::swiper::large::LargeSpace;
 use crate::gc::swiper::old::{OldGen, OldGenProtected};
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{walk_region, walk_region_and_skip_garbage};
-use crate::gc::{Address, GcReason, Region};
+use crate::gc::weak::WeakRefLocation;
+use crate::gc::{fill_region, Address, GcReason, Region};
 use crate::object::Obj;
 use crate::stdlib;
 use crate::timer::Timer;
@@ -34,8 +35,17 @@ pub struct FullCollector<'a> {
     old_committed: Region,
     init_old_top: Vec<Address>,
 
+    // pinned objects in the old generation in ascending order and the
+    // unused gaps in front of them
+    pinned: Vec<Address>,
+    pinned_gaps: Vec<Region>,
+    next_pinned_gap: usize,
+
     reason: GcReason,
 
+    // all live objects are already marked by concurrent marking
+    marked: bool,
+
     min_heap_size: usize,
     max_heap_size: usize,
 
@@ -54,6 +64,7 @@ impl<'a> FullCollector<'a> {
         perm_space: &'a Space,
         rootset: &'a [Slot],
         reason: GcReason,
+        marked: bool,
         min_heap_size: usize,
         max_heap_size: usize,
     ) -> FullCollector<'a> {
@@ -76,7 +87,12 @@ impl<'a> FullCollector<'a> {
             old_committed: Default::default(),
             init_old_top: Vec::new(),
 
+            pinned: Vec::new(),
+            pinned_gaps: Vec::new(),
+            next_pinned_gap: 0,
+
             reason,
+            marked,
 
             min_heap_size,
             max_heap_size,
@@ -91,8 +107,16 @@ impl<'a> FullCollector<'a> {
 
     pub fn collect(&mut self) {
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
         self.init_old_top = self.old_protected.regions.iter().map(|r| r.top()).collect();
+        self.pinned = self
+            .vm
+            .gc
+            .pinned_objects()
+            .objects()
+            .into_iter()
+            .filter(|&object| self.old.total().contains(object))
+            .collect();
 
         let mut timer = Timer::new(stats);
 
@@ -139,6 +163,7 @@ impl<'a> FullCollector<'a> {
             println!("Full GC: Phase 2 (compute forward)");
         }
 
+        self.process_weak_refs();
         self.update_references();
 
         if stats {
@@ -179,13 +204,21 @@ impl<'a> FullCollector<'a> {
     }
 
     fn mark_live(&mut self) {
+        if self.marked {
+            return;
+        }
+
         marking::start(self.rootset, self.heap, self.perm_space.total());
     }
 
     fn compute_forward(&mut self) {
-        self.walk_old_and_young_and_skip_garbage(|full, object, _address, object_size| {
+        self.walk_old_and_young_and_skip_garbage(|full, object, address, object_size| {
             if object.header().is_marked_non_atomic() {
-                let fwd = full.allocate(object_size);
+                let fwd = if full.pinned.binary_search(&address).is_ok() {
+                    full.allocate_pinned(address, object_size)
+                } else {
+                    full.allocate(object_size)
+                };
                 object.header_mut().set_fwdptr_non_atomic(fwd);
                 true
             } else {
@@ -194,7 +227,7 @@ impl<'a> FullCollector<'a> {
         });
 
         if !self.fits_into_heap() {
-            stdlib::trap(Trap::OOM.int());
+            stdlib::fatal_trap(Trap::OOM);
         }
 
         self.old_protected.commit_single_region(self.old_top);
@@ -261,6 +294,16 @@ impl<'a> FullCollector<'a> {
                 let dest = object.header().fwdptr_non_atomic();
                 debug_assert!(full.old_committed.contains(dest));
 
+                // all objects in front of a pinned object were already
+                // relocated, now the gap in front of it can be filled
+                if let Some(&gap) = full.pinned_gaps.get(full.next_pinned_gap) {
+                    if gap.end == address {
+                        fill_region(full.vm, gap.start, gap.end);
+                        full.old.update_crossing(gap.start, gap.end, false);
+                        full.next_pinned_gap += 1;
+                    }
+                }
+
                 // determine location after relocated object
                 let next_dest = dest.offset(object_size);
                 debug_assert!(full.old_committed.valid_top(next_dest));
@@ -293,6 +336,31 @@ impl<'a> FullCollector<'a> {
         }
     }
 
+    fn process_weak_refs(&mut self) {
+        let heap = self.heap;
+        let large_space = self.large_space;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Current, |object_addr| {
+                if !heap.contains(object_addr) {
+                    return Some(object_addr);
+                }
+
+                let object = object_addr.to_obj();
+
+                if !object.header().is_marked_non_atomic() {
+                    None
+                } else if large_space.contains(object_addr) {
+                    // large objects do not move in memory
+                    Some(object_addr)
+                } else {
+                    Some(object.header().fwdptr_non_atomic())
+                }
+            });
+    }
+
     fn forward_reference(&mut self, slot: Slot) {
         let object_addr = slot.get();
 
@@ -395,6 +463,17 @@ impl<'a> FullCollector<'a> {
 
         panic!("FAIL: Not enough space for objects in old generation.");
     }
+
+    fn allocate_pinned(&mut self, object: Address, object_size: usize) -> Address {
+        assert!(self.old_top <= object);
+
+        if self.old_top < object {
+            self.pinned_gaps.push(Region::new(self.old_top, object));
+        }
+
+        self.old_top = object.offset(object_size);
+        object
+    }
 }
 
 pub fn verify_marking(
@@ -0,0 +1,234 @@
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::gc::root::Slot;
+use crate::gc::swiper::large::LargeSpace;
+use crate::gc::swiper::walk_region;
+use crate::gc::swiper::young::YoungGen;
+use crate::gc::{Address, Region};
+use crate::threads::{DoraThread, THREAD};
+use crate::vm::VM;
+
+// number of objects traced before the marker checks for a stop request
+const STOP_CHECK_INTERVAL: usize = 256;
+
+// number of overwritten references a thread buffers before handing them
+// over to the marker
+const SATB_BUFFER_SIZE: usize = 256;
+
+// Snapshot-at-the-beginning marking of the heap concurrent to the mutator.
+//
+// Marking starts at the end of a successful minor collection: all objects
+// reachable at that point form the snapshot. Eden is empty at that time,
+// everything allocated in eden or the large space afterwards is considered
+// live. While marking is active, the mutator records every reference it
+// overwrites in its thread's SATB buffer (pre-write barrier), so that no
+// object of the snapshot is missed. Full buffers are handed over to the
+// marker, the remaining ones are drained in the remark pause. Reading the
+// referent of a WeakRef also records it, since the mutator might store it
+// into an already traced object. Minor collections are not performed during
+// marking, the next collection finishes marking in its pause (remark) and
+// then compacts the heap with the already computed marking.
+pub struct ConcurrentMarker {
+    shared: Arc<SharedState>,
+    marker: Mutex<Option<JoinHandle<()>>>,
+    active: AtomicBool,
+}
+
+struct SharedState {
+    heap: Region,
+    eden: Region,
+    large: Region,
+
+    worklist: Mutex<Vec<Address>>,
+    satb: Mutex<Vec<Address>>,
+
+    // large objects allocated during marking, they might not be initialized
+    // yet and are therefore never traced by the marker
+    allocated_large: Mutex<HashSet<Address>>,
+
+    stop: AtomicBool,
+}
+
+impl ConcurrentMarker {
+    pub fn new(heap: Region, young: &YoungGen, large: &LargeSpace) -> ConcurrentMarker {
+        ConcurrentMarker {
+            shared: Arc::new(SharedState {
+                heap,
+                eden: young.eden_total(),
+                large: large.total(),
+
+                worklist: Mutex::new(Vec::new()),
+                satb: Mutex::new(Vec::new()),
+                allocated_large: Mutex::new(HashSet::new()),
+
+                stop: AtomicBool::new(false),
+            }),
+            marker: Mutex::new(None),
+            active: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    // Starts marking from the given roots. Needs to be invoked while all
+    // threads are stopped and eden is empty.
+    pub fn start(&self, vm: &VM, threads: &[Arc<DoraThread>], rootset: &[Slot]) {
+        assert!(!self.is_active());
+
+        {
+            let mut worklist = self.shared.worklist.lock();
+            assert!(worklist.is_empty());
+
+            for root in rootset {
+                let root_ptr = root.get();
+
+                if self.shared.heap.contains(root_ptr) {
+                    worklist.push(root_ptr);
+                }
+            }
+        }
+
+        self.shared.stop.store(false, Ordering::Relaxed);
+        self.active.store(true, Ordering::Release);
+        vm.threads.set_concurrent_marking(threads, true);
+
+        let shared = self.shared.clone();
+        let marker = thread::Builder::new()
+            .name("concurrent-marker".into())
+            .spawn(move || shared.run())
+            .expect("could not start concurrent marker");
+
+        *self.marker.lock() = Some(marker);
+    }
+
+    // Finishes marking. Afterwards all live objects in the heap are marked.
+    // Needs to be invoked while all threads are stopped and the heap is iterable.
+    pub fn remark(&self, vm: &VM, threads: &[Arc<DoraThread>], rootset: &[Slot], young: &YoungGen) {
+        assert!(self.is_active());
+        self.stop();
+
+        vm.threads.set_concurrent_marking(threads, false);
+        self.active.store(false, Ordering::Release);
+
+        // objects allocated during marking are live
+        walk_region(young.eden_active(), |object, _address, _size| {
+            object.header_mut().mark_non_atomic();
+        });
+
+        let allocated_large =
+            mem::replace(&mut *self.shared.allocated_large.lock(), HashSet::new());
+
+        for address in allocated_large {
+            address.to_mut_obj().header_mut().mark_non_atomic();
+        }
+
+        let mut stack = mem::replace(&mut *self.shared.worklist.lock(), Vec::new());
+        stack.append(&mut *self.shared.satb.lock());
+
+        for thread in threads {
+            stack.append(&mut *thread.satb_buffer().lock());
+        }
+
+        for root in rootset {
+            stack.push(root.get());
+        }
+
+        while let Some(address) = stack.pop() {
+            self.shared.trace(address, &mut stack);
+        }
+    }
+
+    // Stops the marker thread and waits for its termination.
+    pub fn stop(&self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+
+        if let Some(marker) = self.marker.lock().take() {
+            marker.join().expect("concurrent marker failed");
+        }
+    }
+
+    pub fn record_overwritten(&self, old: Address) {
+        THREAD.with(|thread| {
+            let thread = thread.borrow();
+            let mut buffer = thread.satb_buffer().lock();
+            buffer.push(old);
+
+            if buffer.len() >= SATB_BUFFER_SIZE {
+                self.shared.satb.lock().append(&mut *buffer);
+            }
+        });
+    }
+
+    // Hands the buffered references of the thread over to the marker.
+    pub fn flush_thread(&self, thread: &DoraThread) {
+        let mut buffer = thread.satb_buffer().lock();
+
+        if !buffer.is_empty() {
+            self.shared.satb.lock().append(&mut *buffer);
+        }
+    }
+
+    pub fn record_large_allocation(&self, object: Address) {
+        self.shared.allocated_large.lock().insert(object);
+    }
+}
+
+impl SharedState {
+    fn run(&self) {
+        let mut stack = mem::replace(&mut *self.worklist.lock(), Vec::new());
+        let mut traced = 0;
+
+        loop {
+            if let Some(address) = stack.pop() {
+                self.trace(address, &mut stack);
+                traced += 1;
+
+                if traced % STOP_CHECK_INTERVAL == 0 && self.stop.load(Ordering::Relaxed) {
+                    break;
+                }
+            } else {
+                let mut satb = self.satb.lock();
+
+                if satb.is_empty() {
+                    break;
+                }
+
+                stack.append(&mut *satb);
+            }
+        }
+
+        // remaining objects are traced in the remark pause
+        self.worklist.lock().append(&mut stack);
+    }
+
+    fn trace(&self, address: Address, stack: &mut Vec<Address>) {
+        if !self.heap.contains(address) || self.eden.contains(address) {
+            return;
+        }
+
+        if self.large.contains(address) && self.allocated_large.lock().contains(&address) {
+            return;
+        }
+
+        let object = address.to_mut_obj();
+
+        if !object.header().try_mark_non_atomic() {
+            return;
+        }
+
+        object.visit_reference_fields(|field| {
+            let field_addr = field.get();
+
+            if self.heap.contains(field_addr) {
+                stack.push(field_addr);
+            }
+        });
+    }
+}
@@ -2,13 +2,17 @@ use parking_lot::Mutex;
 use std::cmp::{max, min};
 use std::f32;
 use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::sync::Arc;
 
 use crate::driver::cmd::Args;
 use crate::gc::swiper::large::LargeSpace;
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{CollectionKind, CommonOldGen};
-use crate::gc::{align_gen, align_gen_down, formatted_size, AllNumbers, GcReason, GEN_SIZE, M};
+use crate::gc::{
+    align_gen, align_gen_down, formatted_size, AllNumbers, GcReason, HeapStats, GEN_SIZE, M,
+};
 use crate::mem;
 use crate::stdlib;
 use crate::timer;
@@ -18,6 +22,16 @@ const INIT_HEAP_SIZE_RATIO: usize = 2;
 const INIT_YOUNG_RATIO: usize = 4;
 const INIT_SEMI_RATIO: usize = 3;
 
+// factors for adapting the size of young and old generation
+const GROW_FACTOR: f32 = 1.5;
+const SHRINK_FACTOR: f32 = 0.75;
+
+// weight of the last collection in the average GC-time ratio
+const GC_TIME_RATIO_WEIGHT: f32 = 0.3;
+
+// number of consecutive collections with low occupancy before shrinking
+const SHRINK_AFTER_COLLECTIONS: usize = 3;
+
 pub fn init(config: &mut HeapConfig, args: &Args) {
     assert!(config.min_heap_size <= config.max_heap_size);
 
@@ -49,6 +63,12 @@ pub fn init(config: &mut HeapConfig, args: &Args) {
 
     config.old_size = 0;
     config.old_limit = old_limit;
+    config.old_target_limit = old_limit;
+
+    if let Some(ref path) = args.flag_gc_log {
+        let file = File::create(path).expect("couldn't create GC log file");
+        config.log = Some(BufWriter::new(file));
+    }
 }
 
 fn calculate_young_size(args: &Args, young_size: usize, min_semi_size: usize) -> (usize, usize) {
@@ -81,6 +101,13 @@ pub fn choose_collection_kind(
     };
 }
 
+// concurrent marking starts when the old generation exceeds the configured
+// ratio of its limit
+pub fn start_concurrent_marking(config: &SharedHeapConfig, args: &Args) -> bool {
+    let config = config.lock();
+    config.old_size as f64 >= config.old_limit as f64 * args.gc_concurrent_mark_ratio()
+}
+
 pub fn start(
     config: &SharedHeapConfig,
     young: &YoungGen,
@@ -92,6 +119,8 @@ pub fn start(
     config.gc_start = timer::timestamp();
     config.start_object_size = object_size(young, old, large);
     config.start_memory_size = memory_size(young, old, large);
+    config.start_sizes = SpaceSizes::new(young, old, large);
+    config.minor_promoted = 0;
 }
 
 pub fn stop(
@@ -102,11 +131,13 @@ pub fn stop(
     large: &LargeSpace,
     args: &Args,
     reason: GcReason,
+    threads: usize,
 ) {
     let mut config = config.lock();
 
     let gc_end = timer::timestamp();
     config.gc_duration = timer::in_ms(gc_end - config.gc_start);
+    update_gc_time_ratio(&mut *config, gc_end);
 
     assert!(young.eden_active().empty());
     assert!(young.from_active().empty());
@@ -125,6 +156,12 @@ pub fn stop(
     let target_young_size = min(target_young_size, max_young_size);
     let target_young_size = max(target_young_size, GEN_SIZE);
 
+    let target_young_size = if args.young_appel() {
+        adapt_young_size(&mut *config, kind, young, args, target_young_size)
+    } else {
+        target_young_size
+    };
+
     let to_size = young.to_active().size();
     let min_semi_size = align_gen(mem::page_align(to_size) * 2);
 
@@ -132,15 +169,16 @@ pub fn stop(
     let young_size = eden_size + semi_size;
 
     if old_size + young_size > config.max_heap_size {
-        stdlib::trap(Trap::OOM.int());
+        stdlib::fatal_trap(Trap::OOM);
     }
 
     young.set_limit(eden_size, semi_size);
-    config.old_limit = config.max_heap_size - young_size;
+    config.old_limit = adapt_old_limit(&mut *config, kind, args, young_size);
     assert!(config.old_limit >= old_size);
 
     config.end_object_size = object_size(young, old, large);
     config.end_memory_size = memory_size(young, old, large);
+    config.end_sizes = SpaceSizes::new(young, old, large);
 
     assert!(young_size + config.old_limit <= config.max_heap_size);
 
@@ -149,7 +187,7 @@ pub fn stop(
             config.total_minor_collections += 1;
             config.total_minor_pause += config.gc_duration;
 
-            if args.flag_gc_stats {
+            if args.gc_phases() {
                 config.minor_phases.last_mut().unwrap().total = config.gc_duration;
             }
         }
@@ -158,7 +196,7 @@ pub fn stop(
             config.total_full_collections += 1;
             config.total_full_pause += config.gc_duration;
 
-            if args.flag_gc_stats {
+            if args.gc_phases() {
                 config.full_phases.last_mut().unwrap().total = config.gc_duration;
             }
         }
@@ -167,6 +205,255 @@ pub fn stop(
     if args.flag_gc_verbose {
         print(&*config, kind, reason);
     }
+
+    if config.log.is_some() {
+        log(&mut *config, kind, reason, threads);
+    }
+}
+
+pub fn heap_stats(
+    config: &SharedHeapConfig,
+    young: &YoungGen,
+    old: &dyn CommonOldGen,
+    large: &LargeSpace,
+) -> HeapStats {
+    let (eden_size, semi_size) = young.committed_size();
+    let young_used = young.active_size();
+    let old_used = old.active_size();
+    let old_committed = old.committed_size();
+    let large_size = large.committed_size();
+
+    let config = config.lock();
+
+    HeapStats {
+        young_used,
+        young_committed: eden_size + semi_size,
+        old_used,
+        old_committed,
+        large_used: large_size,
+        large_committed: large_size,
+
+        minor_collections: config.total_minor_collections,
+        minor_pause: config.total_minor_pause,
+        full_collections: config.total_full_collections,
+        full_pause: config.total_full_pause,
+
+        min_heap_size: config.min_heap_size,
+        max_heap_size: config.max_heap_size,
+        young_limit: config.max_heap_size - config.old_limit,
+        old_limit: config.old_limit,
+    }
+}
+
+// Updates the average ratio of time spent in collections to total time. The
+// time since the end of the last collection is considered mutator time.
+fn update_gc_time_ratio(config: &mut HeapConfig, gc_end: u64) {
+    let mutator = timer::in_ms(config.gc_start - config.last_gc_end);
+    let total = mutator + config.gc_duration;
+    let ratio = if total > 0.0 {
+        config.gc_duration / total
+    } else {
+        0.0
+    };
+
+    config.gc_time_ratio = if config.total_minor_collections + config.total_full_collections == 0 {
+        ratio
+    } else {
+        GC_TIME_RATIO_WEIGHT * ratio + (1.0 - GC_TIME_RATIO_WEIGHT) * config.gc_time_ratio
+    };
+
+    config.last_gc_end = gc_end;
+}
+
+// Chooses the size of the young generation: grows it when too much time is
+// spent in collections, shrinks it when minor collections exceed the pause
+// target or when few objects survive while the GC-time ratio is low.
+fn adapt_young_size(
+    config: &mut HeapConfig,
+    kind: CollectionKind,
+    young: &YoungGen,
+    args: &Args,
+    max_young_size: usize,
+) -> usize {
+    let (eden_size, semi_size) = young.committed_size();
+    let current_size = eden_size + semi_size;
+    let target_ratio = args.gc_time_ratio();
+
+    let pause_exceeded = kind.is_minor()
+        && args
+            .flag_gc_target_pause
+            .map(|target_pause| config.gc_duration > target_pause)
+            .unwrap_or(false);
+
+    let (young_size, decision) = if pause_exceeded {
+        config.young_low_occupancy = 0;
+        (scale_size(current_size, SHRINK_FACTOR), "pause")
+    } else if config.gc_time_ratio > target_ratio {
+        config.young_low_occupancy = 0;
+        (scale_size(current_size, GROW_FACTOR), "gc-time")
+    } else {
+        let survivors = young.to_active().size();
+        let occupancy = survivors as f32 / current_size as f32;
+
+        if config.gc_time_ratio < target_ratio / 2.0 && occupancy < 1.0 - args.gc_max_free_ratio() {
+            config.young_low_occupancy += 1;
+        } else {
+            config.young_low_occupancy = 0;
+        }
+
+        if config.young_low_occupancy >= SHRINK_AFTER_COLLECTIONS {
+            config.young_low_occupancy = 0;
+            (scale_size(current_size, SHRINK_FACTOR), "low-occupancy")
+        } else {
+            (current_size, "unchanged")
+        }
+    };
+
+    let target_size = young_size;
+    let young_size = min(young_size, max_young_size);
+    let young_size = max(young_size, GEN_SIZE);
+
+    if young_size != current_size {
+        let decision = if young_size < target_size {
+            "heap-limit"
+        } else {
+            decision
+        };
+
+        log_resize(config, args, "young", current_size, young_size, decision);
+    }
+
+    young_size
+}
+
+// Chooses the limit of the old generation, reaching the limit triggers a full
+// collection. The limit grows when full collections take too much time and
+// shrinks after full collections repeatedly left most of it free. Memory
+// freed by full collections is uncommitted by the old generation itself.
+fn adapt_old_limit(
+    config: &mut HeapConfig,
+    kind: CollectionKind,
+    args: &Args,
+    young_size: usize,
+) -> usize {
+    let old_size = config.old_size;
+    let current_limit = config.old_target_limit;
+    let max_old_limit = config.max_heap_size.saturating_sub(young_size);
+
+    if !kind.is_minor() {
+        let target_ratio = args.gc_time_ratio();
+        let max_free_ratio = args.gc_max_free_ratio();
+        let occupancy = old_size as f32 / current_limit as f32;
+
+        let (limit, decision) = if config.gc_time_ratio > target_ratio {
+            config.old_low_occupancy = 0;
+            (scale_size(current_limit, GROW_FACTOR), "gc-time")
+        } else if occupancy < 1.0 - max_free_ratio {
+            config.old_low_occupancy += 1;
+
+            if config.old_low_occupancy >= SHRINK_AFTER_COLLECTIONS {
+                config.old_low_occupancy = 0;
+                let limit = (old_size as f32 / (1.0 - max_free_ratio)) as usize;
+                (align_gen(limit), "low-occupancy")
+            } else {
+                (current_limit, "unchanged")
+            }
+        } else {
+            config.old_low_occupancy = 0;
+            (current_limit, "unchanged")
+        };
+
+        let target_limit = max(limit, old_size + GEN_SIZE);
+        let limit = min(target_limit, max_old_limit);
+
+        if limit != current_limit {
+            let decision = if limit < target_limit {
+                "heap-limit"
+            } else {
+                decision
+            };
+
+            log_resize(config, args, "old", current_limit, limit, decision);
+            config.old_target_limit = limit;
+        }
+    }
+
+    let min_old_limit = if config.min_heap_size > young_size {
+        config.min_heap_size - young_size
+    } else {
+        0
+    };
+
+    let old_limit = max(config.old_target_limit, min_old_limit);
+    let old_limit = max(old_limit, old_size);
+    min(old_limit, max_old_limit)
+}
+
+fn scale_size(size: usize, factor: f32) -> usize {
+    align_gen((size as f32 * factor) as usize)
+}
+
+fn log_resize(
+    config: &HeapConfig,
+    args: &Args,
+    space: &str,
+    old_size: usize,
+    new_size: usize,
+    decision: &str,
+) {
+    if args.flag_gc_verbose {
+        println!(
+            "GC: resize {} {} -> {} ({}; gc-time {:.1}%)",
+            space,
+            formatted_size(old_size),
+            formatted_size(new_size),
+            decision,
+            config.gc_time_ratio * 100.0,
+        );
+    }
+}
+
+fn log(config: &mut HeapConfig, kind: CollectionKind, reason: GcReason, threads: usize) {
+    let phases = match kind {
+        CollectionKind::Minor => {
+            let phases = config.minor_phases.last().unwrap();
+            format!(
+                "{{\"roots\":{},\"tracing\":{},\"total\":{}}}",
+                phases.roots, phases.tracing, phases.total
+            )
+        }
+
+        CollectionKind::Full => {
+            let phases = config.full_phases.last().unwrap();
+            format!(
+                "{{\"marking\":{},\"compute_forward\":{},\"update_refs\":{},\"relocate\":{},\"reset_cards\":{},\"total\":{}}}",
+                phases.marking,
+                phases.compute_forward,
+                phases.update_refs,
+                phases.relocate,
+                phases.reset_cards,
+                phases.total
+            )
+        }
+    };
+
+    let record = format!(
+        "{{\"collection\":{},\"reason\":\"{}\",\"kind\":\"{}\",\"threads\":{},\"pause\":{},\"phases\":{},\"before\":{},\"after\":{},\"promoted\":{}}}\n",
+        config.total_minor_collections + config.total_full_collections,
+        reason,
+        kind,
+        threads,
+        config.gc_duration,
+        phases,
+        config.start_sizes,
+        config.end_sizes,
+        config.minor_promoted,
+    );
+
+    let log = config.log.as_mut().unwrap();
+    log.write_all(record.as_bytes())
+        .and_then(|_| log.flush())
+        .expect("couldn't write GC log");
 }
 
 fn print(config: &HeapConfig, kind: CollectionKind, reason: GcReason) {
@@ -213,6 +500,33 @@ fn memory_size(young: &YoungGen, old: &dyn CommonOldGen, large: &LargeSpace) ->
     young_size + old.committed_size() + large.committed_size()
 }
 
+#[derive(Copy, Clone, Default)]
+struct SpaceSizes {
+    young: usize,
+    old: usize,
+    large: usize,
+}
+
+impl SpaceSizes {
+    fn new(young: &YoungGen, old: &dyn CommonOldGen, large: &LargeSpace) -> SpaceSizes {
+        SpaceSizes {
+            young: young.active_size(),
+            old: old.active_size(),
+            large: large.committed_size(),
+        }
+    }
+}
+
+impl fmt::Display for SpaceSizes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{\"young\":{},\"old\":{},\"large\":{}}}",
+            self.young, self.old, self.large
+        )
+    }
+}
+
 pub struct HeapConfig {
     min_heap_size: usize,
     max_heap_size: usize,
@@ -222,14 +536,28 @@ pub struct HeapConfig {
     pub old_size: usize,
     pub old_limit: usize,
 
+    // target limit of the old generation chosen by the sizing policy
+    old_target_limit: usize,
+
     gc_start: u64,
     gc_duration: f32,
 
+    // end of the last collection and average ratio of collection time
+    last_gc_end: u64,
+    gc_time_ratio: f32,
+
+    // consecutive collections with low occupancy
+    young_low_occupancy: usize,
+    old_low_occupancy: usize,
+
     start_object_size: usize,
     start_memory_size: usize,
     end_object_size: usize,
     end_memory_size: usize,
 
+    start_sizes: SpaceSizes,
+    end_sizes: SpaceSizes,
+
     pub minor_promoted: usize,
     pub minor_copied: usize,
     pub minor_dead: usize,
@@ -241,6 +569,8 @@ pub struct HeapConfig {
 
     full_phases: Vec<FullCollectorPhases>,
     minor_phases: Vec<MinorCollectorPhases>,
+
+    log: Option<BufWriter<File>>,
 }
 
 impl HeapConfig {
@@ -256,14 +586,25 @@ impl HeapConfig {
             old_size: 0,
             old_limit: 0,
 
+            old_target_limit: 0,
+
             gc_start: 0,
             gc_duration: 0f32,
 
+            last_gc_end: timer::timestamp(),
+            gc_time_ratio: 0f32,
+
+            young_low_occupancy: 0,
+            old_low_occupancy: 0,
+
             start_object_size: 0,
             start_memory_size: 0,
             end_object_size: 0,
             end_memory_size: 0,
 
+            start_sizes: SpaceSizes::default(),
+            end_sizes: SpaceSizes::default(),
+
             minor_promoted: 0,
             minor_copied: 0,
             minor_dead: 0,
@@ -275,6 +616,8 @@ impl HeapConfig {
 
             full_phases: Vec::new(),
             minor_phases: Vec::new(),
+
+            log: None,
         }
     }
 
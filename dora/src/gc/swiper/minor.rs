@@ -10,6 +10,7 @@ use crate::gc::swiper::old::{OldGen, OldGenProtected};
 use crate::gc::swiper::on_different_cards;
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{CardIdx, CARD_SIZE};
+use crate::gc::weak::WeakRefLocation;
 use crate::gc::{Address, GcReason, Region};
 use crate::object::{offset_of_array_data, Obj};
 use crate::timer::Timer;
@@ -31,6 +32,7 @@ pub struct MinorCollector<'a> {
     young_top: Address,
     young_limit: Address,
     init_old_top: Vec<Address>,
+    pinned: Vec<Address>,
 
     promotion_failed: bool,
     promoted_size: usize,
@@ -72,6 +74,7 @@ impl<'a> MinorCollector<'a> {
             young_top: Address::null(),
             young_limit: Address::null(),
             init_old_top: Vec::new(),
+            pinned: Vec::new(),
 
             promotion_failed: false,
             promoted_size: 0,
@@ -95,9 +98,17 @@ impl<'a> MinorCollector<'a> {
 
     pub fn collect(&mut self) -> bool {
         self.init_old_top = self.old_protected.regions.iter().map(|r| r.top()).collect();
+        self.pinned = self
+            .vm
+            .gc
+            .pinned_objects()
+            .objects()
+            .into_iter()
+            .filter(|&object| self.young.contains(object))
+            .collect();
 
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let mut timer = Timer::new(self.vm.args.flag_gc_stats);
+        let mut timer = Timer::new(self.vm.args.gc_phases());
 
         self.young.unprotect_from();
         self.young.swap_semi();
@@ -121,7 +132,7 @@ impl<'a> MinorCollector<'a> {
 
         self.visit_dirty_cards();
 
-        if self.vm.args.flag_gc_stats {
+        if self.vm.args.gc_phases() {
             let duration = timer.stop();
             self.phases.roots = duration;
         }
@@ -132,7 +143,7 @@ impl<'a> MinorCollector<'a> {
 
         self.trace_gray_objects();
 
-        if self.vm.args.flag_gc_stats {
+        if self.vm.args.gc_phases() {
             let duration = timer.stop();
             self.phases.tracing = duration;
         }
@@ -141,6 +152,8 @@ impl<'a> MinorCollector<'a> {
             println!("Minor GC: Phase 3 (traverse) finished");
         }
 
+        self.process_weak_refs();
+
         if self.promotion_failed {
             // oh no: promotion failed, we need a subsequent full GC
             self.remove_forwarding_pointers();
@@ -163,6 +176,28 @@ impl<'a> MinorCollector<'a> {
         self.promotion_failed
     }
 
+    fn process_weak_refs(&mut self) {
+        let young = self.young;
+        let promotion_failed = self.promotion_failed;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Forwarded, |object_addr| {
+                if !young.contains(object_addr) {
+                    return Some(object_addr);
+                }
+
+                match object_addr.to_obj().header().vtblptr_forwarded_atomic() {
+                    Ok(fwd_addr) => Some(fwd_addr),
+                    // objects are not copied anymore after promotion failed, keep
+                    // them alive for now. The subsequent full GC cleans this up.
+                    Err(_) if promotion_failed => Some(object_addr),
+                    Err(_) => None,
+                }
+            });
+    }
+
     fn visit_roots(&mut self) {
         // detect all references from roots into young generation
         for &root in self.rootset {
@@ -522,8 +557,11 @@ impl<'a> MinorCollector<'a> {
         let copy_addr = self.young_top;
         let next_young_top = copy_addr.offset(obj_size);
 
-        // if object is old enough we copy it into the old generation
-        if self.young.should_be_promoted(obj_addr) || next_young_top > self.young_limit {
+        // if object is old enough or pinned we copy it into the old generation
+        if self.young.should_be_promoted(obj_addr)
+            || self.pinned.contains(&obj_addr)
+            || next_young_top > self.young_limit
+        {
             return self.promote_object(obj, obj_size);
         }
 
@@ -14,6 +14,7 @@ use crate::gc::swiper::old::{OldGen, OldGenProtected, OldGenRegion};
 use crate::gc::swiper::verify::verify_mapped_regions;
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{walk_region, walk_region_and_skip_garbage, CardIdx, CARD_REFS};
+use crate::gc::weak::WeakRefLocation;
 use crate::gc::{Address, GcReason, Region};
 use crate::os;
 use crate::stdlib;
@@ -37,6 +38,9 @@ pub struct ParallelFullCollector<'a> {
     reason: GcReason,
     number_workers: usize,
 
+    // all live objects are already marked by concurrent marking
+    marked: bool,
+
     min_heap_size: usize,
     max_heap_size: usize,
 
@@ -44,6 +48,9 @@ pub struct ParallelFullCollector<'a> {
     young_units: Vec<Unit>,
     regions: Vec<CollectRegion>,
 
+    // pinned objects in the old generation in ascending order
+    pinned: Vec<Address>,
+
     phases: FullCollectorPhases,
 }
 
@@ -60,6 +67,7 @@ impl<'a> ParallelFullCollector<'a> {
         rootset: &'a [Slot],
         reason: GcReason,
         number_workers: usize,
+        marked: bool,
         min_heap_size: usize,
         max_heap_size: usize,
     ) -> ParallelFullCollector<'a> {
@@ -79,6 +87,7 @@ impl<'a> ParallelFullCollector<'a> {
 
             reason,
             number_workers,
+            marked,
 
             min_heap_size,
             max_heap_size,
@@ -87,6 +96,8 @@ impl<'a> ParallelFullCollector<'a> {
             young_units: Vec::new(),
             regions: Vec::new(),
 
+            pinned: Vec::new(),
+
             phases: FullCollectorPhases::new(),
         }
     }
@@ -97,7 +108,7 @@ impl<'a> ParallelFullCollector<'a> {
 
     pub fn collect(&mut self, pool: &mut Pool) {
         let dev_verbose = self.vm.args.flag_gc_dev_verbose;
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
 
         let mut timer = Timer::new(stats);
 
@@ -144,6 +155,7 @@ impl<'a> ParallelFullCollector<'a> {
             println!("Full GC: Phase 2 (compute forward)");
         }
 
+        self.process_weak_refs();
         self.update_references(pool);
 
         if stats {
@@ -225,6 +237,10 @@ impl<'a> ParallelFullCollector<'a> {
     }
 
     fn mark_live(&mut self, pool: &mut Pool) {
+        if self.marked {
+            return;
+        }
+
         pmarking::start(
             self.rootset,
             self.heap.clone(),
@@ -247,7 +263,7 @@ impl<'a> ParallelFullCollector<'a> {
         let regions: Vec<Region> = self.regions.iter().map(|r| r.mapping).collect();
 
         if !self.fits_into_heap(&regions) {
-            stdlib::trap(Trap::OOM.int());
+            stdlib::fatal_trap(Trap::OOM);
         }
 
         self.compute_actual_forward(pool);
@@ -313,6 +329,16 @@ impl<'a> ParallelFullCollector<'a> {
     }
 
     fn compute_units(&mut self) {
+        let old_total = self.old_total;
+        self.pinned = self
+            .vm
+            .gc
+            .pinned_objects()
+            .objects()
+            .into_iter()
+            .filter(|&object| old_total.contains(object))
+            .collect();
+
         let active = self.old_protected.active_size();
         let unit_size = active / (8 * self.number_workers);
 
@@ -333,6 +359,29 @@ impl<'a> ParallelFullCollector<'a> {
     }
 
     fn units_for_old_region(&mut self, region: Region, unit_size: usize) {
+        let pinned = self
+            .pinned
+            .iter()
+            .cloned()
+            .filter(|&object| region.contains(object))
+            .collect::<Vec<_>>();
+
+        let mut last = region.start;
+
+        // pinned objects are not moved, each of them gets its own unit
+        for object in pinned {
+            self.units_for_range(Region::new(last, object), unit_size);
+
+            let object_end = object.offset(object.to_obj().size());
+            self.units
+                .push(Unit::pinned(Region::new(object, object_end)));
+            last = object_end;
+        }
+
+        self.units_for_range(Region::new(last, region.end), unit_size);
+    }
+
+    fn units_for_range(&mut self, region: Region, unit_size: usize) {
         let mut last = region.start;
 
         while last < region.end {
@@ -418,12 +467,44 @@ impl<'a> ParallelFullCollector<'a> {
         let mut last_mapping_end = self.old_total.start;
 
         for (id, unit) in self.units.iter().enumerate() {
+            if unit.pinned {
+                // Pinned objects need to stay where they are: end the current
+                // region right in front of the object and create a region
+                // with just the pinned object. Independent of the slide
+                // direction its compaction region is the object itself.
+                if start < id || last_span_end < unit.region.start {
+                    self.add_collect_region(
+                        start,
+                        id - start,
+                        unit.region.start,
+                        &mut size,
+                        &mut last_span_end,
+                        &mut last_mapping_end,
+                        &mut regions,
+                    );
+                }
+
+                size = unit.live;
+                self.add_collect_region(
+                    id,
+                    1,
+                    unit.region.end,
+                    &mut size,
+                    &mut last_span_end,
+                    &mut last_mapping_end,
+                    &mut regions,
+                );
+                start = id + 1;
+                continue;
+            }
+
             size += unit.live;
 
             if size > region_size {
                 self.add_collect_region(
                     start,
-                    id,
+                    id - start + 1,
+                    unit.region.end,
                     &mut size,
                     &mut last_span_end,
                     &mut last_mapping_end,
@@ -434,9 +515,10 @@ impl<'a> ParallelFullCollector<'a> {
         }
 
         if start < self.units.len() {
-            let end = self.units.len() - 1;
+            let end = self.units.last().expect("missing unit").region.end;
             self.add_collect_region(
                 start,
+                self.units.len() - start,
                 end,
                 &mut size,
                 &mut last_span_end,
@@ -546,7 +628,8 @@ impl<'a> ParallelFullCollector<'a> {
     fn add_collect_region(
         &self,
         unit_start_idx: usize,
-        unit_end_idx: usize,
+        units: usize,
+        span_end: Address,
         size: &mut usize,
         last_span_end: &mut Address,
         last_mapping_end: &mut Address,
@@ -555,9 +638,6 @@ impl<'a> ParallelFullCollector<'a> {
         let slide_start = regions.len() % 2 == 0;
         let span_start = *last_span_end;
 
-        let unit_end = &self.units[unit_end_idx];
-        let span_end = unit_end.region.end;
-
         let (compact_start, compact_end) = if slide_start {
             (span_start, span_start.offset(*size))
         } else {
@@ -573,7 +653,7 @@ impl<'a> ParallelFullCollector<'a> {
 
         regions.push(CollectRegion::new(
             unit_start_idx,
-            unit_end_idx - unit_start_idx + 1,
+            units,
             slide_start,
             span,
             compact,
@@ -624,6 +704,31 @@ impl<'a> ParallelFullCollector<'a> {
         });
     }
 
+    fn process_weak_refs(&mut self) {
+        let heap = self.heap;
+        let large_space = self.large_space;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Current, |object_addr| {
+                if !heap.contains(object_addr) {
+                    return Some(object_addr);
+                }
+
+                let object = object_addr.to_obj();
+
+                if !object.header().is_marked_non_atomic() {
+                    None
+                } else if large_space.contains(object_addr) {
+                    // large objects do not move in memory
+                    Some(object_addr)
+                } else {
+                    Some(object.header().fwdptr_non_atomic())
+                }
+            });
+    }
+
     fn update_references(&mut self, pool: &mut Pool) {
         let next_large = Mutex::new(Address::null());
         let next_large = &next_large;
@@ -882,6 +987,7 @@ struct Unit {
     region: Region,
     live: usize,
     young: bool,
+    pinned: bool,
 }
 
 impl Unit {
@@ -890,6 +996,7 @@ impl Unit {
             region,
             live: 0,
             young: false,
+            pinned: false,
         }
     }
 
@@ -898,6 +1005,16 @@ impl Unit {
             region,
             live: 0,
             young: true,
+            pinned: false,
+        }
+    }
+
+    fn pinned(region: Region) -> Unit {
+        Unit {
+            region,
+            live: 0,
+            young: false,
+            pinned: true,
         }
     }
 
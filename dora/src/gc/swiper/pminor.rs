@@ -14,6 +14,7 @@ use crate::gc::swiper::on_different_cards;
 use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{CardIdx, CARD_SIZE, LARGE_OBJECT_SIZE};
 use crate::gc::tlab::{TLAB_OBJECT_SIZE, TLAB_SIZE};
+use crate::gc::weak::WeakRefLocation;
 use crate::gc::{fill_region, Address, GcReason, Region};
 use crate::object::{offset_of_array_data, Obj};
 use crate::timer::Timer;
@@ -40,6 +41,7 @@ pub struct ParallelMinorCollector<'a> {
     young_top: Address,
     young_limit: Address,
     init_old_top: Vec<Address>,
+    pinned: Vec<Address>,
 
     promotion_failed: bool,
     promoted_size: usize,
@@ -84,6 +86,7 @@ impl<'a> ParallelMinorCollector<'a> {
             young_top: Address::null(),
             young_limit: Address::null(),
             init_old_top: Vec::new(),
+            pinned: Vec::new(),
 
             promotion_failed: false,
             promoted_size: 0,
@@ -114,6 +117,14 @@ impl<'a> ParallelMinorCollector<'a> {
             let protected = self.old.protected();
             protected.regions.iter().map(|r| r.top()).collect()
         };
+        self.pinned = self
+            .vm
+            .gc
+            .pinned_objects()
+            .objects()
+            .into_iter()
+            .filter(|&object| self.young.contains(object))
+            .collect();
 
         self.young.unprotect_from();
         self.young.swap_semi();
@@ -136,6 +147,8 @@ impl<'a> ParallelMinorCollector<'a> {
             println!("Minor GC: Worker threads finished");
         }
 
+        self.process_weak_refs();
+
         if self.promotion_failed {
             // oh no: promotion failed, we need a subsequent full GC
             self.remove_forwarding_pointers();
@@ -156,12 +169,34 @@ impl<'a> ParallelMinorCollector<'a> {
         self.promotion_failed
     }
 
+    fn process_weak_refs(&mut self) {
+        let young = self.young;
+        let promotion_failed = self.promotion_failed;
+
+        self.vm
+            .gc
+            .weak_refs
+            .process(WeakRefLocation::Forwarded, |object_addr| {
+                if !young.contains(object_addr) {
+                    return Some(object_addr);
+                }
+
+                match object_addr.to_obj().header().vtblptr_forwarded_atomic() {
+                    Ok(fwd_addr) => Some(fwd_addr),
+                    // objects are not copied anymore after promotion failed, keep
+                    // them alive for now. The subsequent full GC cleans this up.
+                    Err(_) if promotion_failed => Some(object_addr),
+                    Err(_) => None,
+                }
+            });
+    }
+
     fn run_threads(&mut self) {
         let mut workers = Vec::with_capacity(self.number_workers);
         let mut stealers = Vec::with_capacity(self.number_workers);
         let injector = Injector::new();
 
-        let stats = self.vm.args.flag_gc_stats;
+        let stats = self.vm.args.gc_phases();
         let timer = Timer::new(stats);
 
         for _ in 0..self.number_workers {
@@ -193,6 +228,7 @@ impl<'a> ParallelMinorCollector<'a> {
         let large = self.large;
         let rootset = self.rootset;
         let init_old_top = &self.init_old_top;
+        let pinned = &self.pinned;
         let old_region_start = {
             let protected = self.old.protected();
             protected
@@ -258,6 +294,7 @@ impl<'a> ParallelMinorCollector<'a> {
                         crossing_map,
                         rootset,
                         init_old_top,
+                        pinned,
                         old_region_start,
                         barrier,
 
@@ -411,6 +448,7 @@ struct CopyTask<'a> {
     crossing_map: &'a CrossingMap,
     rootset: &'a [Slot],
     init_old_top: &'a [Address],
+    pinned: &'a [Address],
     old_region_start: &'a [Address],
     barrier: &'a Barrier,
 
@@ -1037,8 +1075,11 @@ impl<'a> CopyTask<'a> {
             "copy objects only from from-space."
         );
 
-        // If object is old enough we copy it into the old generation
-        if self.copy_failed || self.young.should_be_promoted(obj_addr) {
+        // If object is old enough or pinned we copy it into the old generation
+        if self.copy_failed
+            || self.young.should_be_promoted(obj_addr)
+            || self.pinned.contains(&obj_addr)
+        {
             return self.promote_object(vtblptr, obj, obj_size);
         }
 
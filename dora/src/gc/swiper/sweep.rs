@@ -14,7 +14,7 @@ use crate::gc::swiper::young::YoungGen;
 use crate::gc::swiper::{CollectionKind, CARD_SIZE_BITS, LARGE_OBJECT_SIZE};
 use crate::gc::tlab;
 use crate::gc::{align_gen, formatted_size, GEN_SIZE};
-use crate::gc::{Address, Collector, GcReason, Region};
+use crate::gc::{Address, Collector, GcReason, HeapStats, Region};
 use crate::mem;
 use crate::os::{self, MemoryPermission};
 use crate::safepoint;
@@ -200,6 +200,10 @@ impl Collector for SweepSwiper {
         self.card_table_offset
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        controller::heap_stats(&self.config, &self.young, &self.old, &self.large)
+    }
+
     fn dump_summary(&self, _runtime: f32) {
         unimplemented!()
     }
@@ -254,6 +258,7 @@ impl SweepSwiper {
                 &self.large,
                 &vm.args,
                 reason,
+                threads.len(),
             );
 
             kind
@@ -97,8 +97,11 @@ impl YoungGen {
         self.semi.set_age_marker(to_block.start);
     }
 
+    // Size of all objects in the young generation. Outside of a minor
+    // collection survivors and objects allocated when eden is full are
+    // in the to-space, the from-space is empty.
     pub fn active_size(&self) -> usize {
-        self.eden.active().size() + self.semi.from_block().active().size()
+        self.eden.active().size() + self.semi.to_active().size()
     }
 
     pub fn unprotect_from(&self) {
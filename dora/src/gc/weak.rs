@@ -0,0 +1,92 @@
+use parking_lot::Mutex;
+
+use crate::gc::root::Slot;
+use crate::gc::Address;
+
+// Keeps track of all WeakRef-objects in the heap. The referent of a WeakRef is
+// not part of the reference fields of its class and therefore not traced by
+// the collectors. After liveness of all objects is known, each collector needs
+// to call `process` to update or clear the referents.
+pub struct WeakRefs {
+    entries: Mutex<Vec<WeakRefEntry>>,
+    cleared: Mutex<u64>,
+}
+
+#[derive(Copy, Clone)]
+struct WeakRefEntry {
+    object: Address,
+    offset: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum WeakRefLocation {
+    // objects have not been moved yet, weak references need to be updated
+    // at the current address (e.g. for mark-compact before relocation)
+    Current,
+
+    // objects were already copied, weak references need to be updated
+    // in the copied object (e.g. for copying collectors)
+    Forwarded,
+}
+
+impl WeakRefs {
+    pub fn new() -> WeakRefs {
+        WeakRefs {
+            entries: Mutex::new(Vec::new()),
+            cleared: Mutex::new(0),
+        }
+    }
+
+    pub fn register(&self, object: Address, offset: usize) {
+        let mut entries = self.entries.lock();
+        entries.push(WeakRefEntry { object, offset });
+    }
+
+    // number of referents cleared since start of the VM
+    pub fn cleared(&self) -> u64 {
+        *self.cleared.lock()
+    }
+
+    // `forward` returns the new address of an object if it is still alive,
+    // `None` otherwise. Entries for dead WeakRef-objects are removed, referents
+    // of live WeakRef-objects are either updated or cleared.
+    pub fn process<F>(&self, location: WeakRefLocation, mut forward: F)
+    where
+        F: FnMut(Address) -> Option<Address>,
+    {
+        let mut entries = self.entries.lock();
+        let mut cleared = self.cleared.lock();
+        let mut live_entries = Vec::with_capacity(entries.len());
+
+        for entry in entries.iter() {
+            let fwd_object = match forward(entry.object) {
+                Some(fwd_object) => fwd_object,
+                None => continue,
+            };
+
+            let object = match location {
+                WeakRefLocation::Current => entry.object,
+                WeakRefLocation::Forwarded => fwd_object,
+            };
+
+            let slot = Slot::at(object.offset(entry.offset));
+            let referent = slot.get();
+
+            if referent.is_non_null() {
+                if let Some(fwd_referent) = forward(referent) {
+                    slot.set(fwd_referent);
+                } else {
+                    slot.set(Address::null());
+                    *cleared += 1;
+                }
+            }
+
+            live_entries.push(WeakRefEntry {
+                object: fwd_object,
+                offset: entry.offset,
+            });
+        }
+
+        *entries = live_entries;
+    }
+}
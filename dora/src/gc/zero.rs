@@ -1,6 +1,6 @@
 use crate::driver::cmd::Args;
 use crate::gc::bump::BumpAllocator;
-use crate::gc::{Address, Collector, GcReason, Region};
+use crate::gc::{Address, Collector, GcReason, HeapStats, Region};
 use crate::os::{self, MemoryPermission};
 use crate::vm::VM;
 
@@ -55,6 +55,19 @@ impl Collector for ZeroCollector {
         // do nothing
     }
 
+    fn heap_stats(&self) -> HeapStats {
+        let heap_size = self.end.offset_from(self.start);
+
+        HeapStats {
+            old_used: self.alloc.top().offset_from(self.start),
+            old_committed: heap_size,
+            min_heap_size: heap_size,
+            max_heap_size: heap_size,
+            old_limit: heap_size,
+            ..Default::default()
+        }
+    }
+
     fn dump_summary(&self, runtime: f32) {
         let mutator = runtime;
         let gc = 0.0f32;
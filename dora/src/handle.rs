@@ -1,8 +1,10 @@
 use parking_lot::{Mutex, MutexGuard};
 use std::ops::{Deref, DerefMut};
 
+use crate::gc::Address;
 use crate::object::{Obj, Ref};
 use crate::threads::THREAD;
+use crate::vm::get_vm;
 
 pub const HANDLE_SIZE: usize = 256;
 
@@ -138,6 +140,42 @@ impl<T> Handle<T> {
     pub fn cast<R>(self) -> Handle<R> {
         Handle(self.0 as *mut Ref<R>)
     }
+
+    // Keeps the object at its address until the returned guard is dropped,
+    // e.g. while native code accesses its memory. Pinning might trigger a
+    // collection, so only use the address of the object after this call.
+    pub fn pin(self) -> PinnedHandle<T> {
+        let vm = get_vm();
+        vm.gc.pin(vm, self.cast());
+        PinnedHandle(self)
+    }
+}
+
+pub struct PinnedHandle<T>(Handle<T>);
+
+impl<T> PinnedHandle<T> {
+    pub fn handle(&self) -> Handle<T> {
+        self.0
+    }
+
+    pub fn address(&self) -> Address {
+        self.0.direct().address()
+    }
+}
+
+impl<T> Deref for PinnedHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0.deref()
+    }
+}
+
+impl<T> Drop for PinnedHandle<T> {
+    fn drop(&mut self) {
+        let vm = get_vm();
+        vm.gc.unpin(self.address());
+    }
 }
 
 impl<T> Deref for Handle<T> {
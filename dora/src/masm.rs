@@ -357,6 +357,14 @@ pub enum CondCode {
     UnsignedLessEq,
 }
 
+// rounding of MacroAssembler::float_round()
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FloatRounding {
+    Down,
+    Up,
+    TowardZero,
+}
+
 #[derive(Debug)]
 pub struct ScratchReg {
     ind: u32,
@@ -7,7 +7,7 @@ use crate::cpu::asm::*;
 use crate::cpu::reg::*;
 use crate::cpu::{FReg, Reg};
 use crate::gc::swiper::CARD_SIZE_BITS;
-use crate::masm::{CondCode, Label, MacroAssembler, Mem};
+use crate::masm::{CondCode, FloatRounding, Label, MacroAssembler, Mem};
 use crate::mem::ptr_width;
 use crate::object::{offset_of_array_data, offset_of_array_length, Header};
 use crate::threads::ThreadLocalData;
@@ -124,6 +124,58 @@ impl MacroAssembler {
         self.emit_u32(asm::ldp_post(1, REG_FP, REG_LR, REG_SP, 2));
     }
 
+    // Saves all callee-saved registers on the stack, stores the stack
+    // pointer at [save] and continues on the stack sp by restoring the
+    // callee-saved registers stored there and returning. See
+    // `fiber_stack_pointer()` for the layout expected on a new stack.
+    pub fn switch_stack(&mut self, save: Reg, sp: Reg) {
+        self.push_callee_saved();
+        self.emit_u32(asm::add_imm(1, REG_TMP1, REG_SP, 0, 0));
+        self.emit_u32(asm::strx_imm(REG_TMP1, save, 0));
+        self.emit_u32(asm::add_imm(1, REG_SP, sp, 0, 0));
+        self.pop_callee_saved_and_return();
+    }
+
+    // Saves all callee-saved registers on the stack, stores the stack
+    // pointer at [save] and invokes fct with arg. Switching to the stored
+    // stack pointer with `switch_stack()` returns from this code as well.
+    pub fn call_saving_stack(&mut self, save: Reg, fct: Reg, arg: Reg) {
+        self.push_callee_saved();
+        self.emit_u32(asm::add_imm(1, REG_TMP1, REG_SP, 0, 0));
+        self.emit_u32(asm::strx_imm(REG_TMP1, save, 0));
+        self.copy_reg(MachineMode::Ptr, CCALL_REG_PARAMS[0], arg);
+        self.emit_u32(asm::blr(fct));
+        self.pop_callee_saved_and_return();
+    }
+
+    fn push_callee_saved(&mut self) {
+        for &(first, second) in CALLEE_SAVED_PAIRS.iter().rev() {
+            self.emit_u32(asm::stp_pre(1, first, second, REG_SP, -2));
+        }
+
+        let fregs_size = (CALLEE_SAVED_FREGS.len() * 8) as u32;
+        self.emit_u32(asm::sub_imm(1, REG_SP, REG_SP, fregs_size, 0));
+
+        for (idx, &freg) in CALLEE_SAVED_FREGS.iter().enumerate() {
+            self.emit_u32(asm::strd_imm(freg, REG_SP, idx as u32));
+        }
+    }
+
+    fn pop_callee_saved_and_return(&mut self) {
+        for (idx, &freg) in CALLEE_SAVED_FREGS.iter().enumerate() {
+            self.emit_u32(asm::ldrd_imm(freg, REG_SP, idx as u32));
+        }
+
+        let fregs_size = (CALLEE_SAVED_FREGS.len() * 8) as u32;
+        self.emit_u32(asm::add_imm(1, REG_SP, REG_SP, fregs_size, 0));
+
+        for &(first, second) in CALLEE_SAVED_PAIRS.iter() {
+            self.emit_u32(asm::ldp_post(1, first, second, REG_SP, 2));
+        }
+
+        self.emit_u32(asm::ret());
+    }
+
     pub fn increase_stack_frame(&mut self, size: i32) {
         if size > 0 {
             self.load_int_const(MachineMode::Ptr, REG_TMP1, size as i64);
@@ -675,6 +727,38 @@ impl MacroAssembler {
         self.emit_u32(asm::fneg(dbl, dest, src));
     }
 
+    pub fn float_abs(&mut self, mode: MachineMode, dest: FReg, src: FReg) {
+        let dbl = match mode {
+            MachineMode::Float32 => 0,
+            MachineMode::Float64 => 1,
+            _ => unimplemented!(),
+        };
+
+        self.emit_u32(asm::fabs(dbl, dest, src));
+    }
+
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: FloatRounding,
+    ) {
+        let dbl = match mode {
+            MachineMode::Float32 => 0,
+            MachineMode::Float64 => 1,
+            _ => unimplemented!(),
+        };
+
+        let inst = match rounding {
+            FloatRounding::Down => asm::frintm(dbl, dest, src),
+            FloatRounding::Up => asm::frintp(dbl, dest, src),
+            FloatRounding::TowardZero => asm::frintz(dbl, dest, src),
+        };
+
+        self.emit_u32(inst);
+    }
+
     pub fn float_sqrt(&mut self, mode: MachineMode, dest: FReg, src: FReg) {
         let dbl = match mode {
             MachineMode::Float32 => 0,
@@ -1025,6 +1109,94 @@ impl MacroAssembler {
         }
     }
 
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        self.emit_u32(asm::ldar(size_flag(mode), dest, *address));
+    }
+
+    // Sequentially consistent store, `src` is clobbered. stlr is never
+    // reordered with a later ldar, so this is also the release store.
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.atomic_store_release(mode, mem, src);
+    }
+
+    pub fn atomic_store_release(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let address = self.get_scratch();
+        self.lea(*address, mem);
+        self.emit_u32(asm::stlr(size_flag(mode), src, *address));
+    }
+
+    // Stores `src` and returns the previous value in `src`.
+    pub fn atomic_swap(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.atomic_update(mode, mem, src, |_, _, src| src);
+    }
+
+    // Adds `src` and returns the previous value in `src`.
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        let sum = self.get_scratch();
+
+        self.atomic_update(mode, mem, src, |masm, current, src| {
+            masm.int_add(mode, *sum, current, src);
+            *sum
+        });
+    }
+
+    // Retries storing the value computed by `update` from the current value
+    // until no other thread stored in between. The previous value is returned
+    // in `src`.
+    fn atomic_update<F>(&mut self, mode: MachineMode, mem: Mem, src: Reg, update: F)
+    where
+        F: Fn(&mut MacroAssembler, Reg, Reg) -> Reg,
+    {
+        let sf = size_flag(mode);
+        let address = self.get_scratch();
+        let current = self.get_scratch();
+        let status = self.get_scratch();
+        self.lea(*address, mem);
+
+        let lbl_retry = self.create_label();
+        self.bind_label(lbl_retry);
+        self.emit_u32(asm::ldaxr(sf, *current, *address));
+        let value = update(self, *current, src);
+        self.emit_u32(asm::stlxr(sf, *status, value, *address));
+        self.emit_u32(asm::cmp_imm(0, *status, 0, 0));
+        self.jump_if(CondCode::NotEqual, lbl_retry);
+
+        self.copy_reg(mode, src, *current);
+    }
+
+    // Stores `new` if the current value equals `expected`, which needs to be
+    // REG_RESULT. The previous value is returned in REG_RESULT.
+    pub fn atomic_compare_exchange(
+        &mut self,
+        mode: MachineMode,
+        mem: Mem,
+        expected: Reg,
+        new: Reg,
+    ) {
+        assert_eq!(expected, REG_RESULT);
+
+        let sf = size_flag(mode);
+        let address = self.get_scratch();
+        let current = self.get_scratch();
+        let status = self.get_scratch();
+        self.lea(*address, mem);
+
+        let lbl_retry = self.create_label();
+        let lbl_done = self.create_label();
+        self.bind_label(lbl_retry);
+        self.emit_u32(asm::ldaxr(sf, *current, *address));
+        self.cmp_reg(mode, *current, expected);
+        self.jump_if(CondCode::NotEqual, lbl_done);
+        self.emit_u32(asm::stlxr(sf, *status, new, *address));
+        self.emit_u32(asm::cmp_imm(0, *status, 0, 0));
+        self.jump_if(CondCode::NotEqual, lbl_retry);
+        self.bind_label(lbl_done);
+
+        self.copy_reg(mode, expected, *current);
+    }
+
     pub fn store_zero(&mut self, mode: MachineMode, mem: Mem) {
         self.store_mem(mode, mem, REG_ZERO.into());
     }
@@ -7,8 +7,8 @@ use crate::compiler::codegen::AnyReg;
 use crate::compiler::fct::LazyCompilationSite;
 use crate::cpu::*;
 use crate::gc::swiper::CARD_SIZE_BITS;
-use crate::masm::{CondCode, Label, MacroAssembler, Mem};
-use crate::mem::{fits_i32, ptr_width};
+use crate::masm::{CondCode, FloatRounding, Label, MacroAssembler, Mem};
+use crate::mem::{align_usize, fits_i32, ptr_width, ptr_width_usize};
 use crate::object::{offset_of_array_data, offset_of_array_length, Header};
 use crate::threads::ThreadLocalData;
 use crate::ty::{MachineMode, SourceTypeArray};
@@ -84,6 +84,51 @@ impl MacroAssembler {
         self.asm.popq_r(RBP.into());
     }
 
+    // Pushes all callee-saved registers, stores the stack pointer at
+    // [save] and continues on the stack sp by popping the callee-saved
+    // registers stored there and returning. See `fiber_stack_pointer()`
+    // for the layout expected on a new stack.
+    pub fn switch_stack(&mut self, save: Reg, sp: Reg) {
+        self.push_callee_saved();
+        self.asm
+            .movq_ar(Address::offset(save.into(), 0), RSP.into());
+        self.asm.movq_rr(RSP.into(), sp.into());
+        self.pop_callee_saved_and_return();
+    }
+
+    // Pushes all callee-saved registers, stores the stack pointer at
+    // [save] and invokes fct with arg. Switching to the stored stack
+    // pointer with `switch_stack()` returns from this code as well.
+    pub fn call_saving_stack(&mut self, save: Reg, fct: Reg, arg: Reg) {
+        self.push_callee_saved();
+        self.asm
+            .movq_ar(Address::offset(save.into(), 0), RSP.into());
+
+        // the return address and the callee-saved registers are on the stack
+        let pushed = (CALLEE_SAVED.len() + 1) * ptr_width_usize();
+        let padding = (align_usize(pushed, STACK_FRAME_ALIGNMENT) - pushed) as i64;
+
+        self.asm.subq_ri(RSP.into(), Immediate(padding));
+        self.asm.movq_rr(CCALL_REG_PARAMS[0].into(), arg.into());
+        self.asm.call_r(fct.into());
+        self.asm.addq_ri(RSP.into(), Immediate(padding));
+        self.pop_callee_saved_and_return();
+    }
+
+    fn push_callee_saved(&mut self) {
+        for &reg in CALLEE_SAVED.iter() {
+            self.asm.pushq_r(reg.into());
+        }
+    }
+
+    fn pop_callee_saved_and_return(&mut self) {
+        for &reg in CALLEE_SAVED.iter().rev() {
+            self.asm.popq_r(reg.into());
+        }
+
+        self.asm.retq();
+    }
+
     pub fn increase_stack_frame(&mut self, size: i32) {
         debug_assert!(size as usize % STACK_FRAME_ALIGNMENT == 0);
 
@@ -882,6 +927,62 @@ impl MacroAssembler {
         }
     }
 
+    // Loads are sequentially consistent on x64 as long as all sequentially
+    // consistent stores use atomic_store.
+    pub fn atomic_load(&mut self, mode: MachineMode, dest: Reg, mem: Mem) {
+        self.load_mem(mode, dest.into(), mem);
+    }
+
+    // Sequentially consistent store, `src` is clobbered.
+    pub fn atomic_store(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.atomic_swap(mode, mem, src);
+    }
+
+    // Plain stores already have release semantics on x64.
+    pub fn atomic_store_release(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        self.store_mem(mode, mem, src.into());
+    }
+
+    // Stores `src` and returns the previous value in `src`.
+    pub fn atomic_swap(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        match mode {
+            MachineMode::Int32 => self.asm.xchgl_ar(address_from_mem(mem), src.into()),
+            MachineMode::Int64 | MachineMode::Ptr => {
+                self.asm.xchgq_ar(address_from_mem(mem), src.into())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Adds `src` and returns the previous value in `src`.
+    pub fn atomic_fetch_add(&mut self, mode: MachineMode, mem: Mem, src: Reg) {
+        match mode {
+            MachineMode::Int32 => self.asm.lock_xaddl_ar(address_from_mem(mem), src.into()),
+            MachineMode::Int64 => self.asm.lock_xaddq_ar(address_from_mem(mem), src.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    // Stores `new` if the current value equals `expected`, which needs to be
+    // REG_RESULT. The previous value is returned in REG_RESULT.
+    pub fn atomic_compare_exchange(
+        &mut self,
+        mode: MachineMode,
+        mem: Mem,
+        expected: Reg,
+        new: Reg,
+    ) {
+        assert_eq!(expected, REG_RESULT);
+
+        match mode {
+            MachineMode::Int32 => self.asm.lock_cmpxchgl_ar(address_from_mem(mem), new.into()),
+            MachineMode::Int64 | MachineMode::Ptr => {
+                self.asm.lock_cmpxchgq_ar(address_from_mem(mem), new.into())
+            }
+            _ => unreachable!(),
+        }
+    }
+
     pub fn store_zero(&mut self, mode: MachineMode, mem: Mem) {
         match mode {
             MachineMode::Int8 => self.asm.movb_ai(address_from_mem(mem), Immediate(0)),
@@ -1112,6 +1213,62 @@ impl MacroAssembler {
         }
     }
 
+    pub fn float_abs(&mut self, mode: MachineMode, dest: FReg, src: FReg) {
+        let (fst, snd) = if mode == MachineMode::Float32 {
+            (i32::max_value(), 0)
+        } else {
+            (-1, i32::max_value())
+        };
+
+        // align MMX data to 16 bytes
+        self.dseg.align(16);
+        self.dseg.add_i32(0);
+        self.dseg.add_i32(0);
+        self.dseg.add_i32(snd);
+        let disp = self.dseg.add_i32(fst);
+
+        if dest != src {
+            self.copy_freg(mode, dest, src);
+        }
+
+        let pos = self.pos() as i32;
+
+        let xmm_reg: XmmRegister = dest.into();
+
+        let inst_size = 7
+            + if mode == MachineMode::Float64 { 1 } else { 0 }
+            + if xmm_reg.needs_rex() { 1 } else { 0 };
+
+        let address = Address::rip(-(disp + pos + inst_size));
+
+        match mode {
+            MachineMode::Float32 => self.asm.andps_ra(dest.into(), address),
+            MachineMode::Float64 => self.asm.andpd_ra(dest.into(), address),
+            _ => unimplemented!(),
+        }
+    }
+
+    // requires SSE 4.1
+    pub fn float_round(
+        &mut self,
+        mode: MachineMode,
+        dest: FReg,
+        src: FReg,
+        rounding: FloatRounding,
+    ) {
+        let rounding = match rounding {
+            FloatRounding::Down => 1,
+            FloatRounding::Up => 2,
+            FloatRounding::TowardZero => 3,
+        };
+
+        match mode {
+            MachineMode::Float32 => self.asm.roundss_ri(dest.into(), src.into(), rounding),
+            MachineMode::Float64 => self.asm.roundsd_ri(dest.into(), src.into(), rounding),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn float_sqrt(&mut self, mode: MachineMode, dest: FReg, src: FReg) {
         match mode {
             MachineMode::Float32 => self.asm.sqrtss_rr(dest.into(), src.into()),
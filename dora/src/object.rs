@@ -630,9 +630,9 @@ fn byte_array_alloc_heap(vm: &VM, len: usize) -> Ref<UInt8Array> {
                 + len; // array content
 
     let size = mem::align_usize(size, mem::ptr_width() as usize);
-    let ptr = vm.gc.alloc(vm, size, false);
-
     let clsid = vm.known.byte_array(vm);
+    let ptr = vm.gc.alloc(vm, size, false, clsid);
+
     let cls = vm.class_defs.idx(clsid);
     let vtable = cls.vtable.read();
     let vtable: &VTable = vtable.as_ref().unwrap();
@@ -652,9 +652,9 @@ pub fn int_array_alloc_heap(vm: &VM, len: usize) -> Ref<Int32Array> {
                 + len * 4; // array content
 
     let size = mem::align_usize(size, mem::ptr_width() as usize);
-    let ptr = vm.gc.alloc(vm, size, false);
-
     let clsid = vm.known.int_array(vm);
+    let ptr = vm.gc.alloc(vm, size, false, clsid);
+
     let cls = vm.class_defs.idx(clsid);
     let vtable = cls.vtable.read();
     let vtable: &VTable = vtable.as_ref().unwrap();
@@ -669,25 +669,27 @@ pub fn int_array_alloc_heap(vm: &VM, len: usize) -> Ref<Int32Array> {
 }
 
 fn str_alloc_heap(vm: &VM, len: usize) -> Ref<Str> {
-    str_alloc(vm, len, |vm, size| vm.gc.alloc(vm, size, false))
+    str_alloc(vm, len, |vm, size, clsid| {
+        vm.gc.alloc(vm, size, false, clsid)
+    })
 }
 
 fn str_alloc_perm(vm: &VM, len: usize) -> Ref<Str> {
-    str_alloc(vm, len, |vm, size| vm.gc.alloc_perm(size))
+    str_alloc(vm, len, |vm, size, _clsid| vm.gc.alloc_perm(size))
 }
 
 fn str_alloc<F>(vm: &VM, len: usize, alloc: F) -> Ref<Str>
 where
-    F: FnOnce(&VM, usize) -> Address,
+    F: FnOnce(&VM, usize, ClassDefId) -> Address,
 {
     let size = Header::size() as usize      // Object header
                 + mem::ptr_width() as usize // length field
                 + len; // string content
 
     let size = mem::align_usize(size, mem::ptr_width() as usize);
-    let ptr = alloc(vm, size);
-
     let clsid = vm.known.str(vm);
+    let ptr = alloc(vm, size, clsid);
+
     let cls = vm.class_defs.idx(clsid);
     let vtable = cls.vtable.read();
     let vtable: &VTable = vtable.as_ref().unwrap();
@@ -786,7 +788,7 @@ where
                    + mem::ptr_width() as usize    // length field
                    + len * std::mem::size_of::<T>(); // array content
 
-        let ptr = vm.gc.alloc(vm, size, T::REF).to_usize();
+        let ptr = vm.gc.alloc(vm, size, T::REF, clsid).to_usize();
         let cls = vm.class_defs.idx(clsid);
         let vtable = cls.vtable.read();
         let vtable: &VTable = vtable.as_ref().unwrap();
@@ -834,13 +836,21 @@ pub fn alloc(vm: &VM, clsid: ClassDefId) -> Ref<Obj> {
 
     let size = mem::align_usize(size, mem::ptr_width() as usize);
 
-    let ptr = vm.gc.alloc(vm, size, false).to_usize();
+    let ptr = vm.gc.alloc(vm, size, false, clsid).to_usize();
     let vtable = cls_def.vtable.read();
     let vtable: &VTable = vtable.as_ref().unwrap();
     let mut handle: Ref<Obj> = ptr.into();
     handle.header_mut().set_vtblptr(Address::from_ptr(vtable));
     handle.header_mut().clear_fwdptr();
 
+    // the heap is not cleared on allocation, fields need to be null before
+    // the next collection visits the object
+    let header_size = Header::size() as usize;
+
+    unsafe {
+        ptr::write_bytes((ptr + header_size) as *mut u8, 0, size - header_size);
+    }
+
     handle
 }
 
@@ -850,9 +860,7 @@ pub fn write_ref(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, value
     let slot = obj.address().offset(field.offset as usize);
     assert!(field.ty.reference_type());
 
-    unsafe {
-        *slot.to_mut_ptr::<Address>() = value.address();
-    }
+    vm.gc.write_ref(obj.address(), slot, value.address());
 }
 
 pub fn write_int32(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, value: i32) {
@@ -866,12 +874,14 @@ pub fn write_int32(vm: &VM, obj: Ref<Obj>, cls_id: ClassDefId, fid: FieldId, val
     }
 }
 
+#[repr(C)]
 pub struct Stacktrace {
     pub header: Header,
     pub backtrace: Ref<Int32Array>,
     pub elements: Ref<Obj>,
 }
 
+#[repr(C)]
 pub struct StacktraceElement {
     pub header: Header,
     pub name: Ref<Str>,
@@ -1,5 +1,8 @@
+use std::process;
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::stack::stacktrace_from_dtn;
 use crate::stdlib;
 use crate::threads::{DoraThread, ThreadState, THREAD};
 use crate::vm::{get_vm, stack_pointer, Trap, VM};
@@ -38,8 +41,20 @@ fn stop_threads(vm: &VM, threads: &[Arc<DoraThread>]) -> usize {
         thread.tld.set_safepoint_requested();
     }
 
+    let start = Instant::now();
+    let mut reported = false;
+
     while !all_threads_blocked(vm, &thread_self, threads, safepoint_id) {
-        // do nothing
+        if let Some(timeout) = vm.args.safepoint_timeout() {
+            if !reported && start.elapsed() >= timeout {
+                report_safepoint_timeout(vm, &thread_self, threads, safepoint_id);
+                reported = true;
+
+                if vm.args.flag_safepoint_timeout_abort {
+                    process::abort();
+                }
+            }
+        }
     }
 
     safepoint_id
@@ -67,6 +82,63 @@ fn all_threads_blocked(
     all_blocked
 }
 
+// Dumps the state of all threads when a safepoint takes too long. Only the
+// stacks of stopped threads are walked, a running thread might pop the
+// frames while they are read. For those only the last DTN is printed.
+fn report_safepoint_timeout(
+    vm: &VM,
+    thread_self: &Arc<DoraThread>,
+    threads: &[Arc<DoraThread>],
+    safepoint_id: usize,
+) {
+    let timeout = vm.args.flag_safepoint_timeout.expect("no timeout");
+    eprintln!(
+        "safepoint {} not reached by all threads after {}ms:",
+        safepoint_id, timeout
+    );
+
+    for thread in threads {
+        let reached = thread.in_safepoint(safepoint_id);
+        let current = if Arc::ptr_eq(thread, thread_self) {
+            ", requested safepoint"
+        } else if reached {
+            ", in safepoint"
+        } else {
+            ""
+        };
+
+        eprintln!("thread #{}: {:?}{}", thread.id(), thread.state(), current);
+
+        let dtn = thread.dtn();
+
+        if dtn.is_null() {
+            eprintln!("  no DTN: thread didn't call into native code");
+            continue;
+        }
+
+        let (fp, pc) = unsafe { ((*dtn).fp, (*dtn).pc) };
+        let code = vm.code_map.lock().get(pc.into());
+        eprintln!("  last DTN: fp = {:#x}, pc = {:#x} ({:?})", fp, pc, code);
+
+        // the requesting thread runs this code, threads in the safepoint or
+        // parked can't leave before the safepoint is over
+        let stopped = Arc::ptr_eq(thread, thread_self) || reached || thread.state().is_parked();
+
+        if !stopped {
+            eprintln!("  stack not walked: thread didn't stop");
+            continue;
+        }
+
+        let stacktrace = stacktrace_from_dtn(vm, dtn);
+
+        if stacktrace.len() == 0 {
+            eprintln!("  no Dora frames");
+        } else {
+            stacktrace.dump_err(vm);
+        }
+    }
+}
+
 fn resume_threads(vm: &VM, threads: &[Arc<DoraThread>], safepoint_id: usize) {
     for thread in threads.iter() {
         thread.tld.clear_safepoint_requested();
@@ -77,12 +149,14 @@ fn resume_threads(vm: &VM, threads: &[Arc<DoraThread>], safepoint_id: usize) {
 }
 
 pub extern "C" fn guard_check() {
-    let thread = THREAD.with(|thread| thread.borrow().clone());
-    let stack_overflow = thread.tld.real_stack_limit() > stack_pointer();
+    let stack_overflow =
+        THREAD.with(|thread| thread.borrow().tld.real_stack_limit() > stack_pointer());
 
     if stack_overflow {
+        // a caught trap doesn't return, nothing to drop here
         stdlib::trap(Trap::STACK_OVERFLOW.int());
     } else {
+        let thread = THREAD.with(|thread| thread.borrow().clone());
         block(get_vm(), &thread);
     }
 }
@@ -3164,16 +3164,20 @@ impl<'a> TypeCheck<'a> {
                     )
                 };
 
-                if implements_stringable {
+                if !implements_stringable {
+                    let ty = part_expr.name_fct(self.vm, self.fct);
+                    self.vm.diag.lock().report(
+                        self.file_id,
+                        part.pos(),
+                        SemError::ExpectedStringable(ty),
+                    );
+
                     continue;
                 }
 
-                let ty = part_expr.name_fct(self.vm, self.fct);
-                self.vm.diag.lock().report(
-                    self.file_id,
-                    part.pos(),
-                    SemError::ExpectedStringable(ty),
-                );
+                if let Some(ref spec) = e.specs[idx] {
+                    self.check_format_spec(spec, part_expr, part.pos());
+                }
             } else {
                 assert!(part.is_lit_str());
             }
@@ -3185,6 +3189,34 @@ impl<'a> TypeCheck<'a> {
         str_ty
     }
 
+    // Width, fill and alignment are supported for all types, all other
+    // options only for numbers.
+    fn check_format_spec(&mut self, spec: &ast::FormatSpec, ty: SourceType, pos: Position) {
+        let is_int = match ty {
+            SourceType::UInt8 | SourceType::Int32 | SourceType::Int64 => true,
+            _ => false,
+        };
+
+        let valid = if spec.kind.is_radix() {
+            is_int && spec.precision.is_none()
+        } else if spec.kind == ast::FormatKind::LowerExp || spec.precision.is_some() {
+            ty.is_float()
+        } else if spec.sign || spec.zero {
+            is_int || ty.is_float()
+        } else {
+            true
+        };
+
+        if !valid {
+            let ty = ty.name_fct(self.vm, self.fct);
+            self.vm.diag.lock().report(
+                self.file_id,
+                pos,
+                SemError::InvalidFormatSpec(spec.value.clone(), ty),
+            );
+        }
+    }
+
     fn check_expr(&mut self, e: &ast::Expr, expected_ty: SourceType) -> SourceType {
         match *e {
             ast::Expr::LitChar(ref expr) => self.check_expr_lit_char(expr, expected_ty),
@@ -3322,13 +3354,17 @@ fn arg_allows(vm: &VM, def: SourceType, arg: SourceType, self_ty: Option<SourceT
                 return true;
             }
 
-            let other_cls_id;
-            let other_list_id;
+            let other_list_id = match arg {
+                SourceType::Class(other_cls_id, other_list_id) => {
+                    if other_cls_id != cls_id {
+                        // compare against the superclass of arg with the same class
+                        return match arg.superclass_with_id(vm, cls_id) {
+                            Some(superclass) => arg_allows(vm, def, superclass, self_ty),
+                            None => false,
+                        };
+                    }
 
-            match arg {
-                SourceType::Class(cls_id, list_id) => {
-                    other_cls_id = cls_id;
-                    other_list_id = list_id;
+                    other_list_id
                 }
 
                 _ => {
@@ -3339,11 +3375,7 @@ fn arg_allows(vm: &VM, def: SourceType, arg: SourceType, self_ty: Option<SourceT
             let params = vm.source_type_arrays.lock().get(list_id);
             let other_params = vm.source_type_arrays.lock().get(other_list_id);
 
-            if params.len() == 0 && other_params.len() == 0 {
-                return arg.subclass_from(vm, def);
-            }
-
-            if cls_id != other_cls_id || params.len() != other_params.len() {
+            if params.len() != other_params.len() {
                 return false;
             }
 
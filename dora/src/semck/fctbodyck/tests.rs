@@ -1506,6 +1506,31 @@ fn test_template() {
     ok("fun f[T: std::Stringable](x: T): String { return \"${x}\"; }");
 }
 
+#[test]
+fn test_template_format_spec() {
+    ok("fun f(x: Int32): String { return \"x = ${x:08x}\"; }");
+    ok("fun f(x: UInt8): String { return \"x = ${x:b}\"; }");
+    ok("fun f(x: Float64): String { return \"x = ${x:+.3}\"; }");
+    ok("fun f(x: Float32): String { return \"x = ${x:>12.2e}\"; }");
+    ok("fun f(x: String): String { return \"x = ${x:*^8}\"; }");
+    ok("fun f[T: std::Stringable](x: T): String { return \"${x:<8}\"; }");
+    err(
+        "fun f(x: Float64): String { return \"x = ${x:x}\"; }",
+        pos(1, 43),
+        SemError::InvalidFormatSpec("x".into(), "Float64".into()),
+    );
+    err(
+        "fun f(x: Int64): String { return \"x = ${x:.2}\"; }",
+        pos(1, 41),
+        SemError::InvalidFormatSpec(".2".into(), "Int64".into()),
+    );
+    err(
+        "fun f(x: String): String { return \"x = ${x:08}\"; }",
+        pos(1, 42),
+        SemError::InvalidFormatSpec("08".into(), "String".into()),
+    );
+}
+
 #[test]
 fn test_trait_object_as_argument() {
     ok("trait Foo { fun bar(): Int32; }
@@ -2266,6 +2291,49 @@ fn test_methods_with_generics() {
     ");
 }
 
+#[test]
+fn test_generic_subclass_as_argument() {
+    ok("
+        @open @abstract class Foo[A]
+        @open class Bar[A] extends Foo[A]
+        class Baz extends Bar[Int32]
+        fun f(x: Foo[Int32]) {}
+        fun g[T](x: Foo[T]) {}
+        fun test[T]() {
+            f(Bar[Int32]());
+            f(Baz());
+            g[Int32](Baz());
+            g[T](Bar[T]());
+        }
+    ");
+
+    err(
+        "
+        @open @abstract class Foo[A]
+        class Bar[A] extends Foo[A]
+        fun f(x: Foo[Int32]) {}
+        fun test() { f(Bar[String]()); }
+    ",
+        pos(5, 23),
+        SemError::ParamTypesIncompatible(
+            "f".into(),
+            vec!["Foo[Int32]".into()],
+            vec!["Bar[String]".into()],
+        ),
+    );
+
+    err(
+        "
+        @open class Foo
+        fun f(x: Foo) {}
+        fun test[T](x: Bar[T]) { f(x); }
+        class Bar[T]
+    ",
+        pos(4, 35),
+        SemError::ParamTypesIncompatible("f".into(), vec!["Foo".into()], vec!["Bar[T]".into()]),
+    );
+}
+
 #[test]
 fn test_is_types() {
     err(
@@ -2393,6 +2461,18 @@ fn extension_class_with_type_param() {
     );
 }
 
+#[test]
+fn extension_class_prefers_concrete_type() {
+    ok("
+        class Foo[T](let value: T)
+        impl[T] Foo[T] { fun foo(): Int32 { 1 } }
+        impl Foo[Int32] { fun foo(): String { \"concrete\" } }
+        fun f(x: Foo[Int32]): String { x.foo() }
+        fun g(x: Foo[Int64]): Int32 { x.foo() }
+        fun h[T](x: Foo[T]): Int32 { x.foo() }
+    ");
+}
+
 #[test]
 fn extension_class_tuple() {
     ok("
@@ -3644,3 +3724,21 @@ fn trait_object_cast() {
         SemError::TypeNotImplementingTrait("Bar".into(), "Foo".into()),
     );
 }
+
+#[test]
+fn test_atomic_ref_with_value_type() {
+    ok("class Foo fun f() { std::AtomicRef[Foo](Foo()); }");
+    ok("trait Foo {} fun f(x: Foo) { std::AtomicRef[Foo](x); }");
+
+    err(
+        "fun f() { std::AtomicRef[Int32](1); }",
+        pos(1, 32),
+        SemError::ReferenceTypeExpected("Int32".into()),
+    );
+
+    err(
+        "fun f[T](x: T) { std::AtomicRef[T](x); }",
+        pos(1, 35),
+        SemError::ReferenceTypeExpected("T".into()),
+    );
+}
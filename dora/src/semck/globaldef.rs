@@ -281,6 +281,9 @@ impl<'x> visit::Visitor for GlobalDef<'x> {
                 initializer: None,
                 address_init: Address::null(),
                 address_value: Address::null(),
+                is_thread_local: node.is_thread_local,
+                offset_init: 0,
+                offset_value: 0,
             };
 
             globals.push(Arc::new(RwLock::new(global)));
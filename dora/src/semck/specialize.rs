@@ -372,7 +372,10 @@ fn create_specialized_class_regular(
 
         csize = offset + field_size;
 
-        add_ref_fields(vm, &mut ref_fields, offset, ty);
+        // the referent of a WeakRef is not traced by the GC
+        if Some(cls.id) != vm.known.classes.weak_ref {
+            add_ref_fields(vm, &mut ref_fields, offset, ty);
+        }
     }
 
     let size = InstanceSize::Fixed(mem::align_i32(csize, mem::ptr_width()));
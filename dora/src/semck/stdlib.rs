@@ -47,6 +47,10 @@ pub fn resolve_internal_classes(vm: &mut VM) {
     vm.known.classes.stacktrace = Some(find_class(vm, stdlib, "Stacktrace"));
     vm.known.classes.stacktrace_element = Some(find_class(vm, stdlib, "StacktraceElement"));
 
+    vm.known.classes.weak_ref = Some(find_class(vm, stdlib, "WeakRef"));
+    vm.known.classes.pinned = Some(find_class(vm, stdlib, "Pinned"));
+    vm.known.classes.atomic_ref = Some(find_class(vm, stdlib, "AtomicRef"));
+
     vm.known.traits.stringable = find_trait(vm, stdlib, "Stringable");
     vm.known.traits.zero = find_trait(vm, stdlib, "Zero");
     vm.known.traits.iterator = find_trait(vm, stdlib, "Iterator");
@@ -142,6 +146,12 @@ pub fn discover_known_methods(vm: &mut VM) {
     vm.known.functions.string_buffer_append = find_method(vm, stdlib, "StringBuffer", "append");
     vm.known.functions.string_buffer_to_string =
         find_method(vm, stdlib, "StringBuffer", "toString");
+    vm.known.functions.format_uint8 = find_fct(vm, stdlib, "formatUInt8");
+    vm.known.functions.format_int32 = find_fct(vm, stdlib, "formatInt32");
+    vm.known.functions.format_int64 = find_fct(vm, stdlib, "formatInt64");
+    vm.known.functions.format_float32 = find_fct(vm, stdlib, "formatFloat32");
+    vm.known.functions.format_float64 = find_fct(vm, stdlib, "formatFloat64");
+    vm.known.functions.format_string = find_fct(vm, stdlib, "formatString");
 }
 
 fn internal_free_classes(vm: &mut VM) {
@@ -204,6 +214,16 @@ fn find_class(vm: &VM, namespace_id: NamespaceId, name: &str) -> ClassId {
     symtable.get_class(iname).expect("class not found")
 }
 
+fn find_namespace(vm: &VM, namespace_id: NamespaceId, name: &str) -> NamespaceId {
+    let iname = vm.interner.intern(name);
+    let symtable = NestedSymTable::new(vm, namespace_id);
+
+    match symtable.get(iname) {
+        Some(Sym::Namespace(namespace_id)) => namespace_id,
+        _ => panic!("namespace not found"),
+    }
+}
+
 fn internal_class(vm: &VM, namespace_id: NamespaceId, name: &str) -> ClassId {
     let iname = vm.interner.intern(name);
     let symtable = NestedSymTable::new(vm, namespace_id);
@@ -271,6 +291,442 @@ pub fn resolve_internal_functions(vm: &mut VM) {
     );
     native_fct(vm, stdlib, "sleep", stdlib::sleep as *const u8);
 
+    native_fct(vm, stdlib, "formatUInt8", stdlib::format_uint8 as *const u8);
+    native_fct(vm, stdlib, "formatInt32", stdlib::format_int32 as *const u8);
+    native_fct(vm, stdlib, "formatInt64", stdlib::format_int64 as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "formatFloat32",
+        stdlib::format_float32 as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "formatFloat64",
+        stdlib::format_float64 as *const u8,
+    );
+    native_fct(vm, stdlib, "formatString", stdlib::format_str as *const u8);
+
+    native_fct(
+        vm,
+        stdlib,
+        "textToUpperCase",
+        stdlib::text::text_to_upper as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "textToLowerCase",
+        stdlib::text::text_to_lower as *const u8,
+    );
+    native_fct(vm, stdlib, "textToNfc", stdlib::text::text_nfc as *const u8);
+    native_fct(vm, stdlib, "textToNfd", stdlib::text::text_nfd as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "textCompare",
+        stdlib::text::text_compare as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntCompare",
+        stdlib::bigint::bigint_compare as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntAdd",
+        stdlib::bigint::bigint_add as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntSub",
+        stdlib::bigint::bigint_sub as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntMul",
+        stdlib::bigint::bigint_mul as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntDivRem",
+        stdlib::bigint::bigint_div_rem as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntShiftLeft",
+        stdlib::bigint::bigint_shift_left as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntShiftRight",
+        stdlib::bigint::bigint_shift_right as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntBitwise",
+        stdlib::bigint::bigint_bitwise as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntToString",
+        stdlib::bigint::bigint_to_string as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "bigIntParse",
+        stdlib::bigint::bigint_parse as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "sortInt32Array",
+        stdlib::sort_int32_array as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "sortFloat64Array",
+        stdlib::sort_float64_array as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "syncNextKey",
+        stdlib::sync_next_key as *const u8,
+    );
+    native_fct(vm, stdlib, "mutexLock", stdlib::mutex_lock as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "mutexTryLock",
+        stdlib::mutex_try_lock as *const u8,
+    );
+    native_fct(vm, stdlib, "mutexUnlock", stdlib::mutex_unlock as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "conditionWait",
+        stdlib::condition_wait as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "conditionNotify",
+        stdlib::condition_notify as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "conditionNotifyAll",
+        stdlib::condition_notify_all as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "weakRefRegister",
+        stdlib::weak_ref_register as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "weakRefClearedCount",
+        stdlib::weak_ref_cleared_count as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "pinningSupported",
+        stdlib::pinning_supported as *const u8,
+    );
+    native_fct(vm, stdlib, "pinnedPin", stdlib::pinned_pin as *const u8);
+    native_fct(vm, stdlib, "pinnedUnpin", stdlib::pinned_unpin as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "pinnedAddress",
+        stdlib::pinned_address as *const u8,
+    );
+
+    let gc = find_namespace(vm, stdlib, "gc");
+    native_fct(
+        vm,
+        gc,
+        "readHeapStats",
+        stdlib::gc_read_heap_stats as *const u8,
+    );
+
+    native_fct(
+        vm,
+        stdlib,
+        "ioLastErrorKind",
+        stdlib::io::io_last_error_kind as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "ioLastErrorMessage",
+        stdlib::io::io_last_error_message as *const u8,
+    );
+
+    let io = find_namespace(vm, stdlib, "io");
+    native_fct(vm, io, "fileOpen", stdlib::io::file_open as *const u8);
+    native_fct(vm, io, "fileStdin", stdlib::io::file_stdin as *const u8);
+    native_fct(vm, io, "fileRead", stdlib::io::file_read as *const u8);
+    native_fct(vm, io, "fileWrite", stdlib::io::file_write as *const u8);
+    native_fct(
+        vm,
+        io,
+        "fileWriteString",
+        stdlib::io::file_write_string as *const u8,
+    );
+    native_fct(vm, io, "fileSeek", stdlib::io::file_seek as *const u8);
+    native_fct(vm, io, "fileSync", stdlib::io::file_sync as *const u8);
+    native_fct(vm, io, "fileClose", stdlib::io::file_close as *const u8);
+    native_fct(vm, io, "fileRemove", stdlib::io::file_remove as *const u8);
+    native_fct(vm, io, "fileRename", stdlib::io::file_rename as *const u8);
+    native_fct(
+        vm,
+        io,
+        "fileMetadata",
+        stdlib::io::file_metadata as *const u8,
+    );
+    native_fct(vm, io, "dirOpen", stdlib::io::dir_open as *const u8);
+    native_fct(vm, io, "dirNext", stdlib::io::dir_next as *const u8);
+    native_fct(
+        vm,
+        io,
+        "dirEntryName",
+        stdlib::io::dir_entry_name as *const u8,
+    );
+    native_fct(vm, io, "dirCreate", stdlib::io::dir_create as *const u8);
+    native_fct(vm, io, "dirRemove", stdlib::io::dir_remove as *const u8);
+
+    let process = find_namespace(vm, stdlib, "process");
+    native_fct(
+        vm,
+        process,
+        "commandNew",
+        stdlib::process::command_new as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandArg",
+        stdlib::process::command_arg as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandEnv",
+        stdlib::process::command_env as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandEnvRemove",
+        stdlib::process::command_env_remove as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandEnvClear",
+        stdlib::process::command_env_clear as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandCurrentDir",
+        stdlib::process::command_current_dir as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandStdio",
+        stdlib::process::command_stdio as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "commandSpawn",
+        stdlib::process::command_spawn as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childPipe",
+        stdlib::process::child_pipe as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childId",
+        stdlib::process::child_id as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childWait",
+        stdlib::process::child_wait as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childTryWait",
+        stdlib::process::child_try_wait as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childKill",
+        stdlib::process::child_kill as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childOutput",
+        stdlib::process::child_output as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "childOutputBytes",
+        stdlib::process::child_output_bytes as *const u8,
+    );
+    native_fct(
+        vm,
+        process,
+        "closeHandle",
+        stdlib::io::file_close as *const u8,
+    );
+
+    let net = find_namespace(vm, stdlib, "net");
+    native_fct(vm, net, "tcpListen", stdlib::net::tcp_listen as *const u8);
+    native_fct(vm, net, "tcpAccept", stdlib::net::tcp_accept as *const u8);
+    native_fct(
+        vm,
+        net,
+        "tcpSetAcceptTimeout",
+        stdlib::net::tcp_set_accept_timeout as *const u8,
+    );
+    native_fct(vm, net, "tcpConnect", stdlib::net::tcp_connect as *const u8);
+    native_fct(
+        vm,
+        net,
+        "tcpShutdown",
+        stdlib::net::tcp_shutdown as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "tcpSetNoDelay",
+        stdlib::net::tcp_set_nodelay as *const u8,
+    );
+    native_fct(vm, net, "udpBind", stdlib::net::udp_bind as *const u8);
+    native_fct(vm, net, "udpConnect", stdlib::net::udp_connect as *const u8);
+    native_fct(vm, net, "udpSendTo", stdlib::net::udp_send_to as *const u8);
+    native_fct(
+        vm,
+        net,
+        "udpRecvFrom",
+        stdlib::net::udp_recv_from as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "udpLastSender",
+        stdlib::net::udp_last_sender as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "socketLocalAddr",
+        stdlib::net::socket_local_addr as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "socketPeerAddr",
+        stdlib::net::socket_peer_addr as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "socketSetReadTimeout",
+        stdlib::net::socket_set_read_timeout as *const u8,
+    );
+    native_fct(
+        vm,
+        net,
+        "socketSetWriteTimeout",
+        stdlib::net::socket_set_write_timeout as *const u8,
+    );
+    native_fct(vm, net, "socketRead", stdlib::io::file_read as *const u8);
+    native_fct(vm, net, "socketWrite", stdlib::io::file_write as *const u8);
+    native_fct(vm, net, "closeHandle", stdlib::io::file_close as *const u8);
+
+    let env = find_namespace(vm, stdlib, "env");
+    native_fct(
+        vm,
+        env,
+        "envVarExists",
+        stdlib::env::env_var_exists as *const u8,
+    );
+    native_fct(vm, env, "envVar", stdlib::env::env_var as *const u8);
+    native_fct(vm, env, "envSetVar", stdlib::env::env_set_var as *const u8);
+    native_fct(
+        vm,
+        env,
+        "envRemoveVar",
+        stdlib::env::env_remove_var as *const u8,
+    );
+    native_fct(
+        vm,
+        env,
+        "envVarsSnapshot",
+        stdlib::env::env_vars_snapshot as *const u8,
+    );
+    native_fct(
+        vm,
+        env,
+        "envVarsKey",
+        stdlib::env::env_vars_key as *const u8,
+    );
+    native_fct(
+        vm,
+        env,
+        "envVarsValue",
+        stdlib::env::env_vars_value as *const u8,
+    );
+    native_fct(
+        vm,
+        env,
+        "envCurrentDir",
+        stdlib::env::env_current_dir as *const u8,
+    );
+    native_fct(
+        vm,
+        env,
+        "envSetCurrentDir",
+        stdlib::env::env_set_current_dir as *const u8,
+    );
+
     if vm.args.flag_boots.is_some() {
         native_fct(
             vm,
@@ -283,6 +739,9 @@ pub fn resolve_internal_functions(vm: &mut VM) {
     native_fct(vm, stdlib, "call", stdlib::call as *const u8);
 
     intrinsic_fct(vm, stdlib, "unsafeKillRefs", Intrinsic::UnsafeKillRefs);
+    intrinsic_fct(vm, stdlib, "unsafeIsNull", Intrinsic::UnsafeIsNull);
+    intrinsic_fct(vm, stdlib, "weakRefIsCleared", Intrinsic::WeakRefIsCleared);
+    intrinsic_fct(vm, stdlib, "weakRefKeepAlive", Intrinsic::WeakRefKeepAlive);
 
     native_method(
         vm,
@@ -605,6 +1064,41 @@ pub fn resolve_internal_functions(vm: &mut VM) {
         "clone",
         stdlib::str_clone as *const u8,
     );
+    native_method(
+        vm,
+        stdlib,
+        "String",
+        "findFrom",
+        stdlib::str_find as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "String",
+        "findLast",
+        stdlib::str_rfind as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "String",
+        "charCount",
+        stdlib::str_char_count as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "String",
+        "trimStartOffset",
+        stdlib::str_trim_start as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "String",
+        "trimEndOffset",
+        stdlib::str_trim_end as *const u8,
+    );
 
     native_method(
         vm,
@@ -644,6 +1138,66 @@ pub fn resolve_internal_functions(vm: &mut VM) {
 
     intrinsic_method(vm, stdlib, "Float32", "isNan", Intrinsic::Float32IsNan);
     intrinsic_method(vm, stdlib, "Float32", "sqrt", Intrinsic::Float32Sqrt);
+    intrinsic_method(vm, stdlib, "Float32", "abs", Intrinsic::Float32Abs);
+    intrinsic_method(vm, stdlib, "Float32", "floor", Intrinsic::Float32RoundDown);
+    intrinsic_method(vm, stdlib, "Float32", "ceil", Intrinsic::Float32RoundUp);
+    intrinsic_method(
+        vm,
+        stdlib,
+        "Float32",
+        "trunc",
+        Intrinsic::Float32RoundToZero,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "sin",
+        stdlib::float32_sin as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "cos",
+        stdlib::float32_cos as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "tan",
+        stdlib::float32_tan as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "atan2",
+        stdlib::float32_atan2 as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "exp",
+        stdlib::float32_exp as *const u8,
+    );
+    native_method(vm, stdlib, "Float32", "ln", stdlib::float32_ln as *const u8);
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "log2",
+        stdlib::float32_log2 as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float32",
+        "pow",
+        stdlib::float32_pow as *const u8,
+    );
 
     native_method(
         vm,
@@ -683,6 +1237,66 @@ pub fn resolve_internal_functions(vm: &mut VM) {
 
     intrinsic_method(vm, stdlib, "Float64", "isNan", Intrinsic::Float64IsNan);
     intrinsic_method(vm, stdlib, "Float64", "sqrt", Intrinsic::Float64Sqrt);
+    intrinsic_method(vm, stdlib, "Float64", "abs", Intrinsic::Float64Abs);
+    intrinsic_method(vm, stdlib, "Float64", "floor", Intrinsic::Float64RoundDown);
+    intrinsic_method(vm, stdlib, "Float64", "ceil", Intrinsic::Float64RoundUp);
+    intrinsic_method(
+        vm,
+        stdlib,
+        "Float64",
+        "trunc",
+        Intrinsic::Float64RoundToZero,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "sin",
+        stdlib::float64_sin as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "cos",
+        stdlib::float64_cos as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "tan",
+        stdlib::float64_tan as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "atan2",
+        stdlib::float64_atan2 as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "exp",
+        stdlib::float64_exp as *const u8,
+    );
+    native_method(vm, stdlib, "Float64", "ln", stdlib::float64_ln as *const u8);
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "log2",
+        stdlib::float64_log2 as *const u8,
+    );
+    native_method(
+        vm,
+        stdlib,
+        "Float64",
+        "pow",
+        stdlib::float64_pow as *const u8,
+    );
 
     native_static(
         vm,
@@ -721,17 +1335,163 @@ pub fn resolve_internal_functions(vm: &mut VM) {
         stack::stack_element as *const u8,
     );
 
-    native_method(
+    native_fct(
+        vm,
+        stdlib,
+        "threadNextId",
+        stdlib::thread_next_id as *const u8,
+    );
+    native_fct(vm, stdlib, "threadSpawn", stdlib::spawn_thread as *const u8);
+    native_fct(
         vm,
         stdlib,
-        "Thread",
-        "start",
-        stdlib::spawn_thread as *const u8,
+        "threadCurrent",
+        stdlib::thread_current as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "threadSetCurrent",
+        stdlib::thread_set_current as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "threadTrapMessage",
+        stdlib::thread_trap_message as *const u8,
+    );
+    native_fct(
+        vm,
+        stdlib,
+        "threadTrapStacktrace",
+        stdlib::thread_trap_stacktrace as *const u8,
+    );
+
+    native_fct(vm, stdlib, "fiberCreate", stdlib::fiber_create as *const u8);
+    native_fct(vm, stdlib, "fiberResume", stdlib::fiber_resume as *const u8);
+    native_fct(vm, stdlib, "fiberYield", stdlib::fiber_yield as *const u8);
+    native_fct(
+        vm,
+        stdlib,
+        "fiberCurrent",
+        stdlib::fiber_current as *const u8,
     );
 
     intrinsic_method(vm, stdlib, "Option", "isNone", Intrinsic::OptionIsNone);
     intrinsic_method(vm, stdlib, "Option", "isSome", Intrinsic::OptionIsSome);
     intrinsic_method(vm, stdlib, "Option", "unwrap", Intrinsic::OptionUnwrap);
+
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt32",
+        "load",
+        Intrinsic::AtomicInt32Load,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt32",
+        "store",
+        Intrinsic::AtomicInt32Store,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt32",
+        "swap",
+        Intrinsic::AtomicInt32Swap,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt32",
+        "compareExchange",
+        Intrinsic::AtomicInt32CompareExchange,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt32",
+        "fetchAdd",
+        Intrinsic::AtomicInt32FetchAdd,
+    );
+
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt64",
+        "load",
+        Intrinsic::AtomicInt64Load,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt64",
+        "store",
+        Intrinsic::AtomicInt64Store,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt64",
+        "swap",
+        Intrinsic::AtomicInt64Swap,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt64",
+        "compareExchange",
+        Intrinsic::AtomicInt64CompareExchange,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicInt64",
+        "fetchAdd",
+        Intrinsic::AtomicInt64FetchAdd,
+    );
+
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicBool",
+        "loadInt32",
+        Intrinsic::AtomicInt32Load,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicBool",
+        "storeInt32",
+        Intrinsic::AtomicInt32Store,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicBool",
+        "swapInt32",
+        Intrinsic::AtomicInt32Swap,
+    );
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicBool",
+        "compareExchangeInt32",
+        Intrinsic::AtomicInt32CompareExchange,
+    );
+
+    intrinsic_method(vm, stdlib, "AtomicRef", "load", Intrinsic::AtomicRefLoad);
+    intrinsic_method(vm, stdlib, "AtomicRef", "store", Intrinsic::AtomicRefStore);
+    intrinsic_method(vm, stdlib, "AtomicRef", "swap", Intrinsic::AtomicRefSwap);
+    intrinsic_method(
+        vm,
+        stdlib,
+        "AtomicRef",
+        "compareExchange",
+        Intrinsic::AtomicRefCompareExchange,
+    );
 }
 
 fn intrinsic_ctor(vm: &VM, namespace_id: NamespaceId, class_name: &str, intrinsic: Intrinsic) {
@@ -750,6 +1510,14 @@ fn intrinsic_ctor(vm: &VM, namespace_id: NamespaceId, class_name: &str, intrinsi
     ctor.intrinsic = Some(intrinsic);
 }
 
+fn find_fct(vm: &VM, namespace_id: NamespaceId, name: &str) -> FctId {
+    let name = vm.interner.intern(name);
+
+    NestedSymTable::new(vm, namespace_id)
+        .get_fct(name)
+        .expect("function not found")
+}
+
 fn find_method(vm: &VM, namespace_id: NamespaceId, container_name: &str, name: &str) -> FctId {
     let container_name = vm.interner.intern(container_name);
 
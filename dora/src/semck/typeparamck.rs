@@ -66,7 +66,24 @@ pub fn check_class(
         error,
     };
 
-    checker.check(type_params)
+    if !checker.check(type_params) {
+        return false;
+    }
+
+    // the JIT accesses the value of AtomicRef atomically as a pointer
+    if Some(cls_id) == vm.known.classes.atomic_ref
+        && !type_params[0].reference_type()
+        && !type_params[0].is_error()
+    {
+        if let ErrorReporting::Yes(file_id, pos) = checker.error {
+            let name = type_params[0].name_fct(vm, fct);
+            let msg = SemError::ReferenceTypeExpected(name);
+            vm.diag.lock().report(file_id, pos, msg);
+        }
+        return false;
+    }
+
+    true
 }
 
 pub fn check_super<'a>(vm: &VM, cls: &Class, error: ErrorReporting) -> bool {
@@ -2,6 +2,7 @@ use std::ptr;
 
 use crate::compiler::fct::JitFctId;
 use crate::compiler::map::CodeDescriptor;
+use crate::gc::Address;
 use crate::handle::{root, Handle};
 use crate::object::{alloc, Array, Int32Array, Ref, Stacktrace, StacktraceElement, Str};
 use crate::threads::THREAD;
@@ -24,6 +25,10 @@ impl NativeStacktrace {
         self.elems.push(StackElem { fct_id, lineno });
     }
 
+    pub fn frames(&self) -> impl Iterator<Item = (JitFctId, i32)> + '_ {
+        self.elems.iter().map(|elem| (elem.fct_id, elem.lineno))
+    }
+
     pub fn dump(&self, vm: &VM) {
         let frames = self.elems.len();
         for (ind, elem) in self.elems.iter().enumerate() {
@@ -101,19 +106,29 @@ impl DoraToNativeInfo {
 }
 
 pub fn stacktrace_from_last_dtn(vm: &VM) -> NativeStacktrace {
-    let mut stacktrace = NativeStacktrace::new();
-    frames_from_dtns(&mut stacktrace, vm);
-    return stacktrace;
-}
-
-fn frames_from_dtns(stacktrace: &mut NativeStacktrace, vm: &VM) {
-    let mut dtn_ptr = THREAD.with(|thread| {
+    let dtn = THREAD.with(|thread| {
         let thread = thread.borrow();
         let dtn = thread.dtn();
 
         dtn
     });
 
+    stacktrace_from_dtn(vm, dtn)
+}
+
+// Stack trace of all frames reachable from the given DTN. Frames of Dora
+// code that ran after the DTN was pushed aren't included.
+pub fn stacktrace_from_dtn(vm: &VM, dtn: *const DoraToNativeInfo) -> NativeStacktrace {
+    let mut stacktrace = NativeStacktrace::new();
+    frames_from_dtns(&mut stacktrace, vm, dtn);
+    return stacktrace;
+}
+
+fn frames_from_dtns(
+    stacktrace: &mut NativeStacktrace,
+    vm: &VM,
+    mut dtn_ptr: *const DoraToNativeInfo,
+) {
     while !dtn_ptr.is_null() {
         let dtn = unsafe { &*dtn_ptr };
 
@@ -214,12 +229,15 @@ pub extern "C" fn stack_element(obj: Handle<Stacktrace>, ind: i32) -> Ref<Stackt
     let fct = vm.fcts.idx(jit_fct.fct_id());
     let fct = fct.read();
     let name = fct.name_with_params(vm);
-    ste.name = Str::from_buffer(vm, name.as_bytes());
+    let name = Str::from_buffer(vm, name.as_bytes());
+    let slot = Address::from_ptr(&ste.name as *const _);
+    vm.gc
+        .write_ref(ste.direct().address(), slot, name.address());
 
     ste.direct()
 }
 
-fn set_backtrace(vm: &VM, mut obj: Handle<Stacktrace>, via_retrieve: bool) {
+fn set_backtrace(vm: &VM, obj: Handle<Stacktrace>, via_retrieve: bool) {
     let stacktrace = stacktrace_from_last_dtn(vm);
     let mut skip = 0;
 
@@ -286,6 +304,15 @@ fn set_backtrace(vm: &VM, mut obj: Handle<Stacktrace>, via_retrieve: bool) {
         assert!(skip_constructor);
     }
 
+    fill_backtrace(vm, obj, &stacktrace, skip);
+}
+
+// Replaces the backtrace of obj, e.g. with the stack trace of a trap.
+pub fn set_backtrace_from(vm: &VM, obj: Handle<Stacktrace>, stacktrace: &NativeStacktrace) {
+    fill_backtrace(vm, obj, stacktrace, 0);
+}
+
+fn fill_backtrace(vm: &VM, obj: Handle<Stacktrace>, stacktrace: &NativeStacktrace, skip: usize) {
     let len = stacktrace.len() - skip;
 
     let cls_id = vm.known.int_array(vm);
@@ -298,5 +325,7 @@ fn set_backtrace(vm: &VM, mut obj: Handle<Stacktrace>, via_retrieve: bool) {
         array.set_at(i + 1, elem.fct_id.idx() as i32);
         i += 2;
     }
-    obj.backtrace = array.direct();
+    let slot = Address::from_ptr(&obj.backtrace as *const _);
+    vm.gc
+        .write_ref(obj.direct().address(), slot, array.direct().address());
 }
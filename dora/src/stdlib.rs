@@ -1,21 +1,36 @@
 use libc;
 
 use std::char;
-use std::io::{self, Write};
+use std::fmt;
+use std::io::Write;
 use std::mem;
-use std::process;
+use std::ptr;
+use std::slice;
 use std::str;
+use std::sync::atomic::{self, AtomicI32, AtomicUsize, Ordering};
 use std::thread;
 use std::time::Duration;
 
+use dora_parser::ast::{FormatAlign, FormatKind, FormatSpec};
+
 use crate::boots;
 use crate::gc::{Address, GcReason};
-use crate::handle::{scope as handle_scope, Handle};
-use crate::object::{Obj, Ref, Str, UInt8Array};
-use crate::stack::stacktrace_from_last_dtn;
+use crate::handle::{self, scope as handle_scope, Handle};
+use crate::object::{Float64Array, Int32Array, Int64Array, Obj, Ref, Stacktrace, Str, UInt8Array};
+use crate::stack::{set_backtrace_from, stacktrace_from_last_dtn, NativeStacktrace};
+use crate::threads::fiber::{self, Fiber};
+use crate::threads::parking::ParkResult;
 use crate::threads::{DoraThread, STACK_SIZE, THREAD};
 use crate::ty::SourceTypeArray;
-use crate::vm::{get_vm, stack_pointer, Trap};
+use crate::vm::{get_vm, stack_pointer, ClassDefId, Trap, VM};
+
+pub mod bigint;
+pub mod env;
+pub mod io;
+pub mod net;
+pub mod process;
+pub mod text;
+mod unicode_tables;
 
 pub extern "C" fn uint8_to_string(val: u8) -> Ref<Str> {
     handle_scope(|| {
@@ -71,34 +86,204 @@ pub extern "C" fn float64_to_string(val: f64) -> Ref<Str> {
     })
 }
 
+pub extern "C" fn float32_sin(val: f32) -> f32 {
+    val.sin()
+}
+
+pub extern "C" fn float32_cos(val: f32) -> f32 {
+    val.cos()
+}
+
+pub extern "C" fn float32_tan(val: f32) -> f32 {
+    val.tan()
+}
+
+pub extern "C" fn float32_exp(val: f32) -> f32 {
+    val.exp()
+}
+
+pub extern "C" fn float32_ln(val: f32) -> f32 {
+    val.ln()
+}
+
+pub extern "C" fn float32_log2(val: f32) -> f32 {
+    val.log2()
+}
+
+pub extern "C" fn float32_atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
+pub extern "C" fn float32_pow(val: f32, exponent: f32) -> f32 {
+    val.powf(exponent)
+}
+
+pub extern "C" fn float64_sin(val: f64) -> f64 {
+    val.sin()
+}
+
+pub extern "C" fn float64_cos(val: f64) -> f64 {
+    val.cos()
+}
+
+pub extern "C" fn float64_tan(val: f64) -> f64 {
+    val.tan()
+}
+
+pub extern "C" fn float64_exp(val: f64) -> f64 {
+    val.exp()
+}
+
+pub extern "C" fn float64_ln(val: f64) -> f64 {
+    val.ln()
+}
+
+pub extern "C" fn float64_log2(val: f64) -> f64 {
+    val.log2()
+}
+
+pub extern "C" fn float64_atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+pub extern "C" fn float64_pow(val: f64, exponent: f64) -> f64 {
+    val.powf(exponent)
+}
+
+fn format_spec(spec: Handle<Str>) -> FormatSpec {
+    let spec = str::from_utf8(spec.content()).unwrap();
+    FormatSpec::parse(spec).expect("invalid format specifier")
+}
+
+// Pads `sign` followed by `digits` to the width of the specifier, zeros
+// are inserted between sign and digits.
+fn format_pad(spec: &FormatSpec, sign: &str, digits: &str, default_align: FormatAlign) -> Ref<Str> {
+    let len = sign.chars().count() + digits.chars().count();
+    let padding = spec.width.unwrap_or(0).saturating_sub(len);
+    let mut result = String::with_capacity(len + padding);
+
+    if spec.zero {
+        result.push_str(sign);
+        result.extend(std::iter::repeat('0').take(padding));
+        result.push_str(digits);
+    } else {
+        let (before, after) = match spec.align.unwrap_or(default_align) {
+            FormatAlign::Left => (0, padding),
+            FormatAlign::Center => (padding / 2, padding - padding / 2),
+            FormatAlign::Right => (padding, 0),
+        };
+
+        result.extend(std::iter::repeat(spec.fill).take(before));
+        result.push_str(sign);
+        result.push_str(digits);
+        result.extend(std::iter::repeat(spec.fill).take(after));
+    }
+
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, result.as_bytes())
+    })
+}
+
+// `bits` is the two's complement of negative values for binary, octal and
+// hexadecimal output.
+fn format_integer(value: i64, bits: u64, spec: Handle<Str>) -> Ref<Str> {
+    let spec = format_spec(spec);
+
+    let digits = match spec.kind {
+        FormatKind::Display => value.to_string(),
+        FormatKind::Binary => format!("{:b}", bits),
+        FormatKind::Octal => format!("{:o}", bits),
+        FormatKind::LowerHex => format!("{:x}", bits),
+        FormatKind::UpperHex => format!("{:X}", bits),
+        FormatKind::LowerExp => unreachable!(),
+    };
+
+    format_number(&spec, &digits)
+}
+
+// Formats exactly like Rust, without precision the shortest representation
+// that roundtrips is used.
+fn format_float<T: fmt::Display + fmt::LowerExp>(value: T, spec: Handle<Str>) -> Ref<Str> {
+    let spec = format_spec(spec);
+
+    let digits = match (spec.kind, spec.precision) {
+        (FormatKind::LowerExp, Some(precision)) => format!("{:.*e}", precision, value),
+        (FormatKind::LowerExp, None) => format!("{:e}", value),
+        (_, Some(precision)) => format!("{:.*}", precision, value),
+        (_, None) => format!("{}", value),
+    };
+
+    format_number(&spec, &digits)
+}
+
+fn format_number(spec: &FormatSpec, digits: &str) -> Ref<Str> {
+    let (sign, digits) = if digits.starts_with('-') {
+        ("-", &digits[1..])
+    } else if spec.sign {
+        ("+", digits)
+    } else {
+        ("", digits)
+    };
+
+    format_pad(spec, sign, digits, FormatAlign::Right)
+}
+
+pub extern "C" fn format_uint8(value: u8, spec: Handle<Str>) -> Ref<Str> {
+    format_integer(value as i64, value as u64, spec)
+}
+
+pub extern "C" fn format_int32(value: i32, spec: Handle<Str>) -> Ref<Str> {
+    format_integer(value as i64, value as u32 as u64, spec)
+}
+
+pub extern "C" fn format_int64(value: i64, spec: Handle<Str>) -> Ref<Str> {
+    format_integer(value, value as u64, spec)
+}
+
+pub extern "C" fn format_float32(value: f32, spec: Handle<Str>) -> Ref<Str> {
+    format_float(value, spec)
+}
+
+pub extern "C" fn format_float64(value: f64, spec: Handle<Str>) -> Ref<Str> {
+    format_float(value, spec)
+}
+
+// Strings only support width, fill and alignment.
+pub extern "C" fn format_str(value: Handle<Str>, spec: Handle<Str>) -> Ref<Str> {
+    let spec = format_spec(spec);
+    let value = str::from_utf8(value.content()).unwrap();
+    format_pad(&spec, "", value, FormatAlign::Left)
+}
+
 pub extern "C" fn print(val: Handle<Str>) {
-    io::stdout().write(val.content()).unwrap();
+    std::io::stdout().write(val.content()).unwrap();
 }
 
 pub extern "C" fn fatal_error(msg: Handle<Str>) {
     eprint!("fatal error: ");
-    io::stderr().write(msg.content()).unwrap();
+    std::io::stderr().write(msg.content()).unwrap();
     eprintln!("");
 
     let vm = get_vm();
     let stacktrace = stacktrace_from_last_dtn(vm);
     stacktrace.dump_err(vm);
 
-    process::exit(1);
+    std::process::exit(1);
 }
 
 pub extern "C" fn abort() {
     eprintln!("program aborted.");
-    process::exit(1);
+    std::process::exit(1);
 }
 
 pub extern "C" fn exit(status: i32) {
-    process::exit(status);
+    std::process::exit(status);
 }
 
 pub extern "C" fn unreachable() {
     eprintln!("unreachable code executed.");
-    process::exit(1);
+    std::process::exit(1);
 }
 
 pub extern "C" fn timestamp() -> u64 {
@@ -108,7 +293,7 @@ pub extern "C" fn timestamp() -> u64 {
 }
 
 pub extern "C" fn println(val: Handle<Str>) {
-    let stdout = io::stdout();
+    let stdout = std::io::stdout();
     let mut handle = stdout.lock();
     handle.write(val.content()).unwrap();
     handle.write(b"\n").unwrap();
@@ -136,17 +321,21 @@ pub extern "C" fn call(fct: Handle<Str>) {
             let fct = fct.read();
 
             if !fct.param_types.is_empty() {
-                writeln!(&mut io::stderr(), "fct `{}` takes arguments.", fct_name)
-                    .expect("could not print to stderr");
-                process::exit(1);
+                writeln!(
+                    &mut std::io::stderr(),
+                    "fct `{}` takes arguments.",
+                    fct_name
+                )
+                .expect("could not print to stderr");
+                std::process::exit(1);
             }
         }
 
         vm.run(fct_id);
     } else {
-        writeln!(&mut io::stderr(), "fct `{}` not found.", fct_name)
+        writeln!(&mut std::io::stderr(), "fct `{}` not found.", fct_name)
             .expect("could not print to stderr");
-        process::exit(1);
+        std::process::exit(1);
     }
 }
 
@@ -192,9 +381,26 @@ pub extern "C" fn str_from_bytes(val: Handle<UInt8Array>, offset: usize, len: us
     })
 }
 
-pub extern "C" fn gc_alloc(size: usize, array_ref: bool) -> *mut Obj {
+pub extern "C" fn gc_alloc(size: usize, array_ref: bool, cls_def_id: usize) -> *mut Obj {
     let vm = get_vm();
-    vm.gc.alloc(vm, size, array_ref).to_mut_ptr()
+    vm.gc
+        .alloc(vm, size, array_ref, ClassDefId::from(cls_def_id))
+        .to_mut_ptr()
+}
+
+// Invoked by compiled code before overwriting the reference at `base + offset`
+// while concurrent marking is active. Returns `base` since the call clobbers
+// all caller-saved registers.
+pub extern "C" fn gc_pre_write_barrier(base: *mut u8, offset: usize) -> *mut u8 {
+    let vm = get_vm();
+    let slot = Address::from_ptr(base).offset(offset);
+    let old = unsafe { *slot.to_ptr::<Address>() };
+
+    if old.is_non_null() {
+        vm.gc.pre_write_barrier(old);
+    }
+
+    base
 }
 
 pub extern "C" fn gc_collect() {
@@ -207,6 +413,127 @@ pub extern "C" fn gc_minor_collect() {
     vm.gc.minor_collect(vm, GcReason::ForceMinorCollect);
 }
 
+pub extern "C" fn weak_ref_register(weak_ref: Handle<Obj>) {
+    let vm = get_vm();
+    let weak_ref = weak_ref.direct();
+    let cls_def = weak_ref.header().vtbl().class_def();
+    debug_assert_eq!(cls_def.cls_id, vm.known.classes.weak_ref);
+
+    // referent is always the first field of WeakRef
+    let referent = &cls_def.fields[0];
+
+    // only references can be collected, there is nothing to do for values
+    if referent.ty.reference_type() {
+        vm.gc
+            .weak_refs()
+            .register(weak_ref.address(), referent.offset as usize);
+    }
+}
+
+pub extern "C" fn weak_ref_cleared_count() -> i64 {
+    let vm = get_vm();
+    vm.gc.weak_refs().cleared() as i64
+}
+
+// Gives the referenced object of a `Pinned`, null for values since there
+// is nothing to pin for them.
+fn pinned_value(pinned: Handle<Obj>) -> Address {
+    let vm = get_vm();
+    let pinned = pinned.direct();
+    let cls_def = pinned.header().vtbl().class_def();
+    debug_assert_eq!(cls_def.cls_id, vm.known.classes.pinned);
+
+    // value is always the first field of Pinned
+    let value = &cls_def.fields[0];
+
+    if value.ty.reference_type() {
+        let slot = pinned.address().offset(value.offset as usize);
+        unsafe { *slot.to_ptr::<Address>() }
+    } else {
+        Address::null()
+    }
+}
+
+pub extern "C" fn pinning_supported() -> bool {
+    let vm = get_vm();
+    vm.gc.supports_pinning()
+}
+
+pub extern "C" fn pinned_pin(pinned: Handle<Obj>) {
+    let value = pinned_value(pinned);
+
+    if value.is_non_null() {
+        handle_scope(|| {
+            let vm = get_vm();
+            let value: Handle<Obj> = handle::root(value.into());
+            vm.gc.pin(vm, value);
+        });
+    }
+}
+
+pub extern "C" fn pinned_unpin(pinned: Handle<Obj>) {
+    let value = pinned_value(pinned);
+
+    if value.is_non_null() {
+        let vm = get_vm();
+        vm.gc.unpin(value);
+    }
+}
+
+pub extern "C" fn pinned_address(pinned: Handle<Obj>) -> i64 {
+    pinned_value(pinned).to_usize() as i64
+}
+
+// Fills the given array with a snapshot of the heap statistics, the layout
+// needs to match `std::gc::readHeapStats`. Pauses are in microseconds.
+pub extern "C" fn gc_read_heap_stats(mut stats: Handle<Int64Array>) {
+    let vm = get_vm();
+    let heap_stats = vm.gc.heap_stats();
+
+    let values = [
+        heap_stats.young_used as i64,
+        heap_stats.young_committed as i64,
+        heap_stats.old_used as i64,
+        heap_stats.old_committed as i64,
+        heap_stats.large_used as i64,
+        heap_stats.large_committed as i64,
+        heap_stats.minor_collections as i64,
+        (heap_stats.minor_pause * 1000.0) as i64,
+        heap_stats.full_collections as i64,
+        (heap_stats.full_pause * 1000.0) as i64,
+        heap_stats.min_heap_size as i64,
+        heap_stats.max_heap_size as i64,
+        heap_stats.young_limit as i64,
+        heap_stats.old_limit as i64,
+    ];
+
+    assert_eq!(stats.len(), values.len());
+
+    for (idx, &value) in values.iter().enumerate() {
+        stats.set_at(idx, value);
+    }
+}
+
+// Sorting doesn't allocate, so the array can't be moved by the GC.
+pub extern "C" fn sort_int32_array(mut array: Handle<Int32Array>) {
+    let len = array.len();
+    let data = unsafe { slice::from_raw_parts_mut(array.data_mut(), len) };
+    data.sort_unstable();
+}
+
+pub extern "C" fn sort_float64_array(mut array: Handle<Float64Array>) {
+    let len = array.len();
+    let data = unsafe { slice::from_raw_parts_mut(array.data_mut(), len) };
+    data.sort_unstable_by_key(|&value| float64_sort_key(value));
+}
+
+// Total order of `Float64#sortsAs`: negative NaNs, -Inf, ..., -0.0, 0.0, ...,
+// Inf, positive NaNs.
+fn float64_sort_key(value: f64) -> i64 {
+    let bits = value.to_bits() as i64;
+    bits ^ (((bits >> 63) as u64) >> 1) as i64
+}
+
 pub extern "C" fn argc() -> i32 {
     let vm = get_vm();
 
@@ -287,43 +614,274 @@ pub extern "C" fn str_to_float64(val: Handle<Str>) -> f64 {
     val.parse::<f64>().unwrap_or(0.0)
 }
 
+// Byte offset of the first occurrence of `needle` at or after `start`,
+// -1 if there is none.
+pub extern "C" fn str_find(haystack: Handle<Str>, needle: Handle<Str>, start: i64) -> i64 {
+    let haystack = haystack.content();
+    assert!(start >= 0 && start as usize <= haystack.len());
+
+    match find_bytes(&haystack[start as usize..], needle.content()) {
+        Some(idx) => start + idx as i64,
+        None => -1,
+    }
+}
+
+// Byte offset of the last occurrence of `needle`, -1 if there is none.
+pub extern "C" fn str_rfind(haystack: Handle<Str>, needle: Handle<Str>) -> i64 {
+    match rfind_bytes(haystack.content(), needle.content()) {
+        Some(idx) => idx as i64,
+        None => -1,
+    }
+}
+
+// Candidates for a match are located with memchr on the first byte of the
+// needle.
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let (&first, rest) = match needle.split_first() {
+        Some(split) => split,
+        None => return Some(0),
+    };
+
+    let mut pos = 0;
+
+    while pos + needle.len() <= haystack.len() {
+        let candidate = unsafe {
+            libc::memchr(
+                haystack[pos..].as_ptr() as *const libc::c_void,
+                first as libc::c_int,
+                haystack.len() - needle.len() + 1 - pos,
+            )
+        };
+
+        if candidate.is_null() {
+            return None;
+        }
+
+        let idx = candidate as usize - haystack.as_ptr() as usize;
+
+        if &haystack[idx + 1..idx + needle.len()] == rest {
+            return Some(idx);
+        }
+
+        pos = idx + 1;
+    }
+
+    None
+}
+
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.len() > haystack.len() {
+        return None;
+    }
+
+    (0..=haystack.len() - needle.len())
+        .rev()
+        .find(|&idx| &haystack[idx..idx + needle.len()] == needle)
+}
+
+// Counts all bytes that aren't UTF-8 continuation bytes.
+pub extern "C" fn str_char_count(val: Handle<Str>) -> i64 {
+    val.content()
+        .iter()
+        .filter(|&&byte| byte & 0xC0 != 0x80)
+        .count() as i64
+}
+
+// Byte offset of the first char that isn't Unicode whitespace.
+pub extern "C" fn str_trim_start(val: Handle<Str>) -> i64 {
+    let val = str::from_utf8(val.content()).unwrap();
+    (val.len() - val.trim_start().len()) as i64
+}
+
+// Byte offset after the last char that isn't Unicode whitespace.
+pub extern "C" fn str_trim_end(val: Handle<Str>) -> i64 {
+    let val = str::from_utf8(val.content()).unwrap();
+    val.trim_end().len() as i64
+}
+
 pub extern "C" fn trap(trap_id: u32) {
     let vm = get_vm();
     let trap = Trap::from(trap_id).expect("invalid trap id!");
+    let stacktrace = stacktrace_from_last_dtn(vm);
+    let current = THREAD.with(|thread| thread.borrow().clone());
+
+    if current.catches_traps() {
+        // report the trap to threads joining this thread, a trap while
+        // reporting exits the process
+        current.set_catch_traps(false);
+        current.set_trap(trap, stacktrace);
+
+        // continue after the catch stub in spawn_thread, the stack of the
+        // trapping code might be exhausted. Nothing on this stack gets
+        // dropped after switching.
+        let unused = AtomicUsize::new(0);
+        let sp = current.trap_sp();
+        mem::drop(current);
+
+        fiber::switch_stack_raw(vm, &unused, sp);
+        unreachable!("trapped thread resumed");
+    }
 
-    let msg = match trap {
-        Trap::DIV0 => "division by 0",
-        Trap::ASSERT => "assert failed",
-        Trap::INDEX_OUT_OF_BOUNDS => "array index out of bounds",
-        Trap::NIL => "nil check failed",
-        Trap::CAST => "cast failed",
-        Trap::OOM => "out of memory",
-        Trap::STACK_OVERFLOW => "stack overflow",
-        Trap::ILLEGAL => "illegal state",
-    };
+    exit_with_trap(vm, trap, &stacktrace);
+}
 
-    eprintln!("{}", msg);
+// Reports the trap and exits even if the current thread catches traps,
+// used when the native code can't be left (e.g. while the world is stopped).
+pub fn fatal_trap(trap: Trap) -> ! {
+    let vm = get_vm();
     let stacktrace = stacktrace_from_last_dtn(vm);
+    exit_with_trap(vm, trap, &stacktrace);
+}
+
+fn exit_with_trap(vm: &VM, trap: Trap, stacktrace: &NativeStacktrace) -> ! {
+    eprintln!("{}", trap.message());
     stacktrace.dump_err(vm);
     unsafe {
-        libc::_exit(100 + trap_id as i32);
+        libc::_exit(100 + trap.int() as i32);
     }
 }
 
-pub extern "C" fn spawn_thread(obj: Handle<Obj>) {
-    use crate::compiler;
-    use crate::stack::DoraToNativeInfo;
+// States of `std::Mutex`
+const MUTEX_UNLOCKED: i32 = 0;
+const MUTEX_LOCKED: i32 = 1;
+const MUTEX_LOCKED_PARKED: i32 = 2;
+
+// Number of attempts to acquire a contended mutex before parking.
+const MUTEX_SPINS: usize = 100;
+
+// Gives the address of the field with the given index in object. The
+// address is only valid until the thread parks.
+fn field_address(object: Handle<Obj>, idx: usize) -> Address {
+    let object = object.direct();
+    let cls_def = object.header().vtbl().class_def();
+    object.address().offset(cls_def.fields[idx].offset as usize)
+}
+
+// Mutex and Condition store their state in the first field and the key
+// for the parking lot in the second one.
+fn sync_state<'a>(object: Handle<Obj>) -> &'a AtomicI32 {
+    unsafe { &*field_address(object, 0).to_ptr::<AtomicI32>() }
+}
+
+fn sync_key(object: Handle<Obj>) -> u64 {
+    unsafe { *field_address(object, 1).to_ptr::<i64>() as u64 }
+}
+
+pub extern "C" fn sync_next_key() -> i64 {
+    let vm = get_vm();
+    vm.threads.parking_lot.next_key() as i64
+}
+
+pub extern "C" fn mutex_lock(mutex: Handle<Obj>) {
+    for _ in 0..MUTEX_SPINS {
+        if mutex_try_lock(mutex) {
+            return;
+        }
+
+        atomic::spin_loop_hint();
+    }
+
+    let vm = get_vm();
+    let key = sync_key(mutex);
+
+    loop {
+        // the state is only set to unlocked when acquiring the lock, other
+        // threads need to be unparked when this thread unlocks
+        if sync_state(mutex).swap(MUTEX_LOCKED_PARKED, Ordering::Acquire) == MUTEX_UNLOCKED {
+            return;
+        }
+
+        vm.threads.parking_lot.park(
+            vm,
+            key,
+            || sync_state(mutex).load(Ordering::Relaxed) == MUTEX_LOCKED_PARKED,
+            None,
+        );
+    }
+}
+
+pub extern "C" fn mutex_try_lock(mutex: Handle<Obj>) -> bool {
+    sync_state(mutex)
+        .compare_exchange(
+            MUTEX_UNLOCKED,
+            MUTEX_LOCKED,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        )
+        .is_ok()
+}
+
+pub extern "C" fn mutex_unlock(mutex: Handle<Obj>) {
+    match sync_state(mutex).swap(MUTEX_UNLOCKED, Ordering::Release) {
+        MUTEX_LOCKED => {}
+        MUTEX_LOCKED_PARKED => {
+            let vm = get_vm();
+            vm.threads.parking_lot.unpark_one(sync_key(mutex));
+        }
+        _ => trap(Trap::ILLEGAL.int()),
+    }
+}
+
+pub extern "C" fn condition_wait(condition: Handle<Obj>, mutex: Handle<Obj>, millis: i64) -> bool {
+    let vm = get_vm();
+    let seq = sync_state(condition).load(Ordering::Relaxed);
+    let timeout = if millis < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(millis as u64))
+    };
+
+    mutex_unlock(mutex);
+
+    // notifications increment the sequence number, do not wait when
+    // notified since unlocking the mutex
+    let result = vm.threads.parking_lot.park(
+        vm,
+        sync_key(condition),
+        || sync_state(condition).load(Ordering::Relaxed) == seq,
+        timeout,
+    );
+
+    mutex_lock(mutex);
 
+    result != ParkResult::TimedOut
+}
+
+pub extern "C" fn condition_notify(condition: Handle<Obj>) {
     let vm = get_vm();
-    let thread = DoraThread::new(vm);
+    sync_state(condition).fetch_add(1, Ordering::Relaxed);
+    vm.threads.parking_lot.unpark_one(sync_key(condition));
+}
 
+pub extern "C" fn condition_notify_all(condition: Handle<Obj>) {
+    let vm = get_vm();
+    sync_state(condition).fetch_add(1, Ordering::Relaxed);
+    vm.threads.parking_lot.unpark_all(sync_key(condition));
+}
+
+pub extern "C" fn thread_next_id() -> i64 {
+    let vm = get_vm();
+    vm.threads.next_id() as i64
+}
+
+pub extern "C" fn spawn_thread(obj: Handle<Obj>, id: i64, catch_traps: bool) {
+    let vm = get_vm();
+    let thread = DoraThread::with_id(id as usize);
+    thread.set_catch_traps(catch_traps);
+
+    // the thread counts as stopped until it starts running
+    thread.park(vm);
     vm.threads.attach_thread(thread.clone());
-    let obj = obj.direct();
+
+    // the thread object is a root from now on, attaching might have run
+    // the GC and moved the object
+    thread.set_object(obj.direct().address());
 
     thread::spawn(move || {
         THREAD.with(|tld_thread| {
-            *tld_thread.borrow_mut() = thread;
+            *tld_thread.borrow_mut() = thread.clone();
         });
+        thread.unpark(vm);
 
         let stack_top = stack_pointer();
         let stack_limit = stack_top.sub(STACK_SIZE);
@@ -332,41 +890,174 @@ pub extern "C" fn spawn_thread(obj: Handle<Obj>) {
             thread.borrow().tld.set_stack_limit(stack_limit);
         });
 
-        let main = {
-            let cls_id = obj.header().vtbl().class_def().cls_id;
-            let cls_id = cls_id.expect("no corresponding class");
-            let cls = vm.classes.idx(cls_id);
-            let cls = cls.read();
-            let name = vm.interner.intern("run");
-            cls.find_method(vm, name, false)
-                .expect("run() method not found")
-        };
+        // execute the thread object's run-method, a caught trap continues
+        // after the stub as well
+        let catch_stub_address = vm.catch_stub();
+        let fct: extern "C" fn(*const AtomicUsize, extern "C" fn(&DoraThread), &DoraThread) =
+            unsafe { mem::transmute(catch_stub_address) };
+        fct(thread.trap_sp_slot(), run_thread, &thread);
 
-        let tld = THREAD.with(|thread| {
-            let thread = thread.borrow();
-            let ptr = &thread.tld;
+        if thread.trapped() {
+            // the frames of the trapping code are gone
+            thread.set_dtn(ptr::null());
+            thread.tld.set_stack_limit(stack_limit);
+            abort_fibers(vm, &thread);
 
-            Address::from_ptr(ptr as *const _)
-        });
+            invoke_thread_method(vm, "trapped");
+        }
 
-        let fct_ptr = {
-            let mut dtn = DoraToNativeInfo::new();
-            let type_params = SourceTypeArray::empty();
+        // remove thread from list of all threads
+        vm.threads.detach_current_thread();
+    });
+}
 
-            THREAD.with(|thread| {
-                thread
-                    .borrow()
-                    .use_dtn(&mut dtn, || compiler::generate(vm, main, &type_params))
-            })
-        };
+// Invoked through the catch stub, a trap skips the native frames between
+// the stub and the trap. Mustn't own anything that needs to be dropped.
+extern "C" fn run_thread(thread: &DoraThread) {
+    invoke_method(get_vm(), thread, thread.object_slot(), "runThread");
+}
 
-        // execute the thread object's run-method
-        let dora_stub_address = vm.dora_stub();
-        let fct: extern "C" fn(Address, Address, Ref<Obj>) =
-            unsafe { mem::transmute(dora_stub_address) };
-        fct(tld, fct_ptr, obj);
+// Fibers that ran on a trapped thread can't be resumed, their stacks are
+// freed once nothing references them anymore.
+fn abort_fibers(vm: &VM, thread: &DoraThread) {
+    let mut fiber = thread.set_fiber(None);
 
-        // remove thread from list of all threads
-        vm.threads.detach_current_thread();
+    while let Some(running) = fiber {
+        vm.threads.fibers.remove(running.id());
+        fiber = running.abort();
+    }
+}
+
+// Invokes the method with the given name on the std::Thread object of the
+// current thread. The method must not have any arguments.
+fn invoke_thread_method(vm: &VM, name: &str) {
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    invoke_method(vm, &thread, thread.object_slot(), name);
+}
+
+// Invokes the method with the given name on the object stored in slot,
+// which needs to be a root for the GC. The method must not have any
+// arguments.
+fn invoke_method(vm: &VM, thread: &DoraThread, slot: Address, name: &str) {
+    use crate::compiler;
+    use crate::stack::DoraToNativeInfo;
+
+    let object = || -> Ref<Obj> { unsafe { *slot.to_ptr::<Address>() }.into() };
+
+    let method = {
+        let obj = object();
+        let cls_id = obj.header().vtbl().class_def().cls_id;
+        let cls_id = cls_id.expect("no corresponding class");
+        let cls = vm.classes.idx(cls_id);
+        let cls = cls.read();
+        let name = vm.interner.intern(name);
+        cls.find_method(vm, name, false).expect("method not found")
+    };
+
+    let tld = Address::from_ptr(&thread.tld as *const _);
+
+    let fct_ptr = {
+        let mut dtn = DoraToNativeInfo::new();
+        let type_params = SourceTypeArray::empty();
+
+        thread.use_dtn(&mut dtn, || compiler::generate(vm, method, &type_params))
+    };
+
+    // compilation might have moved the object
+    let obj = object();
+
+    let dora_stub_address = vm.dora_stub();
+    let fct: extern "C" fn(Address, Address, Ref<Obj>) =
+        unsafe { mem::transmute(dora_stub_address) };
+    fct(tld, fct_ptr, obj);
+}
+
+pub extern "C" fn fiber_create(obj: Handle<Obj>) -> i64 {
+    let vm = get_vm();
+    let thread_id = THREAD.with(|thread| thread.borrow().id());
+    let entry = Address::from_ptr(fiber_start as *const u8);
+    let fiber = Fiber::new(vm, obj.direct().address(), thread_id, entry);
+
+    // the fiber object is a root from now on
+    vm.threads.fibers.add(fiber) as i64
+}
+
+// Returns true when the fiber finished.
+pub extern "C" fn fiber_resume(id: i64) -> bool {
+    let vm = get_vm();
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    let fiber = vm.threads.fibers.get(id as usize);
+
+    if fiber.thread_id() != thread.id() {
+        // fibers can't move between threads
+        trap(Trap::ILLEGAL.int());
+    }
+
+    let finished = fiber.resume(vm, &thread);
+
+    if finished {
+        // frees the fiber's stack
+        vm.threads.fibers.remove(id as usize);
+    }
+
+    finished
+}
+
+pub extern "C" fn fiber_yield() {
+    let vm = get_vm();
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+
+    match thread.fiber() {
+        Some(fiber) => fiber.suspend(vm, &thread),
+        None => trap(Trap::ILLEGAL.int()),
+    }
+}
+
+pub extern "C" fn fiber_current() -> Ref<Obj> {
+    let fiber = THREAD.with(|thread| thread.borrow().fiber());
+
+    match fiber {
+        Some(fiber) => fiber.object().into(),
+        None => Ref::null(),
+    }
+}
+
+// first function running on the stack of a new fiber
+extern "C" fn fiber_start() {
+    let vm = get_vm();
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    let fiber = thread.fiber().expect("no fiber running");
+
+    invoke_method(vm, &thread, fiber.object_slot(), "runFiber");
+
+    fiber.finish(vm, thread);
+}
+
+pub extern "C" fn thread_current() -> Ref<Obj> {
+    let object = THREAD.with(|thread| thread.borrow().object());
+    object.into()
+}
+
+pub extern "C" fn thread_set_current(obj: Handle<Obj>) {
+    THREAD.with(|thread| {
+        let thread = thread.borrow();
+        assert!(thread.object().is_null());
+        thread.set_object(obj.direct().address());
     });
 }
+
+pub extern "C" fn thread_trap_message() -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+        let message = thread.with_trap(|&(trap, _)| trap.message());
+
+        Str::from_buffer(vm, message.as_bytes())
+    })
+}
+
+pub extern "C" fn thread_trap_stacktrace(obj: Handle<Stacktrace>) {
+    let vm = get_vm();
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    thread.with_trap(|(_, stacktrace)| set_backtrace_from(vm, obj, stacktrace));
+}
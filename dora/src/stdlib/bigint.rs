@@ -0,0 +1,461 @@
+use std::char;
+use std::cmp::{self, Ordering};
+use std::str;
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{Int64Array, Ref, Str};
+use crate::vm::get_vm;
+
+// needs to match `std::BigInt`
+const BITWISE_AND: i32 = 0;
+const BITWISE_OR: i32 = 1;
+const BITWISE_XOR: i32 = 2;
+
+// Magnitudes are stored in 64-bit limbs starting with the least significant
+// one. Results are written into arrays allocated by the caller, limbs that
+// aren't needed are set to zero.
+
+fn read(array: &Handle<Int64Array>) -> Vec<u64> {
+    (0..array.len())
+        .map(|idx| array.get_at(idx) as u64)
+        .collect()
+}
+
+fn write(mut out: Handle<Int64Array>, limbs: &[u64]) {
+    let limbs = trimmed(limbs);
+    assert!(limbs.len() <= out.len(), "result array too small");
+
+    for idx in 0..out.len() {
+        let limb = limbs.get(idx).cloned().unwrap_or(0);
+        out.set_at(idx, limb as i64);
+    }
+}
+
+fn trimmed(limbs: &[u64]) -> &[u64] {
+    let len = limbs
+        .iter()
+        .rposition(|&limb| limb != 0)
+        .map_or(0, |idx| idx + 1);
+
+    &limbs[..len]
+}
+
+fn compare(lhs: &[u64], rhs: &[u64]) -> Ordering {
+    let lhs = trimmed(lhs);
+    let rhs = trimmed(rhs);
+
+    lhs.len()
+        .cmp(&rhs.len())
+        .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+fn add(lhs: &[u64], rhs: &[u64]) -> Vec<u64> {
+    let len = cmp::max(lhs.len(), rhs.len());
+    let mut result = Vec::with_capacity(len + 1);
+    let mut carry = false;
+
+    for idx in 0..len {
+        let lhs = lhs.get(idx).cloned().unwrap_or(0);
+        let rhs = rhs.get(idx).cloned().unwrap_or(0);
+
+        let (sum, overflow1) = lhs.overflowing_add(rhs);
+        let (sum, overflow2) = sum.overflowing_add(carry as u64);
+
+        result.push(sum);
+        carry = overflow1 || overflow2;
+    }
+
+    result.push(carry as u64);
+    result
+}
+
+// Requires lhs >= rhs.
+fn sub(lhs: &[u64], rhs: &[u64]) -> Vec<u64> {
+    debug_assert!(compare(lhs, rhs) != Ordering::Less);
+    let mut result = Vec::with_capacity(lhs.len());
+    let mut borrow = false;
+
+    for idx in 0..lhs.len() {
+        let rhs = rhs.get(idx).cloned().unwrap_or(0);
+
+        let (diff, overflow1) = lhs[idx].overflowing_sub(rhs);
+        let (diff, overflow2) = diff.overflowing_sub(borrow as u64);
+
+        result.push(diff);
+        borrow = overflow1 || overflow2;
+    }
+
+    assert!(!borrow);
+    result
+}
+
+fn mul(lhs: &[u64], rhs: &[u64]) -> Vec<u64> {
+    let lhs = trimmed(lhs);
+    let rhs = trimmed(rhs);
+    let mut result = vec![0u64; lhs.len() + rhs.len()];
+
+    for (i, &lhs) in lhs.iter().enumerate() {
+        let mut carry: u128 = 0;
+
+        for (j, &rhs) in rhs.iter().enumerate() {
+            let product = lhs as u128 * rhs as u128 + result[i + j] as u128 + carry;
+            result[i + j] = product as u64;
+            carry = product >> 64;
+        }
+
+        result[i + rhs.len()] = carry as u64;
+    }
+
+    result
+}
+
+fn div_rem_small(lhs: &[u64], divisor: u64) -> (Vec<u64>, u64) {
+    assert!(divisor != 0, "division by zero");
+    let mut quotient = vec![0u64; lhs.len()];
+    let mut rem: u128 = 0;
+
+    for idx in (0..lhs.len()).rev() {
+        let current = (rem << 64) | lhs[idx] as u128;
+        quotient[idx] = (current / divisor as u128) as u64;
+        rem = current % divisor as u128;
+    }
+
+    (quotient, rem as u64)
+}
+
+// Knuth's Algorithm D (TAOCP Vol. 2, 4.3.1).
+fn div_rem(lhs: &[u64], rhs: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    let lhs = trimmed(lhs);
+    let rhs = trimmed(rhs);
+    assert!(!rhs.is_empty(), "division by zero");
+
+    if compare(lhs, rhs) == Ordering::Less {
+        return (Vec::new(), lhs.to_vec());
+    }
+
+    if rhs.len() == 1 {
+        let (quotient, rem) = div_rem_small(lhs, rhs[0]);
+        return (quotient, vec![rem]);
+    }
+
+    // normalize so that the most significant bit of the divisor is set
+    let shift = rhs[rhs.len() - 1].leading_zeros() as usize;
+    let divisor = shl(rhs, shift);
+    let mut rem = shl(lhs, shift);
+
+    let n = rhs.len();
+    let m = lhs.len() - n;
+    rem.resize(lhs.len() + 1, 0);
+
+    let mut quotient = vec![0u64; m + 1];
+    let top = divisor[n - 1] as u128;
+    let second = divisor[n - 2] as u128;
+
+    for j in (0..=m).rev() {
+        let current = (rem[j + n] as u128) << 64 | rem[j + n - 1] as u128;
+        let mut qhat = current / top;
+        let mut rhat = current % top;
+
+        while qhat > u64::max_value() as u128
+            || qhat * second > (rhat << 64 | rem[j + n - 2] as u128)
+        {
+            qhat -= 1;
+            rhat += top;
+
+            if rhat > u64::max_value() as u128 {
+                break;
+            }
+        }
+
+        // subtract qhat * divisor
+        let mut borrow: i128 = 0;
+        let mut carry: u128 = 0;
+
+        for i in 0..n {
+            let product = qhat * divisor[i] as u128 + carry;
+            carry = product >> 64;
+
+            let diff = rem[i + j] as i128 - borrow - (product as u64) as i128;
+            rem[i + j] = diff as u64;
+            borrow = (diff < 0) as i128;
+        }
+
+        let diff = rem[j + n] as i128 - borrow - carry as i128;
+        rem[j + n] = diff as u64;
+
+        if diff < 0 {
+            // qhat was one too large, add the divisor back
+            qhat -= 1;
+            let mut carry: u128 = 0;
+
+            for i in 0..n {
+                let sum = rem[i + j] as u128 + divisor[i] as u128 + carry;
+                rem[i + j] = sum as u64;
+                carry = sum >> 64;
+            }
+
+            rem[j + n] = rem[j + n].wrapping_add(carry as u64);
+        }
+
+        quotient[j] = qhat as u64;
+    }
+
+    (quotient, shr(&rem[..n], shift))
+}
+
+fn shl(value: &[u64], bits: usize) -> Vec<u64> {
+    let limbs = bits / 64;
+    let bits = bits % 64;
+    let mut result = vec![0u64; limbs];
+
+    if bits == 0 {
+        result.extend_from_slice(value);
+        return result;
+    }
+
+    let mut carry = 0;
+
+    for &limb in value {
+        result.push(limb << bits | carry);
+        carry = limb >> (64 - bits);
+    }
+
+    result.push(carry);
+    result
+}
+
+fn shr(value: &[u64], bits: usize) -> Vec<u64> {
+    let limbs = bits / 64;
+    let bits = bits % 64;
+
+    if limbs >= value.len() {
+        return Vec::new();
+    }
+
+    let value = &value[limbs..];
+
+    if bits == 0 {
+        return value.to_vec();
+    }
+
+    (0..value.len())
+        .map(|idx| {
+            let high = value.get(idx + 1).cloned().unwrap_or(0);
+            value[idx] >> bits | high << (64 - bits)
+        })
+        .collect()
+}
+
+// Flips all bits and adds one.
+fn negate(limbs: &mut [u64]) {
+    let mut carry = true;
+
+    for limb in limbs {
+        let (value, overflow) = (!*limb).overflowing_add(carry as u64);
+        *limb = value;
+        carry = overflow;
+    }
+}
+
+// Applies the operation to the two's complement of both values and returns
+// the magnitude of the result.
+fn bitwise(lhs_negative: bool, lhs: &[u64], rhs_negative: bool, rhs: &[u64], op: i32) -> Vec<u64> {
+    let len = cmp::max(lhs.len(), rhs.len()) + 1;
+
+    let twos_complement = |negative: bool, value: &[u64]| {
+        let mut result = value.to_vec();
+        result.resize(len, 0);
+
+        if negative {
+            negate(&mut result);
+        }
+
+        result
+    };
+
+    let lhs = twos_complement(lhs_negative, lhs);
+    let rhs = twos_complement(rhs_negative, rhs);
+
+    let mut result: Vec<u64> = lhs
+        .iter()
+        .zip(&rhs)
+        .map(|(&lhs, &rhs)| match op {
+            BITWISE_AND => lhs & rhs,
+            BITWISE_OR => lhs | rhs,
+            BITWISE_XOR => lhs ^ rhs,
+            _ => unreachable!(),
+        })
+        .collect();
+
+    if result[len - 1] >> 63 != 0 {
+        negate(&mut result);
+    }
+
+    result
+}
+
+// The largest power of the radix that fits into a limb and its exponent.
+fn radix_chunk(radix: u32) -> (u64, usize) {
+    let mut power = radix as u64;
+    let mut digits = 1;
+
+    while let Some(next) = power.checked_mul(radix as u64) {
+        power = next;
+        digits += 1;
+    }
+
+    (power, digits)
+}
+
+fn to_string(value: &[u64], radix: u32) -> String {
+    let mut value = trimmed(value).to_vec();
+
+    if value.is_empty() {
+        return "0".into();
+    }
+
+    let (power, digits) = radix_chunk(radix);
+    let mut chunks = Vec::new();
+
+    while !value.is_empty() {
+        let (quotient, chunk) = div_rem_small(&value, power);
+        chunks.push(chunk);
+        value = trimmed(&quotient).to_vec();
+    }
+
+    let mut result = String::new();
+
+    for (idx, &chunk) in chunks.iter().rev().enumerate() {
+        let mut chunk_digits = Vec::with_capacity(digits);
+        let mut chunk = chunk;
+
+        while chunk > 0 {
+            let digit = (chunk % radix as u64) as u32;
+            chunk_digits.push(char::from_digit(digit, radix).unwrap());
+            chunk /= radix as u64;
+        }
+
+        // all chunks except the most significant one have leading zeros
+        if idx > 0 {
+            chunk_digits.resize(digits, '0');
+        }
+
+        result.extend(chunk_digits.iter().rev());
+    }
+
+    result
+}
+
+// All characters need to be valid digits.
+fn parse(digits: &str, radix: u32) -> Vec<u64> {
+    let (_, chunk_digits) = radix_chunk(radix);
+    let mut result: Vec<u64> = Vec::new();
+    let bytes = digits.as_bytes();
+
+    for chunk in bytes.chunks(chunk_digits) {
+        let mut factor = 1u64;
+        let mut value = 0u64;
+
+        for &byte in chunk {
+            let digit = (byte as char).to_digit(radix).expect("invalid digit");
+            factor *= radix as u64;
+            value = value * radix as u64 + digit as u64;
+        }
+
+        // result = result * factor + value
+        let mut carry = value as u128;
+
+        for limb in result.iter_mut() {
+            let current = *limb as u128 * factor as u128 + carry;
+            *limb = current as u64;
+            carry = current >> 64;
+        }
+
+        if carry != 0 {
+            result.push(carry as u64);
+        }
+    }
+
+    result
+}
+
+pub extern "C" fn bigint_compare(lhs: Handle<Int64Array>, rhs: Handle<Int64Array>) -> i32 {
+    match compare(&read(&lhs), &read(&rhs)) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+pub extern "C" fn bigint_add(
+    lhs: Handle<Int64Array>,
+    rhs: Handle<Int64Array>,
+    out: Handle<Int64Array>,
+) {
+    write(out, &add(&read(&lhs), &read(&rhs)));
+}
+
+pub extern "C" fn bigint_sub(
+    lhs: Handle<Int64Array>,
+    rhs: Handle<Int64Array>,
+    out: Handle<Int64Array>,
+) {
+    write(out, &sub(&read(&lhs), &read(&rhs)));
+}
+
+pub extern "C" fn bigint_mul(
+    lhs: Handle<Int64Array>,
+    rhs: Handle<Int64Array>,
+    out: Handle<Int64Array>,
+) {
+    write(out, &mul(&read(&lhs), &read(&rhs)));
+}
+
+pub extern "C" fn bigint_div_rem(
+    lhs: Handle<Int64Array>,
+    rhs: Handle<Int64Array>,
+    quotient: Handle<Int64Array>,
+    rem: Handle<Int64Array>,
+) {
+    let (q, r) = div_rem(&read(&lhs), &read(&rhs));
+    write(quotient, &q);
+    write(rem, &r);
+}
+
+pub extern "C" fn bigint_shift_left(value: Handle<Int64Array>, bits: i64, out: Handle<Int64Array>) {
+    write(out, &shl(&read(&value), bits as usize));
+}
+
+pub extern "C" fn bigint_shift_right(
+    value: Handle<Int64Array>,
+    bits: i64,
+    out: Handle<Int64Array>,
+) {
+    write(out, &shr(&read(&value), bits as usize));
+}
+
+pub extern "C" fn bigint_bitwise(
+    lhs_negative: bool,
+    lhs: Handle<Int64Array>,
+    rhs_negative: bool,
+    rhs: Handle<Int64Array>,
+    op: i32,
+    out: Handle<Int64Array>,
+) {
+    let result = bitwise(lhs_negative, &read(&lhs), rhs_negative, &read(&rhs), op);
+    write(out, &result);
+}
+
+pub extern "C" fn bigint_to_string(value: Handle<Int64Array>, radix: i32) -> Ref<Str> {
+    let value = to_string(&read(&value), radix as u32);
+
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, value.as_bytes())
+    })
+}
+
+pub extern "C" fn bigint_parse(digits: Handle<Str>, radix: i32, out: Handle<Int64Array>) {
+    let digits = str::from_utf8(digits.content()).unwrap();
+    write(out, &parse(digits, radix as u32));
+}
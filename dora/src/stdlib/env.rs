@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::env;
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{Ref, Str};
+use crate::stdlib::io::{blocking, io_result, rust_string, set_last_error};
+use crate::vm::get_vm;
+
+thread_local! {
+    // snapshot of the environment taken by env_vars_snapshot
+    static VARS: RefCell<Vec<(String, String)>> = RefCell::new(Vec::new());
+}
+
+fn to_str(value: &str) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, value.as_bytes())
+    })
+}
+
+pub extern "C" fn env_var_exists(key: Handle<Str>) -> bool {
+    env::var_os(rust_string(key)).is_some()
+}
+
+// Returns the empty string for missing variables. Invalid UTF-8 is
+// replaced.
+pub extern "C" fn env_var(key: Handle<Str>) -> Ref<Str> {
+    let value = env::var_os(rust_string(key))
+        .map(|value| value.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    to_str(&value)
+}
+
+// Returns false for keys or values that the OS can't store. The standard
+// library serializes this with env::var and Command::spawn, but not with
+// libc functions reading the environment in other threads (e.g. getaddrinfo
+// for std::net), see std::env::setVar.
+pub extern "C" fn env_set_var(key: Handle<Str>, value: Handle<Str>) -> bool {
+    let key = rust_string(key);
+    let value = rust_string(value);
+
+    if !valid_key(&key) || value.contains('\0') {
+        return false;
+    }
+
+    env::set_var(key, value);
+    true
+}
+
+// Same restrictions as env_set_var.
+pub extern "C" fn env_remove_var(key: Handle<Str>) -> bool {
+    let key = rust_string(key);
+
+    if !valid_key(&key) {
+        return false;
+    }
+
+    env::remove_var(key);
+    true
+}
+
+fn valid_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains('=') && !key.contains('\0')
+}
+
+// Returns the number of variables in the snapshot.
+pub extern "C" fn env_vars_snapshot() -> i64 {
+    let vars: Vec<(String, String)> = env::vars_os()
+        .map(|(key, value)| {
+            (
+                key.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    let len = vars.len();
+
+    VARS.with(|snapshot| *snapshot.borrow_mut() = vars);
+    len as i64
+}
+
+pub extern "C" fn env_vars_key(idx: i64) -> Ref<Str> {
+    let key = VARS.with(|snapshot| snapshot.borrow()[idx as usize].0.clone());
+    to_str(&key)
+}
+
+pub extern "C" fn env_vars_value(idx: i64) -> Ref<Str> {
+    let value = VARS.with(|snapshot| snapshot.borrow()[idx as usize].1.clone());
+    to_str(&value)
+}
+
+// Returns the empty string on errors.
+pub extern "C" fn env_current_dir() -> Ref<Str> {
+    let vm = get_vm();
+
+    match blocking(vm, || env::current_dir()) {
+        Ok(dir) => to_str(&dir.to_string_lossy()),
+
+        Err(error) => {
+            set_last_error(error);
+            to_str("")
+        }
+    }
+}
+
+pub extern "C" fn env_set_current_dir(dir: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let dir = rust_string(dir);
+    let result = blocking(vm, || env::set_current_dir(&dir));
+
+    io_result(result.map(|_| 0))
+}
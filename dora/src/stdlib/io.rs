@@ -0,0 +1,467 @@
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, ReadDir};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::process::{ChildStderr, ChildStdin, ChildStdout, Command};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{Int64Array, Ref, Str, UInt8Array};
+use crate::stdlib::net::{timed_out, ListenerState};
+use crate::stdlib::process::ChildState;
+use crate::threads::THREAD;
+use crate::vm::{get_vm, VM};
+
+// flags of `std::io::OpenOptions`
+const OPEN_READ: i32 = 1;
+const OPEN_WRITE: i32 = 2;
+const OPEN_APPEND: i32 = 4;
+const OPEN_CREATE: i32 = 8;
+const OPEN_CREATE_NEW: i32 = 16;
+const OPEN_TRUNCATE: i32 = 32;
+
+// needs to match `std::io::Metadata`
+const METADATA_FILE: i64 = 0;
+const METADATA_DIR: i64 = 1;
+const METADATA_SYMLINK: i64 = 2;
+const METADATA_OTHER: i64 = 3;
+
+thread_local! {
+    // error of the last failed I/O function on this thread
+    static LAST_ERROR: RefCell<Option<io::Error>> = RefCell::new(None);
+}
+
+pub enum IoHandle {
+    File(File),
+    Stdin,
+    Dir(Mutex<DirState>),
+    Command(Mutex<Command>),
+    Child(Mutex<ChildState>),
+    ChildStdin(Mutex<ChildStdin>),
+    ChildStdout(Mutex<ChildStdout>),
+    ChildStderr(Mutex<ChildStderr>),
+    TcpListener(ListenerState),
+    TcpStream(TcpStream),
+    UdpSocket(UdpSocket),
+}
+
+impl IoHandle {
+    // The locks are acquired while the thread is parked: other threads
+    // might block on them during I/O.
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            IoHandle::File(file) => (&*file).read(buf),
+            IoHandle::Stdin => io::stdin().read(buf),
+            IoHandle::ChildStdout(pipe) => pipe.lock().read(buf),
+            IoHandle::ChildStderr(pipe) => pipe.lock().read(buf),
+            IoHandle::TcpStream(stream) => (&*stream).read(buf).map_err(timed_out),
+            IoHandle::UdpSocket(socket) => socket.recv(buf).map_err(timed_out),
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            IoHandle::File(file) => (&*file).write(buf),
+            IoHandle::ChildStdin(pipe) => pipe.lock().write(buf),
+            IoHandle::TcpStream(stream) => (&*stream).write(buf).map_err(timed_out),
+            IoHandle::UdpSocket(socket) => socket.send(buf).map_err(timed_out),
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            IoHandle::File(file) => (&*file).write_all(buf),
+            IoHandle::ChildStdin(pipe) => pipe.lock().write_all(buf),
+            IoHandle::TcpStream(stream) => (&*stream).write_all(buf).map_err(timed_out),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+pub struct DirState {
+    entries: ReadDir,
+    name: String,
+}
+
+// Files and other OS resources opened by Dora code. Dora objects only
+// store the id of the handle.
+pub struct IoHandles {
+    next_id: AtomicI64,
+    handles: Mutex<HashMap<i64, Arc<IoHandle>>>,
+}
+
+impl IoHandles {
+    pub fn new() -> IoHandles {
+        IoHandles {
+            next_id: AtomicI64::new(1),
+            handles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn add(&self, handle: IoHandle) -> i64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut handles = self.handles.lock();
+        assert!(handles.insert(id, Arc::new(handle)).is_none());
+        id
+    }
+
+    pub fn get(&self, id: i64) -> Option<Arc<IoHandle>> {
+        let handles = self.handles.lock();
+        handles.get(&id).cloned()
+    }
+
+    pub fn remove(&self, id: i64) -> bool {
+        let mut handles = self.handles.lock();
+        handles.remove(&id).is_some()
+    }
+}
+
+// Runs a blocking operation with the current thread parked, the world can
+// be stopped in the meantime. `f` must not access the heap.
+pub fn blocking<F, R>(vm: &VM, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let thread = THREAD.with(|thread| thread.borrow().clone());
+    thread.park(vm);
+    let result = f();
+    thread.unpark(vm);
+    result
+}
+
+// Converts the result of an I/O operation into the value returned to Dora
+// code: -1 signals an error, details are available through
+// `io_last_error_kind` and `io_last_error_message`.
+pub fn io_result(result: io::Result<i64>) -> i64 {
+    match result {
+        Ok(value) => {
+            assert!(value >= 0);
+            value
+        }
+
+        Err(error) => {
+            set_last_error(error);
+            -1
+        }
+    }
+}
+
+pub fn set_last_error(error: io::Error) {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(error));
+}
+
+pub fn closed_handle() -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, "handle is closed")
+}
+
+pub fn unsupported() -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, "operation not supported by handle")
+}
+
+pub fn with_handle<F>(id: i64, f: F) -> i64
+where
+    F: FnOnce(&VM, &IoHandle) -> io::Result<i64>,
+{
+    let vm = get_vm();
+
+    match vm.io_handles.get(id) {
+        Some(handle) => io_result(f(vm, &handle)),
+        None => io_result(Err(closed_handle())),
+    }
+}
+
+pub fn rust_string(value: Handle<Str>) -> String {
+    String::from_utf8_lossy(value.content()).into_owned()
+}
+
+// needs to match the variants of `std::io::ErrorKind`
+fn error_kind(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::NotFound => 0,
+        ErrorKind::PermissionDenied => 1,
+        ErrorKind::AlreadyExists => 2,
+        ErrorKind::InvalidInput => 3,
+        ErrorKind::InvalidData => 4,
+        ErrorKind::UnexpectedEof => 5,
+        ErrorKind::Interrupted => 6,
+        ErrorKind::WouldBlock => 7,
+        ErrorKind::TimedOut => 8,
+        ErrorKind::BrokenPipe => 9,
+        ErrorKind::ConnectionRefused => 10,
+        ErrorKind::ConnectionReset => 11,
+        ErrorKind::ConnectionAborted => 12,
+        ErrorKind::NotConnected => 13,
+        ErrorKind::AddrInUse => 14,
+        ErrorKind::AddrNotAvailable => 15,
+        _ => 16,
+    }
+}
+
+pub extern "C" fn io_last_error_kind() -> i32 {
+    LAST_ERROR.with(|last_error| {
+        let last_error = last_error.borrow();
+        error_kind(last_error.as_ref().expect("no I/O error").kind())
+    })
+}
+
+pub extern "C" fn io_last_error_message() -> Ref<Str> {
+    let message = LAST_ERROR.with(|last_error| {
+        let last_error = last_error.borrow();
+        last_error.as_ref().expect("no I/O error").to_string()
+    });
+
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, message.as_bytes())
+    })
+}
+
+pub extern "C" fn file_open(name: Handle<Str>, flags: i32) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+
+    let result = blocking(vm, || {
+        OpenOptions::new()
+            .read(flags & OPEN_READ != 0)
+            .write(flags & OPEN_WRITE != 0)
+            .append(flags & OPEN_APPEND != 0)
+            .create(flags & OPEN_CREATE != 0)
+            .create_new(flags & OPEN_CREATE_NEW != 0)
+            .truncate(flags & OPEN_TRUNCATE != 0)
+            .open(&name)
+    });
+
+    io_result(result.map(|file| vm.io_handles.add(IoHandle::File(file))))
+}
+
+pub extern "C" fn file_stdin() -> i64 {
+    let vm = get_vm();
+    vm.io_handles.add(IoHandle::Stdin)
+}
+
+pub extern "C" fn file_read(id: i64, mut buf: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    assert!(offset >= 0 && len >= 0 && (offset + len) as usize <= buf.len());
+
+    // the array might be moved while the thread is parked
+    let mut data = vec![0; len as usize];
+
+    let result = with_handle(id, |vm, handle| {
+        blocking(vm, || handle.read(&mut data)).map(|read| read as i64)
+    });
+
+    if result > 0 {
+        for (idx, &byte) in data[..result as usize].iter().enumerate() {
+            buf.set_at(offset as usize + idx, byte);
+        }
+    }
+
+    result
+}
+
+pub extern "C" fn file_write(id: i64, buf: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    assert!(offset >= 0 && len >= 0 && (offset + len) as usize <= buf.len());
+
+    let data: Vec<u8> = (offset..offset + len)
+        .map(|idx| buf.get_at(idx as usize))
+        .collect();
+
+    write_to_handle(id, &data)
+}
+
+// Unlike file_write all bytes are written.
+pub extern "C" fn file_write_string(id: i64, value: Handle<Str>) -> i64 {
+    let data = value.content().to_vec();
+
+    with_handle(id, |vm, handle| {
+        blocking(vm, || handle.write_all(&data)).map(|_| data.len() as i64)
+    })
+}
+
+fn write_to_handle(id: i64, data: &[u8]) -> i64 {
+    with_handle(id, |vm, handle| {
+        blocking(vm, || handle.write(data)).map(|written| written as i64)
+    })
+}
+
+pub extern "C" fn file_seek(id: i64, whence: i32, offset: i64) -> i64 {
+    with_handle(id, |vm, handle| {
+        let pos = match whence {
+            0 if offset >= 0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "invalid seek to a negative position",
+                ))
+            }
+        };
+
+        blocking(vm, || match handle {
+            IoHandle::File(file) => (&*file).seek(pos),
+            _ => Err(unsupported()),
+        })
+        .map(|pos| pos as i64)
+    })
+}
+
+// Writes data and metadata of the file to disk.
+pub extern "C" fn file_sync(id: i64) -> i64 {
+    with_handle(id, |vm, handle| {
+        blocking(vm, || match handle {
+            IoHandle::File(file) => (&*file).sync_all(),
+            _ => Ok(()),
+        })
+        .map(|_| 0)
+    })
+}
+
+pub extern "C" fn file_close(id: i64) -> bool {
+    let vm = get_vm();
+    vm.io_handles.remove(id)
+}
+
+pub extern "C" fn dir_open(name: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+    let result = blocking(vm, || fs::read_dir(&name));
+
+    io_result(result.map(|entries| {
+        let state = DirState {
+            entries,
+            name: String::new(),
+        };
+        vm.io_handles.add(IoHandle::Dir(Mutex::new(state)))
+    }))
+}
+
+// Advances to the next entry of the directory: returns 1 if there is an
+// entry, 0 at the end of the directory.
+pub extern "C" fn dir_next(id: i64) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::Dir(state) => blocking(vm, || {
+            let mut state = state.lock();
+
+            match state.entries.next() {
+                Some(Ok(entry)) => {
+                    state.name = entry.file_name().to_string_lossy().into_owned();
+                    Ok(1)
+                }
+                Some(Err(error)) => Err(error),
+                None => Ok(0),
+            }
+        }),
+
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn dir_entry_name(id: i64) -> Ref<Str> {
+    let vm = get_vm();
+    let handle = vm.io_handles.get(id).expect("handle is closed");
+
+    let name = match *handle {
+        IoHandle::Dir(ref state) => state.lock().name.clone(),
+        _ => panic!("not a directory"),
+    };
+
+    handle_scope(|| Str::from_buffer(vm, name.as_bytes()))
+}
+
+pub extern "C" fn dir_create(name: Handle<Str>, recursive: bool) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+
+    let result = blocking(vm, || {
+        if recursive {
+            fs::create_dir_all(&name)
+        } else {
+            fs::create_dir(&name)
+        }
+    });
+
+    io_result(result.map(|_| 0))
+}
+
+pub extern "C" fn dir_remove(name: Handle<Str>, recursive: bool) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+
+    let result = blocking(vm, || {
+        if recursive {
+            fs::remove_dir_all(&name)
+        } else {
+            fs::remove_dir(&name)
+        }
+    });
+
+    io_result(result.map(|_| 0))
+}
+
+pub extern "C" fn file_remove(name: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+    let result = blocking(vm, || fs::remove_file(&name));
+
+    io_result(result.map(|_| 0))
+}
+
+pub extern "C" fn file_rename(from: Handle<Str>, to: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let from = rust_string(from);
+    let to = rust_string(to);
+    let result = blocking(vm, || fs::rename(&from, &to));
+
+    io_result(result.map(|_| 0))
+}
+
+// Fills the given array with kind, size, modification time (in
+// milliseconds since the epoch) and read-only flag of the file. Symbolic
+// links aren't followed.
+pub extern "C" fn file_metadata(name: Handle<Str>, mut out: Handle<Int64Array>) -> i64 {
+    let vm = get_vm();
+    let name = rust_string(name);
+    let result = blocking(vm, || fs::symlink_metadata(&name));
+
+    io_result(result.map(|metadata| {
+        let file_type = metadata.file_type();
+
+        let kind = if file_type.is_file() {
+            METADATA_FILE
+        } else if file_type.is_dir() {
+            METADATA_DIR
+        } else if file_type.is_symlink() {
+            METADATA_SYMLINK
+        } else {
+            METADATA_OTHER
+        };
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or(0);
+
+        let values = [
+            kind,
+            metadata.len() as i64,
+            modified,
+            metadata.permissions().readonly() as i64,
+        ];
+
+        assert_eq!(out.len(), values.len());
+
+        for (idx, &value) in values.iter().enumerate() {
+            out.set_at(idx, value);
+        }
+
+        0
+    }))
+}
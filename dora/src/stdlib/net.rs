@@ -0,0 +1,352 @@
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::io::{self, ErrorKind};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{Ref, Str, UInt8Array};
+use crate::stdlib::io::{
+    blocking, closed_handle, rust_string, set_last_error, unsupported, with_handle, IoHandle,
+};
+use crate::vm::get_vm;
+
+// needs to match `std::net::Shutdown`
+const SHUTDOWN_READ: i32 = 0;
+const SHUTDOWN_WRITE: i32 = 1;
+const SHUTDOWN_BOTH: i32 = 2;
+
+const ACCEPT_INTERVAL_MS: u64 = 1;
+
+thread_local! {
+    // sender of the datagram received last by udp_recv_from
+    static LAST_SENDER: RefCell<Option<SocketAddr>> = RefCell::new(None);
+}
+
+pub struct ListenerState {
+    listener: TcpListener,
+    // the listener is in non-blocking mode while a timeout is set
+    timeout: Mutex<Option<Duration>>,
+}
+
+// Timeouts of blocking sockets are reported as WouldBlock on unix.
+pub fn timed_out(error: io::Error) -> io::Error {
+    if error.kind() == ErrorKind::WouldBlock {
+        io::Error::new(ErrorKind::TimedOut, "operation timed out")
+    } else {
+        error
+    }
+}
+
+// Negative values disable the timeout.
+fn timeout(millis: i64) -> Option<Duration> {
+    if millis < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(millis as u64))
+    }
+}
+
+fn resolve(addr: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+
+    if addrs.is_empty() {
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "address resolved to nothing",
+        ))
+    } else {
+        Ok(addrs)
+    }
+}
+
+// Returns the empty string on errors.
+fn addr_to_str(addr: io::Result<SocketAddr>) -> Ref<Str> {
+    let addr = match addr {
+        Ok(addr) => addr.to_string(),
+
+        Err(error) => {
+            set_last_error(error);
+            String::new()
+        }
+    };
+
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, addr.as_bytes())
+    })
+}
+
+fn copy_to_array(data: &[u8], mut buf: Handle<UInt8Array>, offset: i64) {
+    for (idx, &byte) in data.iter().enumerate() {
+        buf.set_at(offset as usize + idx, byte);
+    }
+}
+
+pub extern "C" fn tcp_listen(addr: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let addr = rust_string(addr);
+
+    let result = blocking(vm, || {
+        let listener = TcpListener::bind(resolve(&addr)?.as_slice())?;
+
+        Ok(ListenerState {
+            listener,
+            timeout: Mutex::new(None),
+        })
+    });
+
+    match result {
+        Ok(state) => vm.io_handles.add(IoHandle::TcpListener(state)),
+
+        Err(error) => {
+            set_last_error(error);
+            -1
+        }
+    }
+}
+
+// Without a timeout the thread blocks in accept(), otherwise the
+// non-blocking listener is polled until the deadline.
+pub extern "C" fn tcp_accept(id: i64) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::TcpListener(state) => {
+            let stream = blocking(vm, || {
+                let timeout = *state.timeout.lock();
+
+                let timeout = match timeout {
+                    Some(timeout) => timeout,
+                    None => return state.listener.accept(),
+                };
+
+                let deadline = Instant::now() + timeout;
+
+                loop {
+                    match state.listener.accept() {
+                        Ok(result) => {
+                            // accepted sockets might inherit non-blocking mode
+                            result.0.set_nonblocking(false)?;
+                            return Ok(result);
+                        }
+
+                        Err(ref error)
+                            if error.kind() == ErrorKind::WouldBlock
+                                && Instant::now() < deadline =>
+                        {
+                            thread::sleep(Duration::from_millis(ACCEPT_INTERVAL_MS));
+                        }
+
+                        Err(error) => return Err(timed_out(error)),
+                    }
+                }
+            })?;
+
+            Ok(vm.io_handles.add(IoHandle::TcpStream(stream.0)))
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn tcp_set_accept_timeout(id: i64, millis: i64) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::TcpListener(state) => {
+            let mut current = state.timeout.lock();
+            let timeout = timeout(millis);
+            state.listener.set_nonblocking(timeout.is_some())?;
+            *current = timeout;
+            Ok(0)
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Tries all addresses the name resolves to, negative timeouts block until
+// the OS gives up.
+pub extern "C" fn tcp_connect(addr: Handle<Str>, millis: i64) -> i64 {
+    let vm = get_vm();
+    let addr = rust_string(addr);
+
+    let result = blocking(vm, || {
+        let addrs = resolve(&addr)?;
+
+        match timeout(millis) {
+            Some(timeout) => {
+                let mut last_error = None;
+
+                for addr in &addrs {
+                    match TcpStream::connect_timeout(addr, timeout) {
+                        Ok(stream) => return Ok(stream),
+                        Err(error) => last_error = Some(timed_out(error)),
+                    }
+                }
+
+                Err(last_error.expect("no address"))
+            }
+
+            None => TcpStream::connect(addrs.as_slice()),
+        }
+    });
+
+    match result {
+        Ok(stream) => vm.io_handles.add(IoHandle::TcpStream(stream)),
+
+        Err(error) => {
+            set_last_error(error);
+            -1
+        }
+    }
+}
+
+pub extern "C" fn tcp_shutdown(id: i64, how: i32) -> i64 {
+    let how = match how {
+        SHUTDOWN_READ => Shutdown::Read,
+        SHUTDOWN_WRITE => Shutdown::Write,
+        SHUTDOWN_BOTH => Shutdown::Both,
+        _ => unreachable!(),
+    };
+
+    with_handle(id, |_, handle| match handle {
+        IoHandle::TcpStream(stream) => stream.shutdown(how).map(|_| 0),
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn tcp_set_nodelay(id: i64, nodelay: bool) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::TcpStream(stream) => stream.set_nodelay(nodelay).map(|_| 0),
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn socket_peer_addr(id: i64) -> Ref<Str> {
+    let vm = get_vm();
+
+    let addr = match vm.io_handles.get(id).as_deref() {
+        Some(IoHandle::TcpStream(stream)) => stream.peer_addr(),
+        Some(IoHandle::UdpSocket(socket)) => socket.peer_addr(),
+        Some(_) => Err(unsupported()),
+        None => Err(closed_handle()),
+    };
+
+    addr_to_str(addr)
+}
+
+pub extern "C" fn socket_local_addr(id: i64) -> Ref<Str> {
+    let vm = get_vm();
+
+    let addr = match vm.io_handles.get(id).as_deref() {
+        Some(IoHandle::TcpListener(state)) => state.listener.local_addr(),
+        Some(IoHandle::TcpStream(stream)) => stream.local_addr(),
+        Some(IoHandle::UdpSocket(socket)) => socket.local_addr(),
+        Some(_) => Err(unsupported()),
+        None => Err(closed_handle()),
+    };
+
+    addr_to_str(addr)
+}
+
+// Timeouts of reads and writes on TCP streams and UDP sockets.
+pub extern "C" fn socket_set_read_timeout(id: i64, millis: i64) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::TcpStream(stream) => stream.set_read_timeout(timeout(millis)).map(|_| 0),
+        IoHandle::UdpSocket(socket) => socket.set_read_timeout(timeout(millis)).map(|_| 0),
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn socket_set_write_timeout(id: i64, millis: i64) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::TcpStream(stream) => stream.set_write_timeout(timeout(millis)).map(|_| 0),
+        IoHandle::UdpSocket(socket) => socket.set_write_timeout(timeout(millis)).map(|_| 0),
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn udp_bind(addr: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let addr = rust_string(addr);
+
+    let result = blocking(vm, || UdpSocket::bind(resolve(&addr)?.as_slice()));
+
+    match result {
+        Ok(socket) => vm.io_handles.add(IoHandle::UdpSocket(socket)),
+
+        Err(error) => {
+            set_last_error(error);
+            -1
+        }
+    }
+}
+
+// Afterwards datagrams can be sent with file_write and received with
+// file_read.
+pub extern "C" fn udp_connect(id: i64, addr: Handle<Str>) -> i64 {
+    let addr = rust_string(addr);
+
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::UdpSocket(socket) => {
+            blocking(vm, || socket.connect(resolve(&addr)?.as_slice())).map(|_| 0)
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn udp_send_to(
+    id: i64,
+    buf: Handle<UInt8Array>,
+    offset: i64,
+    len: i64,
+    addr: Handle<Str>,
+) -> i64 {
+    assert!(offset >= 0 && len >= 0 && (offset + len) as usize <= buf.len());
+
+    let addr = rust_string(addr);
+    let data: Vec<u8> = (offset..offset + len)
+        .map(|idx| buf.get_at(idx as usize))
+        .collect();
+
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::UdpSocket(socket) => blocking(vm, || {
+            let addrs = resolve(&addr)?;
+            socket.send_to(&data, addrs[0]).map_err(timed_out)
+        })
+        .map(|sent| sent as i64),
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Returns the size of the datagram, the sender is available through
+// udp_last_sender. Bytes that don't fit into the buffer are discarded.
+pub extern "C" fn udp_recv_from(id: i64, buf: Handle<UInt8Array>, offset: i64, len: i64) -> i64 {
+    assert!(offset >= 0 && len >= 0 && (offset + len) as usize <= buf.len());
+
+    // the array might be moved while the thread is parked
+    let mut data = vec![0; len as usize];
+
+    let result = with_handle(id, |vm, handle| match handle {
+        IoHandle::UdpSocket(socket) => {
+            let (received, sender) =
+                blocking(vm, || socket.recv_from(&mut data).map_err(timed_out))?;
+            LAST_SENDER.with(|last_sender| *last_sender.borrow_mut() = Some(sender));
+            Ok(received as i64)
+        }
+
+        _ => Err(unsupported()),
+    });
+
+    if result > 0 {
+        copy_to_array(&data[..result as usize], buf, offset);
+    }
+
+    result
+}
+
+pub extern "C" fn udp_last_sender() -> Ref<Str> {
+    let sender = LAST_SENDER.with(|last_sender| last_sender.borrow().expect("no datagram"));
+    addr_to_str(Ok(sender))
+}
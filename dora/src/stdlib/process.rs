@@ -0,0 +1,322 @@
+use parking_lot::Mutex;
+use std::io;
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{byte_array_from_buffer, Ref, Str, UInt8Array};
+use crate::stdlib::io::{blocking, rust_string, unsupported, with_handle, IoHandle};
+use crate::vm::get_vm;
+
+// streams and modes of `std::process::Command`
+const STDIN: i32 = 0;
+const STDOUT: i32 = 1;
+const STDERR: i32 = 2;
+
+const STDIO_INHERIT: i32 = 0;
+const STDIO_PIPED: i32 = 1;
+const STDIO_NULL: i32 = 2;
+
+// set in the exit status of processes terminated by a signal, the lower
+// bits contain the signal number
+const SIGNAL_FLAG: i64 = 1 << 32;
+
+pub struct ChildState {
+    // None after child_output waited for the process
+    child: Option<Child>,
+    status: Option<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+fn with_command<F>(id: i64, f: F) -> i64
+where
+    F: FnOnce(&mut Command),
+{
+    with_handle(id, |_, handle| match handle {
+        IoHandle::Command(command) => {
+            f(&mut command.lock());
+            Ok(0)
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+fn encode_status(status: ExitStatus) -> i64 {
+    if let Some(code) = status.code() {
+        return code as u32 as i64;
+    }
+
+    signal(status)
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> i64 {
+    use std::os::unix::process::ExitStatusExt;
+    SIGNAL_FLAG | status.signal().expect("neither exit code nor signal") as i64
+}
+
+#[cfg(not(unix))]
+fn signal(_status: ExitStatus) -> i64 {
+    unreachable!("process without exit code")
+}
+
+pub extern "C" fn command_new(program: Handle<Str>) -> i64 {
+    let vm = get_vm();
+    let command = Command::new(rust_string(program));
+    vm.io_handles.add(IoHandle::Command(Mutex::new(command)))
+}
+
+pub extern "C" fn command_arg(id: i64, arg: Handle<Str>) -> i64 {
+    let arg = rust_string(arg);
+    with_command(id, |command| {
+        command.arg(arg);
+    })
+}
+
+pub extern "C" fn command_env(id: i64, key: Handle<Str>, value: Handle<Str>) -> i64 {
+    let key = rust_string(key);
+    let value = rust_string(value);
+    with_command(id, |command| {
+        command.env(key, value);
+    })
+}
+
+pub extern "C" fn command_env_remove(id: i64, key: Handle<Str>) -> i64 {
+    let key = rust_string(key);
+    with_command(id, |command| {
+        command.env_remove(key);
+    })
+}
+
+pub extern "C" fn command_env_clear(id: i64) -> i64 {
+    with_command(id, |command| {
+        command.env_clear();
+    })
+}
+
+pub extern "C" fn command_current_dir(id: i64, dir: Handle<Str>) -> i64 {
+    let dir = rust_string(dir);
+    with_command(id, |command| {
+        command.current_dir(dir);
+    })
+}
+
+pub extern "C" fn command_stdio(id: i64, stream: i32, mode: i32) -> i64 {
+    let stdio = || match mode {
+        STDIO_INHERIT => Stdio::inherit(),
+        STDIO_PIPED => Stdio::piped(),
+        STDIO_NULL => Stdio::null(),
+        _ => unreachable!(),
+    };
+
+    with_command(id, |command| {
+        match stream {
+            STDIN => command.stdin(stdio()),
+            STDOUT => command.stdout(stdio()),
+            STDERR => command.stderr(stdio()),
+            _ => unreachable!(),
+        };
+    })
+}
+
+pub extern "C" fn command_spawn(id: i64) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::Command(command) => {
+            let child = blocking(vm, || command.lock().spawn())?;
+            let state = ChildState {
+                child: Some(child),
+                status: None,
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            };
+
+            Ok(vm.io_handles.add(IoHandle::Child(Mutex::new(state))))
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Moves a piped stream of the child into its own handle, returns 0 if
+// the stream isn't piped or was already taken.
+pub extern "C" fn child_pipe(id: i64, stream: i32) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::Child(state) => {
+            let mut state = state.lock();
+            let child = state.child.as_mut().ok_or_else(unsupported)?;
+
+            let pipe = match stream {
+                STDIN => child
+                    .stdin
+                    .take()
+                    .map(|pipe| IoHandle::ChildStdin(Mutex::new(pipe))),
+                STDOUT => child
+                    .stdout
+                    .take()
+                    .map(|pipe| IoHandle::ChildStdout(Mutex::new(pipe))),
+                STDERR => child
+                    .stderr
+                    .take()
+                    .map(|pipe| IoHandle::ChildStderr(Mutex::new(pipe))),
+                _ => unreachable!(),
+            };
+
+            Ok(pipe.map(|pipe| vm.io_handles.add(pipe)).unwrap_or(0))
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn child_id(id: i64) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::Child(state) => {
+            let state = state.lock();
+            let child = state.child.as_ref().ok_or_else(unsupported)?;
+            Ok(child.id() as i64)
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Returns the encoded exit status. Doesn't block in Child::wait(), which
+// would keep the lock and block e.g. child_kill. Piped stdin that wasn't
+// taken is closed first.
+pub extern "C" fn child_wait(id: i64) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::Child(state) => blocking(vm, || {
+            if let Some(child) = state.lock().child.as_mut() {
+                drop(child.stdin.take());
+            }
+
+            loop {
+                if let Some(status) = try_wait(handle)? {
+                    return Ok(encode_status(status));
+                }
+
+                let pid = state.lock().child.as_ref().ok_or_else(unsupported)?.id();
+                wait_exited(pid)?;
+            }
+        }),
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Blocks until the process exited without collecting its exit status. The
+// process id stays valid for child_kill until try_wait collects the status.
+#[cfg(unix)]
+fn wait_exited(pid: u32) -> io::Result<()> {
+    loop {
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+
+        if result == 0 {
+            return Ok(());
+        }
+
+        let error = io::Error::last_os_error();
+
+        match error.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            // another thread collected the exit status in the meantime
+            Some(libc::ECHILD) => return Ok(()),
+            _ => return Err(error),
+        }
+    }
+}
+
+// other platforms poll, child_wait retries until try_wait succeeds
+#[cfg(not(unix))]
+fn wait_exited(_pid: u32) -> io::Result<()> {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    Ok(())
+}
+
+fn try_wait(handle: &IoHandle) -> io::Result<Option<ExitStatus>> {
+    match handle {
+        IoHandle::Child(state) => {
+            let mut state = state.lock();
+
+            if state.status.is_none() {
+                let child = state.child.as_mut().ok_or_else(unsupported)?;
+                let status = child.try_wait()?;
+                state.status = status;
+            }
+
+            Ok(state.status)
+        }
+
+        _ => Err(unsupported()),
+    }
+}
+
+// Returns 1 if the process exited, child_wait then returns immediately.
+pub extern "C" fn child_try_wait(id: i64) -> i64 {
+    with_handle(id, |_, handle| {
+        try_wait(handle).map(|status| status.is_some() as i64)
+    })
+}
+
+pub extern "C" fn child_kill(id: i64) -> i64 {
+    with_handle(id, |_, handle| match handle {
+        IoHandle::Child(state) => {
+            let mut state = state.lock();
+            let child = state.child.as_mut().ok_or_else(unsupported)?;
+            child.kill().map(|_| 0)
+        }
+
+        _ => Err(unsupported()),
+    })
+}
+
+// Waits for the process while collecting its piped output, returns the
+// encoded exit status. Piped stdin is closed first.
+pub extern "C" fn child_output(id: i64) -> i64 {
+    with_handle(id, |vm, handle| match handle {
+        IoHandle::Child(state) => blocking(vm, || {
+            // the process can't be killed in the meantime
+            let child = state.lock().child.take().ok_or_else(unsupported)?;
+            let output = child.wait_with_output()?;
+
+            let mut state = state.lock();
+            state.status = Some(output.status);
+            state.stdout = output.stdout;
+            state.stderr = output.stderr;
+
+            Ok(encode_status(output.status))
+        }),
+
+        _ => Err(unsupported()),
+    })
+}
+
+pub extern "C" fn child_output_bytes(id: i64, stream: i32) -> Ref<UInt8Array> {
+    let vm = get_vm();
+    let handle = vm.io_handles.get(id).expect("handle is closed");
+
+    let data = match *handle {
+        IoHandle::Child(ref state) => {
+            let state = state.lock();
+
+            match stream {
+                STDOUT => state.stdout.clone(),
+                STDERR => state.stderr.clone(),
+                _ => unreachable!(),
+            }
+        }
+
+        _ => panic!("not a child process"),
+    };
+
+    handle_scope(|| byte_array_from_buffer(vm, &data))
+}
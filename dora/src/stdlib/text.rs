@@ -0,0 +1,419 @@
+use std::char;
+use std::cmp::Ordering;
+use std::str;
+
+use crate::handle::{scope as handle_scope, Handle};
+use crate::object::{Ref, Str};
+use crate::stdlib::unicode_tables::{COMBINING_CLASSES, COMPOSITIONS, DECOMPOSITIONS};
+use crate::vm::get_vm;
+
+// constants of the algorithmic Hangul (de)composition
+const HANGUL_S_BASE: u32 = 0xAC00;
+const HANGUL_L_BASE: u32 = 0x1100;
+const HANGUL_V_BASE: u32 = 0x1161;
+const HANGUL_T_BASE: u32 = 0x11A7;
+const HANGUL_L_COUNT: u32 = 19;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: u32 = HANGUL_L_COUNT * HANGUL_N_COUNT;
+
+// Primary weight of a letter sorted `offset` positions after `base`.
+const fn after(base: char, offset: u32) -> u32 {
+    (base as u32) << 8 | offset
+}
+
+// Primary weights of letters that are sorted differently than their base
+// letter by a locale, keyed by the lower-case letter.
+const TURKIC_LETTERS: &[(char, u32)] = &[
+    ('ç', after('c', 1)),
+    ('ğ', after('g', 1)),
+    ('ı', after('h', 1)),
+    ('ö', after('o', 1)),
+    ('ş', after('s', 1)),
+    ('ü', after('u', 1)),
+];
+
+const SPANISH_LETTERS: &[(char, u32)] = &[('ñ', after('n', 1))];
+
+const SWEDISH_LETTERS: &[(char, u32)] = &[
+    ('å', after('z', 1)),
+    ('ä', after('z', 2)),
+    ('æ', after('z', 2)),
+    ('ö', after('z', 3)),
+    ('ø', after('z', 3)),
+];
+
+const DANISH_LETTERS: &[(char, u32)] = &[
+    ('æ', after('z', 1)),
+    ('ä', after('z', 1)),
+    ('ø', after('z', 2)),
+    ('ö', after('z', 2)),
+    ('å', after('z', 3)),
+];
+
+// The language subtag of tags like "tr", "tr-TR" or "tr_TR".
+fn language(tag: &str) -> String {
+    tag.split(|ch| ch == '-' || ch == '_')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn is_turkic(language: &str) -> bool {
+    language == "tr" || language == "az"
+}
+
+fn tailored_letters(language: &str) -> &'static [(char, u32)] {
+    match language {
+        "tr" | "az" => TURKIC_LETTERS,
+        "es" => SPANISH_LETTERS,
+        "sv" | "fi" => SWEDISH_LETTERS,
+        "da" | "nb" | "nn" | "no" => DANISH_LETTERS,
+        _ => &[],
+    }
+}
+
+// The full case mappings of Rust's char handle the rules that don't depend
+// on the language, like ß becoming SS or the final sigma.
+fn to_upper(value: &str, language: &str) -> String {
+    if !is_turkic(language) {
+        return value.to_uppercase();
+    }
+
+    value.replace('i', "\u{130}").to_uppercase()
+}
+
+fn to_lower(value: &str, language: &str) -> String {
+    if !is_turkic(language) {
+        return value.to_lowercase();
+    }
+
+    // I with a combining dot above is a dotted i
+    value
+        .replace("I\u{307}", "i")
+        .replace('I', "\u{131}")
+        .replace('\u{130}', "i")
+        .to_lowercase()
+}
+
+fn combining_class(ch: char) -> u8 {
+    let result = COMBINING_CLASSES.binary_search_by(|&(first, last, _)| {
+        if last < ch {
+            Ordering::Less
+        } else if first > ch {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+
+    match result {
+        Ok(idx) => COMBINING_CLASSES[idx].2,
+        Err(_) => 0,
+    }
+}
+
+fn decompose_char(ch: char, result: &mut Vec<char>) {
+    let cp = ch as u32;
+
+    if cp >= HANGUL_S_BASE && cp < HANGUL_S_BASE + HANGUL_S_COUNT {
+        let idx = cp - HANGUL_S_BASE;
+        let l = HANGUL_L_BASE + idx / HANGUL_N_COUNT;
+        let v = HANGUL_V_BASE + (idx % HANGUL_N_COUNT) / HANGUL_T_COUNT;
+        let t = HANGUL_T_BASE + idx % HANGUL_T_COUNT;
+
+        result.push(char::from_u32(l).unwrap());
+        result.push(char::from_u32(v).unwrap());
+
+        if t != HANGUL_T_BASE {
+            result.push(char::from_u32(t).unwrap());
+        }
+
+        return;
+    }
+
+    match DECOMPOSITIONS.binary_search_by_key(&ch, |&(ch, _)| ch) {
+        Ok(idx) => {
+            for &part in DECOMPOSITIONS[idx].1 {
+                decompose_char(part, result);
+            }
+        }
+
+        Err(_) => result.push(ch),
+    }
+}
+
+// Full canonical decomposition followed by the canonical ordering of
+// combining marks.
+fn decompose(value: &str) -> Vec<char> {
+    let mut result = Vec::with_capacity(value.len());
+
+    for ch in value.chars() {
+        decompose_char(ch, &mut result);
+    }
+
+    let mut start = 0;
+
+    while start < result.len() {
+        if combining_class(result[start]) == 0 {
+            start += 1;
+            continue;
+        }
+
+        let mut end = start + 1;
+
+        while end < result.len() && combining_class(result[end]) != 0 {
+            end += 1;
+        }
+
+        // the sort is stable
+        result[start..end].sort_by_key(|&ch| combining_class(ch));
+        start = end;
+    }
+
+    result
+}
+
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (first_cp, second_cp) = (first as u32, second as u32);
+
+    if first_cp >= HANGUL_L_BASE
+        && first_cp < HANGUL_L_BASE + HANGUL_L_COUNT
+        && second_cp >= HANGUL_V_BASE
+        && second_cp < HANGUL_V_BASE + HANGUL_V_COUNT
+    {
+        let l = first_cp - HANGUL_L_BASE;
+        let v = second_cp - HANGUL_V_BASE;
+        return char::from_u32(HANGUL_S_BASE + (l * HANGUL_V_COUNT + v) * HANGUL_T_COUNT);
+    }
+
+    if first_cp >= HANGUL_S_BASE
+        && first_cp < HANGUL_S_BASE + HANGUL_S_COUNT
+        && (first_cp - HANGUL_S_BASE) % HANGUL_T_COUNT == 0
+        && second_cp > HANGUL_T_BASE
+        && second_cp < HANGUL_T_BASE + HANGUL_T_COUNT
+    {
+        return char::from_u32(first_cp + second_cp - HANGUL_T_BASE);
+    }
+
+    COMPOSITIONS
+        .binary_search_by(|&(lhs, rhs, _)| (lhs, rhs).cmp(&(first, second)))
+        .ok()
+        .map(|idx| COMPOSITIONS[idx].2)
+}
+
+// Canonical composition of a decomposed string. A character is blocked
+// from the last starter when a character with the same or a higher
+// combining class or another starter comes in between.
+fn compose(decomposed: Vec<char>) -> String {
+    let mut result: Vec<char> = Vec::with_capacity(decomposed.len());
+    let mut starter: Option<usize> = None;
+    // combining class of the last character after the starter
+    let mut last_class: Option<u8> = None;
+
+    for ch in decomposed {
+        let class = combining_class(ch);
+
+        if let Some(starter) = starter {
+            let blocked = match last_class {
+                Some(last_class) => last_class == 0 || last_class >= class,
+                None => false,
+            };
+
+            if !blocked {
+                if let Some(composed) = compose_pair(result[starter], ch) {
+                    result[starter] = composed;
+                    continue;
+                }
+            }
+        }
+
+        if class == 0 {
+            starter = Some(result.len());
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+
+        result.push(ch);
+    }
+
+    result.into_iter().collect()
+}
+
+fn nfd(value: &str) -> String {
+    decompose(value).into_iter().collect()
+}
+
+fn nfc(value: &str) -> String {
+    compose(decompose(value))
+}
+
+struct CollationElement {
+    // the letter ignoring case and accents
+    primary: u32,
+    // the combining marks
+    secondary: Vec<char>,
+    // 1 for upper-case letters
+    tertiary: u8,
+}
+
+fn collation_elements(value: &str, language: &str) -> Vec<CollationElement> {
+    let letters = tailored_letters(language);
+    let mut elements: Vec<CollationElement> = Vec::new();
+
+    for ch in nfc(value).chars() {
+        let lower = to_lower(&ch.to_string(), language);
+        let lower = lower.chars().next().unwrap_or(ch);
+        let tertiary = (lower != ch) as u8;
+
+        if let Some(&(_, primary)) = letters.iter().find(|&&(letter, _)| letter == lower) {
+            elements.push(CollationElement {
+                primary,
+                secondary: Vec::new(),
+                tertiary,
+            });
+
+            continue;
+        }
+
+        let mut decomposed = Vec::new();
+        decompose_char(ch, &mut decomposed);
+
+        for part in decomposed {
+            if combining_class(part) != 0 && !elements.is_empty() {
+                elements.last_mut().unwrap().secondary.push(part);
+                continue;
+            }
+
+            let lower = to_lower(&part.to_string(), language);
+            let lower = lower.chars().next().unwrap_or(part);
+
+            elements.push(CollationElement {
+                primary: after(lower, 0),
+                secondary: Vec::new(),
+                tertiary,
+            });
+        }
+    }
+
+    elements
+}
+
+// A simplified version of the Unicode Collation Algorithm: letters are
+// compared first, then accents and then case. Remaining ties are broken by
+// comparing the decomposed strings, so only canonically equivalent strings
+// are equal.
+fn collate(lhs: &str, rhs: &str, language: &str) -> Ordering {
+    let lhs_elements = collation_elements(lhs, language);
+    let rhs_elements = collation_elements(rhs, language);
+
+    let primaries = |elements: &[CollationElement]| {
+        elements
+            .iter()
+            .map(|element| element.primary)
+            .collect::<Vec<_>>()
+    };
+
+    let secondaries = |elements: &[CollationElement]| {
+        elements
+            .iter()
+            .map(|element| element.secondary.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let tertiaries = |elements: &[CollationElement]| {
+        elements
+            .iter()
+            .map(|element| element.tertiary)
+            .collect::<Vec<_>>()
+    };
+
+    primaries(&lhs_elements)
+        .cmp(&primaries(&rhs_elements))
+        .then_with(|| secondaries(&lhs_elements).cmp(&secondaries(&rhs_elements)))
+        .then_with(|| tertiaries(&lhs_elements).cmp(&tertiaries(&rhs_elements)))
+        .then_with(|| decompose(lhs).cmp(&decompose(rhs)))
+}
+
+fn rust_str(val: &Handle<Str>) -> &str {
+    str::from_utf8(val.content()).unwrap()
+}
+
+fn to_str(value: String) -> Ref<Str> {
+    handle_scope(|| {
+        let vm = get_vm();
+        Str::from_buffer(vm, value.as_bytes())
+    })
+}
+
+pub extern "C" fn text_to_upper(value: Handle<Str>, locale: Handle<Str>) -> Ref<Str> {
+    let language = language(rust_str(&locale));
+    to_str(to_upper(rust_str(&value), &language))
+}
+
+pub extern "C" fn text_to_lower(value: Handle<Str>, locale: Handle<Str>) -> Ref<Str> {
+    let language = language(rust_str(&locale));
+    to_str(to_lower(rust_str(&value), &language))
+}
+
+pub extern "C" fn text_nfc(value: Handle<Str>) -> Ref<Str> {
+    to_str(nfc(rust_str(&value)))
+}
+
+pub extern "C" fn text_nfd(value: Handle<Str>) -> Ref<Str> {
+    to_str(nfd(rust_str(&value)))
+}
+
+pub extern "C" fn text_compare(lhs: Handle<Str>, rhs: Handle<Str>, locale: Handle<Str>) -> i32 {
+    let language = language(rust_str(&locale));
+
+    match collate(rust_str(&lhs), rust_str(&rhs), &language) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_mapping() {
+        assert_eq!("STRASSE", to_upper("straße", "de"));
+        assert_eq!("İSTANBUL", to_upper("istanbul", "tr"));
+        assert_eq!("ISTANBUL", to_upper("istanbul", "en"));
+        assert_eq!("ıi", to_lower("Iİ", "tr"));
+        assert_eq!("i", to_lower("I\u{307}", "az"));
+        assert_eq!("ὀδυσσεύς", to_lower("ὈΔΥΣΣΕΎΣ", "en"));
+    }
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!("e\u{301}", nfd("é"));
+        assert_eq!("é", nfc("e\u{301}"));
+        assert_eq!("d\u{323}\u{307}", nfd("\u{1e0b}\u{323}"));
+        assert_eq!("\u{1e0d}\u{307}", nfc("\u{1e0b}\u{323}"));
+        assert_eq!("\u{1100}\u{1161}\u{11a8}", nfd("각"));
+        assert_eq!("각", nfc("\u{1100}\u{1161}\u{11a8}"));
+        // composition exclusions and singletons
+        assert_eq!("\u{5d9}\u{5b4}", nfc("\u{fb1d}"));
+        assert_eq!("Å", nfc("\u{212b}"));
+    }
+
+    #[test]
+    fn test_collation() {
+        assert_eq!(Ordering::Less, collate("apple", "Banana", "en"));
+        assert_eq!(Ordering::Less, collate("a", "A", "en"));
+        assert_eq!(Ordering::Less, collate("resume", "résumé", "en"));
+        assert_eq!(Ordering::Less, collate("résumé", "resumes", "en"));
+        assert_eq!(Ordering::Equal, collate("é", "e\u{301}", "fr"));
+        assert_eq!(Ordering::Less, collate("ä", "b", "de"));
+        assert_eq!(Ordering::Greater, collate("ä", "z", "sv"));
+        assert_eq!(Ordering::Less, collate("hz", "ıa", "tr"));
+        assert_eq!(Ordering::Less, collate("ıb", "ia", "tr"));
+        assert_eq!(Ordering::Less, collate("nz", "ña", "es"));
+    }
+}
@@ -6,9 +6,14 @@ use std::sync::Arc;
 
 use crate::gc::{tlab, Address, Region, K};
 use crate::handle::HandleMemory;
-use crate::safepoint;
-use crate::stack::DoraToNativeInfo;
-use crate::vm::{get_vm, VM};
+use crate::mem;
+use crate::stack::{DoraToNativeInfo, NativeStacktrace};
+use crate::threads::fiber::{Fiber, Fibers};
+use crate::threads::parking::ParkingLot;
+use crate::vm::{get_vm, Trap, VM};
+
+pub mod fiber;
+pub mod parking;
 
 pub const STACK_SIZE: usize = 500 * K;
 
@@ -24,6 +29,18 @@ pub struct Threads {
     pub safepoint: Mutex<(usize, usize)>,
 
     pub barrier: Barrier,
+
+    // threads waiting for synchronization primitives
+    pub parking_lot: ParkingLot,
+
+    pub fibers: Fibers,
+
+    // mirrors `ThreadLocalData::concurrent_marking` for newly attached threads,
+    // only modified while all threads are stopped
+    concurrent_marking: AtomicBool,
+
+    // size of the area for thread-local globals in each thread
+    thread_local_size: AtomicUsize,
 }
 
 impl Threads {
@@ -34,19 +51,58 @@ impl Threads {
             next_id: AtomicUsize::new(1),
             safepoint: Mutex::new((0, 1)),
             barrier: Barrier::new(),
+            parking_lot: ParkingLot::new(),
+            fibers: Fibers::new(),
+            concurrent_marking: AtomicBool::new(false),
+            thread_local_size: AtomicUsize::new(0),
         }
     }
 
     pub fn attach_current_thread(&self) {
         THREAD.with(|thread| {
             let mut threads = self.threads.lock();
-            threads.push(thread.borrow().clone());
+            let thread = thread.borrow().clone();
+            thread
+                .tld
+                .set_concurrent_marking(self.concurrent_marking.load(Ordering::Relaxed));
+            thread.init_thread_locals(self.thread_local_size.load(Ordering::Relaxed));
+            threads.push(thread);
         });
     }
 
+    // Attaches a thread spawned by the current thread. A thread stopping the
+    // world holds the lock until the world resumes, the current thread
+    // therefore counts as stopped while waiting for the lock.
     pub fn attach_thread(&self, thread: Arc<DoraThread>) {
-        let mut threads = self.threads.lock();
-        threads.push(thread);
+        let vm = get_vm();
+        let current = THREAD.with(|current| current.borrow().clone());
+        current.park(vm);
+
+        {
+            let mut threads = self.threads.lock();
+            thread
+                .tld
+                .set_concurrent_marking(self.concurrent_marking.load(Ordering::Relaxed));
+            thread.init_thread_locals(self.thread_local_size.load(Ordering::Relaxed));
+            threads.push(thread);
+        }
+
+        current.unpark(vm);
+    }
+
+    // Needs to be invoked before the first thread is attached.
+    pub fn set_thread_local_size(&self, size: usize) {
+        self.thread_local_size.store(size, Ordering::Relaxed);
+    }
+
+    // Enables or disables the pre-write barrier in all threads. Needs to be
+    // invoked while all threads are stopped.
+    pub fn set_concurrent_marking(&self, threads: &[Arc<DoraThread>], value: bool) {
+        self.concurrent_marking.store(value, Ordering::Relaxed);
+
+        for thread in threads {
+            thread.tld.set_concurrent_marking(value);
+        }
     }
 
     pub fn next_id(&self) -> usize {
@@ -88,6 +144,7 @@ impl Threads {
         THREAD.with(|thread| {
             thread.borrow().park(vm);
             let mut threads = self.threads.lock();
+            vm.gc.detach_thread(&*thread.borrow());
             threads.retain(|elem| !Arc::ptr_eq(elem, &*thread.borrow()));
             self.cond_join.notify_all();
         });
@@ -120,6 +177,29 @@ pub struct DoraThread {
     pub saved_pc: AtomicUsize,
     pub saved_fp: AtomicUsize,
     pub state: StateManager,
+
+    // the corresponding std::Thread object, a root for the GC. Null for
+    // the main thread until `Thread::current()` is invoked.
+    object: AtomicUsize,
+
+    // traps are reported to `Thread::join()` instead of exiting
+    catch_traps: AtomicBool,
+    trap: Mutex<Option<(Trap, NativeStacktrace)>>,
+
+    // stack pointer stored by the catch stub when the thread started, a
+    // caught trap continues there
+    trap_sp: AtomicUsize,
+
+    // values and initialization flags of all thread-local globals, a root
+    // for the GC. `ThreadLocalData::thread_locals` points to it.
+    thread_locals: Mutex<Vec<usize>>,
+
+    // the fiber running on this thread
+    fiber: Mutex<Option<Arc<Fiber>>>,
+
+    // references overwritten by this thread during concurrent marking,
+    // handed over to the marker in batches
+    satb_buffer: Mutex<Vec<Address>>,
 }
 
 unsafe impl Sync for DoraThread {}
@@ -134,7 +214,7 @@ impl DoraThread {
         DoraThread::with_id(0)
     }
 
-    fn with_id(id: usize) -> Arc<DoraThread> {
+    pub fn with_id(id: usize) -> Arc<DoraThread> {
         Arc::new(DoraThread {
             id: AtomicUsize::new(id),
             handles: HandleMemory::new(),
@@ -142,6 +222,13 @@ impl DoraThread {
             saved_pc: AtomicUsize::new(0),
             saved_fp: AtomicUsize::new(0),
             state: StateManager::new(),
+            object: AtomicUsize::new(0),
+            catch_traps: AtomicBool::new(false),
+            trap: Mutex::new(None),
+            trap_sp: AtomicUsize::new(0),
+            thread_locals: Mutex::new(Vec::new()),
+            fiber: Mutex::new(None),
+            satb_buffer: Mutex::new(Vec::new()),
         })
     }
 
@@ -149,6 +236,79 @@ impl DoraThread {
         self.id.load(Ordering::Relaxed)
     }
 
+    pub fn object(&self) -> Address {
+        self.object.load(Ordering::Relaxed).into()
+    }
+
+    pub fn set_object(&self, object: Address) {
+        self.object.store(object.to_usize(), Ordering::Relaxed);
+    }
+
+    // slot of the std::Thread object for the root set
+    pub fn object_slot(&self) -> Address {
+        Address::from_ptr(&self.object)
+    }
+
+    // Zero-initialized: all thread-local globals are uninitialized and
+    // hold null references.
+    fn init_thread_locals(&self, size: usize) {
+        let words = (size + mem::ptr_width_usize() - 1) / mem::ptr_width_usize();
+        let mut thread_locals = self.thread_locals.lock();
+        *thread_locals = vec![0; words];
+        self.tld
+            .thread_locals
+            .store(thread_locals.as_ptr() as usize, Ordering::Relaxed);
+    }
+
+    pub fn thread_locals(&self) -> Address {
+        self.tld.thread_locals.load(Ordering::Relaxed).into()
+    }
+
+    pub fn satb_buffer(&self) -> &Mutex<Vec<Address>> {
+        &self.satb_buffer
+    }
+
+    pub fn fiber(&self) -> Option<Arc<Fiber>> {
+        self.fiber.lock().clone()
+    }
+
+    // Returns the previously running fiber.
+    pub fn set_fiber(&self, fiber: Option<Arc<Fiber>>) -> Option<Arc<Fiber>> {
+        std::mem::replace(&mut *self.fiber.lock(), fiber)
+    }
+
+    pub fn catches_traps(&self) -> bool {
+        self.catch_traps.load(Ordering::Relaxed)
+    }
+
+    pub fn set_catch_traps(&self, value: bool) {
+        self.catch_traps.store(value, Ordering::Relaxed);
+    }
+
+    pub fn set_trap(&self, trap: Trap, stacktrace: NativeStacktrace) {
+        *self.trap.lock() = Some((trap, stacktrace));
+    }
+
+    pub fn trapped(&self) -> bool {
+        self.trap.lock().is_some()
+    }
+
+    pub fn trap_sp(&self) -> usize {
+        self.trap_sp.load(Ordering::Relaxed)
+    }
+
+    pub fn trap_sp_slot(&self) -> *const AtomicUsize {
+        &self.trap_sp as *const _
+    }
+
+    pub fn with_trap<F, R>(&self, fct: F) -> R
+    where
+        F: FnOnce(&(Trap, NativeStacktrace)) -> R,
+    {
+        let trap = self.trap.lock();
+        fct(trap.as_ref().expect("thread did not trap"))
+    }
+
     pub fn dtn(&self) -> *const DoraToNativeInfo {
         self.tld.dtn.load(Ordering::Relaxed) as *const _
     }
@@ -193,11 +353,24 @@ impl DoraThread {
     }
 
     pub fn unpark(&self, vm: &VM) {
-        if vm.threads.safepoint_id() != 0 {
-            safepoint::block(vm, self);
+        loop {
+            let safepoint_id = {
+                // a safepoint can't be requested while the state changes,
+                // otherwise the thread would run while the world is stopped
+                let safepoint = vm.threads.safepoint.lock();
+
+                if safepoint.0 == 0 {
+                    self.state.unpark(vm);
+                    return;
+                }
+
+                safepoint.0
+            };
+
+            // parked threads count as stopped: wait until the safepoint
+            // is over before running again
+            vm.threads.barrier.wait(safepoint_id);
         }
-
-        self.state.unpark(vm);
     }
 
     pub fn block(&self, safepoint_id: usize) {
@@ -331,6 +504,7 @@ pub struct ThreadLocalData {
     real_stack_limit: AtomicUsize,
     safepoint_requested: AtomicBool,
     dtn: AtomicUsize,
+    thread_locals: AtomicUsize,
 }
 
 impl ThreadLocalData {
@@ -343,6 +517,7 @@ impl ThreadLocalData {
             real_stack_limit: AtomicUsize::new(0),
             safepoint_requested: AtomicBool::new(false),
             dtn: AtomicUsize::new(0),
+            thread_locals: AtomicUsize::new(0),
         }
     }
 
@@ -406,6 +581,10 @@ impl ThreadLocalData {
         offset_of!(ThreadLocalData, dtn) as i32
     }
 
+    pub fn thread_locals_offset() -> i32 {
+        offset_of!(ThreadLocalData, thread_locals) as i32
+    }
+
     pub fn arm_stack_guard(&self) {
         self.guard_stack_limit.store(!0, Ordering::Release);
     }
@@ -414,6 +593,14 @@ impl ThreadLocalData {
         self.safepoint_requested.store(true, Ordering::Relaxed);
     }
 
+    pub fn concurrent_marking(&self) -> bool {
+        self.concurrent_marking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_concurrent_marking(&self, value: bool) {
+        self.concurrent_marking.store(value, Ordering::Relaxed);
+    }
+
     pub fn clear_safepoint_requested(&self) {
         self.safepoint_requested.store(false, Ordering::Relaxed);
     }
@@ -0,0 +1,251 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::cpu;
+use crate::gc::{Address, K};
+use crate::os::{self, MemoryPermission};
+use crate::stack::DoraToNativeInfo;
+use crate::threads::DoraThread;
+use crate::vm::VM;
+
+// default size of the whole stack of a fiber, see --fiber-stack-size
+pub const FIBER_STACK_SIZE: usize = 256 * K;
+
+// Native code invoked from a fiber (e.g. the compiler or the GC) runs on
+// the fiber's stack as well, Dora code can't use the lowest quarter of
+// the stack.
+const FIBER_NATIVE_STACK_RATIO: usize = 4;
+
+// All fibers that were started but didn't finish yet.
+pub struct Fibers {
+    fibers: Mutex<HashMap<usize, Arc<Fiber>>>,
+}
+
+impl Fibers {
+    pub fn new() -> Fibers {
+        Fibers {
+            fibers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn add(&self, fiber: Arc<Fiber>) -> usize {
+        let id = fiber.id();
+        let mut fibers = self.fibers.lock();
+        assert!(fibers.insert(id, fiber).is_none());
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Arc<Fiber> {
+        let fibers = self.fibers.lock();
+        fibers.get(&id).expect("fiber not found").clone()
+    }
+
+    pub fn remove(&self, id: usize) {
+        let mut fibers = self.fibers.lock();
+        assert!(fibers.remove(&id).is_some());
+    }
+
+    pub fn each<F>(&self, mut f: F)
+    where
+        F: FnMut(&Arc<Fiber>),
+    {
+        let fibers = self.fibers.lock();
+
+        for fiber in fibers.values() {
+            f(fiber)
+        }
+    }
+}
+
+pub struct Fiber {
+    // the corresponding std::Fiber object, a root for the GC
+    object: AtomicUsize,
+
+    // a fiber only runs on the thread that started it
+    thread_id: usize,
+    stack: Address,
+    stack_size: usize,
+    finished: AtomicBool,
+
+    // stack pointer while the fiber is suspended
+    sp: AtomicUsize,
+
+    // First and last DTN on the fiber's stack while the fiber is suspended.
+    // The last DTN is detached from the DTNs of the resuming code, the
+    // frames of a suspended fiber are only reachable through `dtn`.
+    dtn: AtomicUsize,
+    last_dtn: AtomicUsize,
+
+    // state of the resuming code, restored when the fiber yields
+    resumer_sp: AtomicUsize,
+    resumer_dtn: AtomicUsize,
+    resumer_stack_limit: AtomicUsize,
+    resumer_fiber: Mutex<Option<Arc<Fiber>>>,
+}
+
+impl Fiber {
+    pub fn new(vm: &VM, object: Address, thread_id: usize, entry: Address) -> Arc<Fiber> {
+        let stack_size = vm.args.fiber_stack_size();
+        let stack = os::commit(stack_size, false);
+
+        // native code overflowing the stack faults instead of overwriting
+        // other memory
+        os::protect(stack, os::page_size(), MemoryPermission::None);
+
+        let stack_top = stack.offset(stack_size);
+        let sp = cpu::fiber_stack_pointer(stack_top, entry);
+
+        Arc::new(Fiber {
+            object: AtomicUsize::new(object.to_usize()),
+            thread_id,
+            stack,
+            stack_size,
+            finished: AtomicBool::new(false),
+            sp: AtomicUsize::new(sp.to_usize()),
+            dtn: AtomicUsize::new(0),
+            last_dtn: AtomicUsize::new(0),
+            resumer_sp: AtomicUsize::new(0),
+            resumer_dtn: AtomicUsize::new(0),
+            resumer_stack_limit: AtomicUsize::new(0),
+            resumer_fiber: Mutex::new(None),
+        })
+    }
+
+    pub fn id(&self) -> usize {
+        self as *const Fiber as usize
+    }
+
+    pub fn thread_id(&self) -> usize {
+        self.thread_id
+    }
+
+    pub fn object(&self) -> Address {
+        self.object.load(Ordering::Relaxed).into()
+    }
+
+    // slot of the std::Fiber object for the root set
+    pub fn object_slot(&self) -> Address {
+        Address::from_ptr(&self.object)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    // First DTN on the stack of the suspended fiber, null while the fiber
+    // runs or wasn't started yet.
+    pub fn dtn(&self) -> *const DoraToNativeInfo {
+        self.dtn.load(Ordering::Relaxed) as *const _
+    }
+
+    fn stack_limit(&self) -> Address {
+        self.stack
+            .offset(self.stack_size / FIBER_NATIVE_STACK_RATIO)
+    }
+
+    // Runs the fiber on the current thread until it yields or finishes.
+    // Returns true when the fiber finished.
+    pub fn resume(self: &Arc<Fiber>, vm: &VM, thread: &DoraThread) -> bool {
+        assert_eq!(thread.id(), self.thread_id);
+        assert!(!self.is_finished());
+
+        self.resumer_dtn
+            .store(thread.dtn() as usize, Ordering::Relaxed);
+        self.resumer_stack_limit
+            .store(thread.tld.real_stack_limit().to_usize(), Ordering::Relaxed);
+        *self.resumer_fiber.lock() = thread.set_fiber(Some(self.clone()));
+
+        let dtn = self.dtn.swap(0, Ordering::Relaxed) as *const DoraToNativeInfo;
+
+        if !dtn.is_null() {
+            // attach the suspended fiber's frames to the resuming code
+            let last_dtn = self.last_dtn.swap(0, Ordering::Relaxed) as *mut DoraToNativeInfo;
+            unsafe {
+                (*last_dtn).last = thread.dtn();
+            }
+            thread.set_dtn(dtn);
+        }
+
+        thread.tld.set_stack_limit(self.stack_limit());
+        switch_stack(vm, &self.resumer_sp, self.sp.load(Ordering::Relaxed));
+
+        self.is_finished()
+    }
+
+    // Invoked on the fiber's stack, continues with the code that resumed
+    // the fiber until the fiber is resumed again.
+    pub fn suspend(&self, vm: &VM, thread: &DoraThread) {
+        let resumer_dtn = self.resumer_dtn.load(Ordering::Relaxed) as *const DoraToNativeInfo;
+        let dtn = thread.dtn();
+        let mut last_dtn = dtn as *mut DoraToNativeInfo;
+
+        unsafe {
+            while (*last_dtn).last != resumer_dtn {
+                last_dtn = (*last_dtn).last as *mut DoraToNativeInfo;
+            }
+
+            (*last_dtn).last = ptr::null();
+        }
+
+        self.dtn.store(dtn as usize, Ordering::Relaxed);
+        self.last_dtn.store(last_dtn as usize, Ordering::Relaxed);
+
+        self.leave(thread);
+        switch_stack(vm, &self.sp, self.resumer_sp.load(Ordering::Relaxed));
+    }
+
+    // Invoked on the fiber's stack after the fiber ran to completion,
+    // continues with the code that resumed the fiber. The fiber's stack
+    // is freed once the resuming code drops the fiber.
+    pub fn finish(self: Arc<Fiber>, vm: &VM, thread: Arc<DoraThread>) -> ! {
+        self.finished.store(true, Ordering::Relaxed);
+        self.leave(&thread);
+
+        let save = &self.sp as *const AtomicUsize;
+        let sp = self.resumer_sp.load(Ordering::Relaxed);
+
+        // nothing on this stack gets dropped after switching
+        mem::drop(thread);
+        mem::drop(self);
+
+        switch_stack_raw(vm, save, sp);
+        unreachable!("finished fiber resumed");
+    }
+
+    // Invoked after the thread running this fiber trapped, the fiber can't
+    // be resumed anymore. Returns the fiber that resumed this fiber.
+    pub fn abort(&self) -> Option<Arc<Fiber>> {
+        self.finished.store(true, Ordering::Relaxed);
+        self.resumer_fiber.lock().take()
+    }
+
+    fn leave(&self, thread: &DoraThread) {
+        thread.set_dtn(self.resumer_dtn.load(Ordering::Relaxed) as *const _);
+        thread
+            .tld
+            .set_stack_limit(self.resumer_stack_limit.load(Ordering::Relaxed).into());
+        thread.set_fiber(self.resumer_fiber.lock().take());
+    }
+}
+
+impl Drop for Fiber {
+    fn drop(&mut self) {
+        os::free(self.stack, self.stack_size);
+    }
+}
+
+fn switch_stack(vm: &VM, save: &AtomicUsize, sp: usize) {
+    switch_stack_raw(vm, save as *const _, sp);
+}
+
+// Stores the stack pointer in save and continues on the stack sp.
+pub fn switch_stack_raw(vm: &VM, save: *const AtomicUsize, sp: usize) {
+    let fiber_stub_address = vm.fiber_stub();
+    let fct: extern "C" fn(*const AtomicUsize, usize) =
+        unsafe { mem::transmute(fiber_stub_address) };
+    fct(save, sp);
+}
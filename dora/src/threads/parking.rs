@@ -0,0 +1,170 @@
+use parking_lot::{Condvar, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::threads::THREAD;
+use crate::vm::VM;
+
+// Queues of threads waiting for a synchronization primitive like
+// `std::Mutex`. The GC might move objects while threads wait on them,
+// therefore queues are identified by keys stored in the objects and not by
+// object addresses.
+pub struct ParkingLot {
+    queues: Mutex<HashMap<u64, VecDeque<Arc<Waiter>>>>,
+    next_key: AtomicU64,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParkResult {
+    Unparked,
+    Invalid,
+    TimedOut,
+}
+
+impl ParkingLot {
+    pub fn new() -> ParkingLot {
+        ParkingLot {
+            queues: Mutex::new(HashMap::new()),
+            next_key: AtomicU64::new(1),
+        }
+    }
+
+    pub fn next_key(&self) -> u64 {
+        self.next_key.fetch_add(1, Ordering::Relaxed)
+    }
+
+    // Parks the current thread in the queue for `key` until another thread
+    // unparks it or the timeout expires. The thread does not wait at all when
+    // `validate` returns false. `validate` is invoked while all queues are
+    // locked and before the thread is parked, so it is still allowed to
+    // access heap objects. The GC might run while the thread waits.
+    pub fn park<F>(&self, vm: &VM, key: u64, validate: F, timeout: Option<Duration>) -> ParkResult
+    where
+        F: FnOnce() -> bool,
+    {
+        let waiter = Arc::new(Waiter::new());
+
+        {
+            let mut queues = self.queues.lock();
+
+            if !validate() {
+                return ParkResult::Invalid;
+            }
+
+            queues
+                .entry(key)
+                .or_insert_with(VecDeque::new)
+                .push_back(waiter.clone());
+        }
+
+        let thread = THREAD.with(|thread| thread.borrow().clone());
+        thread.park(vm);
+
+        let result = if waiter.wait(timeout) {
+            ParkResult::Unparked
+        } else {
+            let mut queues = self.queues.lock();
+
+            // waiters are unparked while holding the lock, the waiter might
+            // have been unparked after the timeout expired
+            if waiter.is_notified() {
+                ParkResult::Unparked
+            } else {
+                let queue = queues.get_mut(&key).expect("missing queue");
+                queue.retain(|elem| !Arc::ptr_eq(elem, &waiter));
+
+                if queue.is_empty() {
+                    queues.remove(&key);
+                }
+
+                ParkResult::TimedOut
+            }
+        };
+
+        thread.unpark(vm);
+        result
+    }
+
+    // Unparks the thread that waits the longest in the queue for `key`.
+    // Returns true if a thread was waiting.
+    pub fn unpark_one(&self, key: u64) -> bool {
+        let mut queues = self.queues.lock();
+
+        let waiter = if let Some(queue) = queues.get_mut(&key) {
+            let waiter = queue.pop_front().expect("empty queue");
+
+            if queue.is_empty() {
+                queues.remove(&key);
+            }
+
+            waiter
+        } else {
+            return false;
+        };
+
+        waiter.notify();
+        true
+    }
+
+    // Unparks all threads in the queue for `key` and returns their number.
+    pub fn unpark_all(&self, key: u64) -> usize {
+        let mut queues = self.queues.lock();
+
+        if let Some(queue) = queues.remove(&key) {
+            for waiter in &queue {
+                waiter.notify();
+            }
+
+            queue.len()
+        } else {
+            0
+        }
+    }
+}
+
+struct Waiter {
+    notified: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Waiter {
+    fn new() -> Waiter {
+        Waiter {
+            notified: Mutex::new(false),
+            cond: Condvar::new(),
+        }
+    }
+
+    fn is_notified(&self) -> bool {
+        *self.notified.lock()
+    }
+
+    fn notify(&self) {
+        let mut notified = self.notified.lock();
+        *notified = true;
+        self.cond.notify_one();
+    }
+
+    // returns false when the timeout expired before the notification
+    fn wait(&self, timeout: Option<Duration>) -> bool {
+        let mut notified = self.notified.lock();
+
+        if let Some(timeout) = timeout {
+            let deadline = Instant::now() + timeout;
+
+            while !*notified {
+                if self.cond.wait_until(&mut notified, deadline).timed_out() {
+                    break;
+                }
+            }
+        } else {
+            while !*notified {
+                self.cond.wait(&mut notified);
+            }
+        }
+
+        *notified
+    }
+}
@@ -299,6 +299,29 @@ impl SourceType {
         cls.subclass_from(vm, ty.cls_id().unwrap())
     }
 
+    // Walks up the superclasses of this class type until it reaches class
+    // cls_id, the type arguments are substituted along the way.
+    pub fn superclass_with_id(&self, vm: &VM, cls_id: ClassId) -> Option<SourceType> {
+        let mut ty = self.clone();
+
+        loop {
+            let ty_cls_id = ty.cls_id()?;
+
+            if ty_cls_id == cls_id {
+                return Some(ty);
+            }
+
+            let parent_class = {
+                let cls = vm.classes.idx(ty_cls_id);
+                let cls = cls.read();
+                cls.parent_class.clone()?
+            };
+
+            let type_params = ty.type_params(vm);
+            ty = semck::specialize::replace_type_param(vm, parent_class, &type_params, None);
+        }
+    }
+
     pub fn name(&self, vm: &VM) -> String {
         let writer = SourceTypePrinter {
             vm,
@@ -5,9 +5,11 @@ use std::ptr;
 use std::sync::Arc;
 
 use crate::compiler;
+use crate::compiler::catch_stub;
 use crate::compiler::compile_stub;
 use crate::compiler::dora_stub;
 use crate::compiler::fct::JitFct;
+use crate::compiler::fiber_stub;
 use crate::compiler::map::{CodeDescriptor, CodeMap};
 use crate::compiler::native_stub::{self, NativeFct, NativeFctDescriptor, NativeStubs};
 use crate::driver::cmd::Args;
@@ -17,6 +19,7 @@ use crate::object::{Ref, Testing};
 use crate::safepoint;
 use crate::stack::DoraToNativeInfo;
 use crate::stdlib;
+use crate::stdlib::io::IoHandles;
 use crate::sym::{NestedSymTable, SymTable};
 use crate::threads::{Threads, STACK_SIZE, THREAD};
 use crate::ty::{LambdaTypes, SourceType, SourceTypeArray, SourceTypeArrays};
@@ -140,7 +143,10 @@ pub struct VM {
     pub trap_stub: Mutex<Address>,
     pub guard_check_stub: Mutex<Address>,
     pub safepoint_stub: Mutex<Address>,
+    pub fiber_stub: Mutex<Address>,
+    pub catch_stub: Mutex<Address>,
     pub threads: Threads,
+    pub io_handles: IoHandles,
     pub parse_arg_file: bool,
     pub prelude_namespace_id: NamespaceId,
     pub stdlib_namespace_id: NamespaceId,
@@ -200,6 +206,12 @@ impl VM {
                     string_buffer_empty: empty_fct_id,
                     string_buffer_append: empty_fct_id,
                     string_buffer_to_string: empty_fct_id,
+                    format_uint8: empty_fct_id,
+                    format_int32: empty_fct_id,
+                    format_int64: empty_fct_id,
+                    format_float32: empty_fct_id,
+                    format_float64: empty_fct_id,
+                    format_string: empty_fct_id,
                 },
 
                 traits: KnownTraits {
@@ -248,7 +260,10 @@ impl VM {
             trap_stub: Mutex::new(Address::null()),
             guard_check_stub: Mutex::new(Address::null()),
             safepoint_stub: Mutex::new(Address::null()),
+            fiber_stub: Mutex::new(Address::null()),
+            catch_stub: Mutex::new(Address::null()),
             threads: Threads::new(),
+            io_handles: IoHandles::new(),
             parse_arg_file: true,
             prelude_namespace_id,
             stdlib_namespace_id,
@@ -679,6 +694,26 @@ impl VM {
         *safepoint_stub_address
     }
 
+    pub fn fiber_stub(&self) -> Address {
+        let mut fiber_stub_address = self.fiber_stub.lock();
+
+        if fiber_stub_address.is_null() {
+            *fiber_stub_address = fiber_stub::generate(self);
+        }
+
+        *fiber_stub_address
+    }
+
+    pub fn catch_stub(&self) -> Address {
+        let mut catch_stub_address = self.catch_stub.lock();
+
+        if catch_stub_address.is_null() {
+            *catch_stub_address = catch_stub::generate(self);
+        }
+
+        *catch_stub_address
+    }
+
     pub fn file(&self, idx: FileId) -> Arc<ast::File> {
         self.files.read().get(idx.to_usize()).unwrap().ast.clone()
     }
@@ -740,4 +775,17 @@ impl Trap {
             _ => None,
         }
     }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Trap::DIV0 => "division by 0",
+            Trap::ASSERT => "assert failed",
+            Trap::INDEX_OUT_OF_BOUNDS => "array index out of bounds",
+            Trap::NIL => "nil check failed",
+            Trap::CAST => "cast failed",
+            Trap::OOM => "out of memory",
+            Trap::STACK_OVERFLOW => "stack overflow",
+            Trap::ILLEGAL => "illegal state",
+        }
+    }
 }
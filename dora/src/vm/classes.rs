@@ -345,11 +345,13 @@ pub fn find_methods_in_class(
         }
     }
 
-    // Find extension methods
+    // Find extension methods, an extension for a concrete type like
+    // Array[Int32] takes precedence over a generic extension.
     {
         let cls_id = object_type.cls_id().expect("no class");
         let cls = vm.classes.idx(cls_id);
         let cls = cls.read();
+        let mut generic_candidate = None;
 
         for &extension_id in &cls.extensions {
             if let Some(bindings) = extension_matches(
@@ -368,14 +370,24 @@ pub fn find_methods_in_class(
                 };
 
                 if let Some(&fct_id) = table.get(&name) {
-                    return vec![Candidate {
-                        object_type,
+                    let candidate = Candidate {
+                        object_type: object_type.clone(),
                         container_type_params: bindings,
                         fct_id: fct_id,
-                    }];
+                    };
+
+                    if extension.type_params.is_empty() {
+                        return vec![candidate];
+                    } else if generic_candidate.is_none() {
+                        generic_candidate = Some(candidate);
+                    }
                 }
             }
         }
+
+        if let Some(candidate) = generic_candidate {
+            return vec![candidate];
+        }
     }
 
     let mut class_type = object_type;
@@ -504,7 +516,7 @@ pub struct TypeParamBound {
     pub trait_id: TraitId,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClassDefId(usize);
 
 impl ClassDefId {
@@ -9,6 +9,7 @@ use dora_parser::ast;
 use dora_parser::interner::Name;
 use dora_parser::lexer::position::Position;
 
+use crate::semck::specialize::replace_type_param;
 use crate::ty::{SourceType, SourceTypeArray};
 use crate::utils::GrowableVec;
 use crate::vm::{
@@ -110,12 +111,24 @@ pub struct EnumDef {
 }
 
 impl EnumDef {
-    pub fn field_id(&self, xenum: &EnumData, variant_id: usize, element: u32) -> u32 {
-        let variant = &xenum.variants[variant_id];
+    // type of the element after replacing the type params of the enum
+    pub fn element_ty(
+        &self,
+        vm: &VM,
+        xenum: &EnumData,
+        variant_id: usize,
+        element: u32,
+    ) -> SourceType {
+        let ty = xenum.variants[variant_id].types[element as usize].clone();
+        replace_type_param(vm, ty, &self.type_params, None)
+    }
+
+    // unit elements are not stored in the variant's object
+    pub fn field_id(&self, vm: &VM, xenum: &EnumData, variant_id: usize, element: u32) -> u32 {
         let mut units = 0;
 
-        for ty in &variant.types[0..element as usize] {
-            if ty.is_unit() {
+        for idx in 0..element {
+            if self.element_ty(vm, xenum, variant_id, idx).is_unit() {
                 units += 1;
             }
         }
@@ -371,6 +371,9 @@ pub enum Intrinsic {
 
     Unreachable,
     UnsafeKillRefs,
+    UnsafeIsNull,
+    WeakRefIsCleared,
+    WeakRefKeepAlive,
 
     Assert,
     Debug,
@@ -493,6 +496,10 @@ pub enum Intrinsic {
     Float32Neg,
     Float32IsNan,
     Float32Sqrt,
+    Float32Abs,
+    Float32RoundDown,
+    Float32RoundUp,
+    Float32RoundToZero,
 
     Float64ToInt32,
     Float64ToInt64,
@@ -511,16 +518,40 @@ pub enum Intrinsic {
     Float64Neg,
     Float64IsNan,
     Float64Sqrt,
+    Float64Abs,
+    Float64RoundDown,
+    Float64RoundUp,
+    Float64RoundToZero,
 
     OptionIsNone,
     OptionIsSome,
     OptionUnwrap,
+
+    AtomicInt32Load,
+    AtomicInt32Store,
+    AtomicInt32Swap,
+    AtomicInt32CompareExchange,
+    AtomicInt32FetchAdd,
+
+    AtomicInt64Load,
+    AtomicInt64Store,
+    AtomicInt64Swap,
+    AtomicInt64CompareExchange,
+    AtomicInt64FetchAdd,
+
+    AtomicRefLoad,
+    AtomicRefStore,
+    AtomicRefSwap,
+    AtomicRefCompareExchange,
 }
 
 impl Intrinsic {
     pub fn emit_as_function(&self) -> bool {
         match self {
             Intrinsic::UnsafeKillRefs
+            | Intrinsic::UnsafeIsNull
+            | Intrinsic::WeakRefIsCleared
+            | Intrinsic::WeakRefKeepAlive
             | Intrinsic::Unreachable
             | Intrinsic::Int64CountZeroBits
             | Intrinsic::Int64CountZeroBitsLeading
@@ -536,6 +567,14 @@ impl Intrinsic {
             | Intrinsic::Int32CountOneBitsTrailing
             | Intrinsic::Float32Sqrt
             | Intrinsic::Float64Sqrt
+            | Intrinsic::Float32Abs
+            | Intrinsic::Float64Abs
+            | Intrinsic::Float32RoundDown
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float32RoundToZero
+            | Intrinsic::Float64RoundToZero
             | Intrinsic::PromoteFloat32ToFloat64
             | Intrinsic::DemoteFloat64ToFloat32
             | Intrinsic::BoolToInt32
@@ -554,6 +593,20 @@ impl Intrinsic {
             | Intrinsic::OptionIsNone
             | Intrinsic::OptionIsSome
             | Intrinsic::OptionUnwrap
+            | Intrinsic::AtomicInt32Load
+            | Intrinsic::AtomicInt32Store
+            | Intrinsic::AtomicInt32Swap
+            | Intrinsic::AtomicInt32CompareExchange
+            | Intrinsic::AtomicInt32FetchAdd
+            | Intrinsic::AtomicInt64Load
+            | Intrinsic::AtomicInt64Store
+            | Intrinsic::AtomicInt64Swap
+            | Intrinsic::AtomicInt64CompareExchange
+            | Intrinsic::AtomicInt64FetchAdd
+            | Intrinsic::AtomicRefLoad
+            | Intrinsic::AtomicRefStore
+            | Intrinsic::AtomicRefSwap
+            | Intrinsic::AtomicRefCompareExchange
             | Intrinsic::Debug => true,
             _ => false,
         }
@@ -637,6 +690,10 @@ impl Intrinsic {
             | Intrinsic::Int32ToFloat32
             | Intrinsic::Int64ToFloat32
             | Intrinsic::Float32Sqrt
+            | Intrinsic::Float32Abs
+            | Intrinsic::Float32RoundDown
+            | Intrinsic::Float32RoundUp
+            | Intrinsic::Float32RoundToZero
             | Intrinsic::DemoteFloat64ToFloat32 => BytecodeType::Float32,
             Intrinsic::Float64Add
             | Intrinsic::Float64Sub
@@ -648,6 +705,10 @@ impl Intrinsic {
             | Intrinsic::Int64ToFloat64
             | Intrinsic::ReinterpretInt64AsFloat64
             | Intrinsic::Float64Sqrt
+            | Intrinsic::Float64Abs
+            | Intrinsic::Float64RoundDown
+            | Intrinsic::Float64RoundUp
+            | Intrinsic::Float64RoundToZero
             | Intrinsic::PromoteFloat32ToFloat64 => BytecodeType::Float64,
             Intrinsic::BoolEq
             | Intrinsic::ByteEq
@@ -659,6 +720,8 @@ impl Intrinsic {
             | Intrinsic::Float32Eq
             | Intrinsic::Float64Eq
             | Intrinsic::BoolNot
+            | Intrinsic::UnsafeIsNull
+            | Intrinsic::WeakRefIsCleared
             | Intrinsic::Float64IsNan
             | Intrinsic::Float32IsNan => BytecodeType::Bool,
             Intrinsic::Int32ToByte | Intrinsic::Int64ToByte => BytecodeType::UInt8,
@@ -40,11 +40,18 @@ pub struct GlobalData {
     pub initializer: Option<FctId>,
     pub address_init: Address,
     pub address_value: Address,
+
+    // thread-local globals are stored in the thread-local area of each
+    // thread instead of at address_init and address_value
+    pub is_thread_local: bool,
+    pub offset_init: i32,
+    pub offset_value: i32,
 }
 
 impl GlobalData {
     pub fn needs_initialization(&self) -> bool {
-        self.initializer.is_some() && !self.is_initialized()
+        // a thread-local global might only be initialized for some threads
+        self.initializer.is_some() && (self.is_thread_local || !self.is_initialized())
     }
 
     pub fn name(&self, vm: &VM) -> String {
@@ -65,20 +72,27 @@ impl GrowableVec<RwLock<GlobalData>> {
 pub fn init_global_addresses(vm: &VM) {
     let globals = vm.globals.lock();
     let mut size = 0;
+    let mut thread_local_size = 0;
     let mut offsets = Vec::with_capacity(globals.len());
 
     for glob in globals.iter() {
         let glob = glob.read();
 
-        let initialized = size;
-        size += SourceType::Bool.size(vm);
+        let area_size = if glob.is_thread_local {
+            &mut thread_local_size
+        } else {
+            &mut size
+        };
+
+        let initialized = *area_size;
+        *area_size += SourceType::Bool.size(vm);
 
         let ty_size = glob.ty.size(vm);
         let ty_align = glob.ty.align(vm);
 
-        let value = mem::align_i32(size, ty_align);
+        let value = mem::align_i32(*area_size, ty_align);
         offsets.push((initialized, value));
-        size = value + ty_size;
+        *area_size = value + ty_size;
     }
 
     let ptr = vm.gc.alloc_perm(size as usize);
@@ -87,9 +101,16 @@ pub fn init_global_addresses(vm: &VM) {
         let mut glob = glob.write();
         let (initialized, value) = offsets[ind];
 
-        glob.address_init = ptr.offset(initialized as usize);
-        glob.address_value = ptr.offset(value as usize);
+        if glob.is_thread_local {
+            glob.offset_init = initialized;
+            glob.offset_value = value;
+        } else {
+            glob.address_init = ptr.offset(initialized as usize);
+            glob.address_value = ptr.offset(value as usize);
+        }
     }
+
+    vm.threads.set_thread_local_size(thread_local_size as usize);
 }
 
 pub fn global_accessible_from(vm: &VM, global_id: GlobalId, namespace_id: NamespaceId) -> bool {
@@ -37,6 +37,9 @@ pub struct KnownClasses {
     pub testing: Option<ClassId>,
     pub stacktrace: Option<ClassId>,
     pub stacktrace_element: Option<ClassId>,
+    pub weak_ref: Option<ClassId>,
+    pub pinned: Option<ClassId>,
+    pub atomic_ref: Option<ClassId>,
 }
 
 impl KnownClasses {
@@ -49,6 +52,9 @@ impl KnownClasses {
             testing: None,
             stacktrace: None,
             stacktrace_element: None,
+            weak_ref: None,
+            pinned: None,
+            atomic_ref: None,
         }
     }
 
@@ -106,6 +112,12 @@ pub struct KnownFunctions {
     pub string_buffer_empty: FctId,
     pub string_buffer_append: FctId,
     pub string_buffer_to_string: FctId,
+    pub format_uint8: FctId,
+    pub format_int32: FctId,
+    pub format_int64: FctId,
+    pub format_float32: FctId,
+    pub format_float64: FctId,
+    pub format_string: FctId,
 }
 
 impl KnownElements {
@@ -72,7 +72,8 @@ class TestExpectation
                 :code,
                 :message,
                 :stdout,
-                :stderr
+                :stderr,
+                :stderr_pattern
 
   def initialize(opts = {})
     fail = opts.fetch(:fail, false)
@@ -197,6 +198,9 @@ class TestCase
     return "stderr does not match (expected #{self.expectation.stderr.inspect} but got #{stderr.inspect})" if
       self.expectation.stderr && self.expectation.stderr != stderr
 
+    return "stderr does not match pattern (got #{stderr.inspect})" if
+      self.expectation.stderr_pattern && self.expectation.stderr_pattern !~ stderr
+
     true
   end
 end
@@ -459,7 +463,13 @@ def parse_test_file(file)
         end
 
       when "stderr"
-        test_case.expectation.stderr = arguments[1]
+        case arguments[1]
+        when "pattern"
+          pattern = IO.read(file.sub(".dora", ".stderr"))
+          test_case.expectation.stderr_pattern = Regexp.new("\\A(?:#{pattern})\\z")
+        else
+          test_case.expectation.stderr = arguments[1]
+        end
 
       when "args"
         test_case.args = arguments[1..-1].join(" ")
@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+
+# Generates dora/src/stdlib/unicode_tables.rs from the Unicode database
+# bundled with Python. Run from the repository root.
+
+import sys
+import unicodedata
+
+MAX_CODE_POINT = 0x10FFFF
+
+# Hangul syllables are decomposed and composed algorithmically.
+HANGUL_FIRST = 0xAC00
+HANGUL_LAST = 0xD7A3
+
+
+def is_hangul(cp):
+    return HANGUL_FIRST <= cp <= HANGUL_LAST
+
+
+def canonical_decomposition(ch):
+    decomposition = unicodedata.decomposition(ch)
+
+    if not decomposition or decomposition.startswith("<"):
+        return None
+
+    return [int(cp, 16) for cp in decomposition.split()]
+
+
+def combining_classes():
+    ranges = []
+
+    for cp in range(MAX_CODE_POINT + 1):
+        ccc = unicodedata.combining(chr(cp))
+
+        if ccc == 0:
+            continue
+
+        if ranges and ranges[-1][1] == cp - 1 and ranges[-1][2] == ccc:
+            ranges[-1][1] = cp
+        else:
+            ranges.append([cp, cp, ccc])
+
+    return ranges
+
+
+def decompositions():
+    result = []
+
+    for cp in range(MAX_CODE_POINT + 1):
+        if is_hangul(cp):
+            continue
+
+        decomposition = canonical_decomposition(chr(cp))
+
+        if decomposition:
+            result.append((cp, decomposition))
+
+    return result
+
+
+# Primary composites are the characters with a canonical decomposition into
+# two characters that aren't excluded from composition, NFC leaves exactly
+# those characters unchanged.
+def compositions(decompositions):
+    result = []
+
+    for cp, decomposition in decompositions:
+        if len(decomposition) != 2:
+            continue
+
+        if unicodedata.normalize("NFC", chr(cp)) != chr(cp):
+            continue
+
+        result.append((decomposition[0], decomposition[1], cp))
+
+    result.sort()
+    return result
+
+
+def char(cp):
+    return "'\\u{%x}'" % cp
+
+
+def main():
+    decomposition_table = decompositions()
+    out = sys.stdout
+
+    out.write("// Generated by tools/unicode-tables.py from Unicode %s, do not edit.\n\n"
+              % unicodedata.unidata_version)
+
+    out.write("// (first, last, class) for all characters with a non-zero canonical\n")
+    out.write("// combining class\n")
+    out.write("pub static COMBINING_CLASSES: &[(char, char, u8)] = &[\n")
+    for first, last, ccc in combining_classes():
+        out.write("    (%s, %s, %d),\n" % (char(first), char(last), ccc))
+    out.write("];\n\n")
+
+    out.write("// single step canonical decompositions, Hangul syllables excluded\n")
+    out.write("pub static DECOMPOSITIONS: &[(char, &[char])] = &[\n")
+    for cp, decomposition in decomposition_table:
+        out.write("    (%s, &[%s]),\n" % (char(cp), ", ".join(char(d) for d in decomposition)))
+    out.write("];\n\n")
+
+    out.write("// (first, second, primary composite), Hangul syllables excluded\n")
+    out.write("pub static COMPOSITIONS: &[(char, char, char)] = &[\n")
+    for first, second, cp in compositions(decomposition_table):
+        out.write("    (%s, %s, %s),\n" % (char(first), char(second), char(cp)))
+    out.write("];\n")
+
+
+if __name__ == "__main__":
+    main()